@@ -0,0 +1,175 @@
+//! Works out how the edges of tiles should be shaped so that the borders between different
+//! terrain types (such as coastlines) look smooth rather than blocky.
+//!
+//! A tile's corner is cut off (and drawn as the neighbouring terrain instead) when the tiles on
+//! both sides of the corner and the tile diagonally past it are all the same, different, type.
+
+use crate::position::{ChunkPos, TilePos};
+use crate::world::{Chunk, Tile, World};
+
+/// Which of a tile's neighbours are a different type to it. There is one bit per neighbour, going
+/// counterclockwise from east; see `NEIGHBOUR_OFFSETS`. Neighbours in chunks that haven't been
+/// generated yet are treated as being the same type.
+pub fn neighbour_mask(world: &World, coords: TilePos) -> u8 {
+    let Some(tile) = world.tile(coords) else {
+        return 0;
+    };
+
+    let mut mask = 0;
+    for (bit, &(dx, dy)) in NEIGHBOUR_OFFSETS.iter().enumerate() {
+        let neighbour = world.tile(coords + offset(dx, dy));
+        if neighbour.is_some_and(|neighbour| neighbour != tile) {
+            mask |= 1 << bit;
+        }
+    }
+    mask
+}
+
+/// For each of the tile's corners (top right, top left, bottom left, then bottom right), the type of
+/// tile that the corner should be drawn as, or None if the corner belongs to the tile itself.
+///
+/// Going counterclockwise like `NEIGHBOUR_OFFSETS`, corner `i` is past the diagonal neighbour
+/// `2 * i + 1`, between the neighbours `2 * i` and `2 * i + 2` (wrapping around).
+pub fn corner_tiles(world: &World, coords: TilePos) -> [Option<Tile>; 4] {
+    let mask = neighbour_mask(world, coords);
+    let neighbour = |index: usize| {
+        let (dx, dy) = NEIGHBOUR_OFFSETS[index];
+        world.tile(coords + offset(dx, dy))
+    };
+
+    std::array::from_fn(|corner| {
+        let neighbours = [
+            2 * corner,
+            2 * corner + 1,
+            (2 * corner + 2) % NEIGHBOUR_OFFSETS.len(),
+        ];
+        if !neighbours.iter().all(|&index| mask & (1 << index) != 0) {
+            return None;
+        }
+        let corner_tile = neighbour(neighbours[0])?;
+        neighbours[1..]
+            .iter()
+            .all(|&index| neighbour(index) == Some(corner_tile))
+            .then_some(corner_tile)
+    })
+}
+
+/// The revisions of a chunk and the eight chunks around it (None for chunks that haven't been
+/// generated). Since tiles are shaped by their neighbours, anything built from a chunk with
+/// autotiling is out of date when any of these change.
+pub fn neighbourhood_revisions(world: &World, chunk_coords: ChunkPos) -> [Option<u64>; 9] {
+    let mut revisions = [None; 9];
+    for (index, revision) in revisions.iter_mut().enumerate() {
+        // Chunks past the edge of the world are the edge chunk again, which doesn't matter here.
+        let neighbour_coords = chunk_coords
+            + ChunkPos {
+                x: index as i32 % 3 - 1,
+                y: index as i32 / 3 - 1,
+            };
+        *revision = world.chunks.get(&neighbour_coords).map(Chunk::revision);
+    }
+    revisions
+}
+
+/// The positions of a tile's neighbours relative to it, going counterclockwise from east.
+const NEIGHBOUR_OFFSETS: [(i32, i32); 8] = [
+    (1, 0),
+    (1, 1),
+    (0, 1),
+    (-1, 1),
+    (-1, 0),
+    (-1, -1),
+    (0, -1),
+    (1, -1),
+];
+
+/// The offset from a tile to one of its neighbours, for adding to the tile's coordinates.
+fn offset(dx: i32, dy: i32) -> TilePos {
+    TilePos {
+        x: dx.into(),
+        y: dy.into(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::world::TileData;
+
+    /// A world with one chunk, where every tile is grass apart from the given water tiles.
+    fn world_with_water(water: &[TilePos]) -> World {
+        let mut world = World::new();
+        world.generate_chunk(ChunkPos::default());
+        for coords in TilePos::rect(TilePos { x: 0, y: 0 }, TilePos { x: 31, y: 31 }) {
+            let tile = if water.contains(&coords) {
+                Tile::Water
+            } else {
+                Tile::Grass
+            };
+            world.set_tile_data(
+                coords,
+                TileData {
+                    tile,
+                    elevation: 0.0,
+                },
+            );
+        }
+        world
+    }
+
+    #[test]
+    fn corners_are_between_their_neighbours() {
+        let corners = [(1, 1), (-1, 1), (-1, -1), (1, -1)];
+        for (corner, &(dx, dy)) in corners.iter().enumerate() {
+            let sides = [
+                NEIGHBOUR_OFFSETS[2 * corner],
+                NEIGHBOUR_OFFSETS[(2 * corner + 2) % NEIGHBOUR_OFFSETS.len()],
+            ];
+            assert_eq!(NEIGHBOUR_OFFSETS[2 * corner + 1], (dx, dy));
+            assert!(sides.contains(&(dx, 0)) && sides.contains(&(0, dy)));
+        }
+    }
+
+    #[test]
+    fn a_corner_is_cut_off_when_all_three_tiles_past_it_match() {
+        let center = TilePos { x: 10, y: 10 };
+        let world = world_with_water(&[
+            TilePos { x: 9, y: 10 },
+            TilePos { x: 9, y: 9 },
+            TilePos { x: 10, y: 9 },
+        ]);
+
+        assert_eq!(neighbour_mask(&world, center), 0b0111_0000);
+        assert_eq!(
+            corner_tiles(&world, center),
+            [None, None, Some(Tile::Water), None]
+        );
+    }
+
+    #[test]
+    fn a_corner_is_kept_when_a_tile_past_it_differs() {
+        let center = TilePos { x: 10, y: 10 };
+        let world = world_with_water(&[TilePos { x: 11, y: 10 }, TilePos { x: 11, y: 11 }]);
+
+        assert_eq!(corner_tiles(&world, center), [None; 4]);
+    }
+
+    #[test]
+    fn neighbourhood_revisions_work_at_the_edges_of_the_world() {
+        let mut world = World::new();
+        for chunk_coords in [
+            ChunkPos {
+                x: i32::MAX,
+                y: i32::MAX,
+            },
+            ChunkPos {
+                x: i32::MIN,
+                y: i32::MIN,
+            },
+        ] {
+            world.generate_chunk(chunk_coords);
+            let revisions = neighbourhood_revisions(&world, chunk_coords);
+            assert_eq!(revisions[4], Some(world.chunks[&chunk_coords].revision()));
+        }
+    }
+}
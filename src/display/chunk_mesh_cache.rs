@@ -0,0 +1,111 @@
+use crate::display::autotile;
+use crate::display::tile_render_instance::TileRenderInstance;
+use crate::position::ChunkPos;
+use crate::world::World;
+use std::collections::{HashMap, HashSet};
+use wgpu::util::{BufferInitDescriptor, DeviceExt};
+use wgpu::{Buffer, Device, RenderPass};
+
+/// Keeps the tile instances for each chunk on the GPU so that they only have to be rebuilt and
+/// uploaded when the chunk changes, rather than every frame. Meshes are kept for each level of
+/// detail separately, so that viewports at different zooms don't keep rebuilding each other's.
+#[derive(Default)]
+pub struct ChunkMeshCache {
+    meshes: HashMap<(ChunkPos, u32), ChunkMesh>,
+    /// The chunks (and their levels of detail) that were visible the last time `update` was called,
+    /// in drawing order.
+    visible_chunks: Vec<(ChunkPos, u32)>,
+    /// The chunks that were visible in any viewport since the last call to `evict_unused`.
+    used_this_frame: HashSet<(ChunkPos, u32)>,
+}
+
+struct ChunkMesh {
+    buffer: Buffer,
+    /// The revisions of the chunk and its neighbours when the mesh was built, since the neighbours
+    /// affect the autotiling.
+    revisions: [Option<u64>; 9],
+    /// The number of instances for the tiles themselves, which come before the road instances.
+    tile_instance_count: u32,
+    instance_count: u32,
+}
+
+impl ChunkMeshCache {
+    /// Makes sure that every generated chunk between the given bottom left and top right chunks
+    /// has an up-to-date mesh at the given level of detail, and remembers them as the chunks to draw.
+    pub fn update(
+        &mut self,
+        device: &Device,
+        world: &World,
+        bottom_left_chunk_pos: ChunkPos,
+        top_right_chunk_pos: ChunkPos,
+        lod_level: u32,
+    ) {
+        self.visible_chunks.clear();
+
+        for chunk_coords in ChunkPos::rect(bottom_left_chunk_pos, top_right_chunk_pos) {
+            // Chunks are generated a few at a time, so the ones at the edges of the screen
+            // might not be ready yet.
+            let Some(chunk) = world.chunks.get(&chunk_coords) else {
+                continue;
+            };
+
+            let key = (chunk_coords, lod_level);
+            let revisions = autotile::neighbourhood_revisions(world, chunk_coords);
+            let is_up_to_date = self
+                .meshes
+                .get(&key)
+                .is_some_and(|mesh| mesh.revisions == revisions);
+            if !is_up_to_date {
+                let (instances, tile_instance_count) =
+                    TileRenderInstance::vec_from_chunk(world, chunk_coords, chunk, lod_level);
+                let buffer = device.create_buffer_init(&BufferInitDescriptor {
+                    label: Some("Chunk mesh buffer"),
+                    contents: bytemuck::cast_slice(&instances),
+                    usage: wgpu::BufferUsages::VERTEX,
+                });
+                self.meshes.insert(
+                    key,
+                    ChunkMesh {
+                        buffer,
+                        revisions,
+                        tile_instance_count: tile_instance_count as u32,
+                        instance_count: instances.len() as u32,
+                    },
+                );
+            }
+
+            self.visible_chunks.push(key);
+            self.used_this_frame.insert(key);
+        }
+    }
+
+    /// Throws away the meshes of chunks that no viewport could see this frame, once there are too
+    /// many of them. This is called once per frame after every viewport has been updated, so that
+    /// viewports looking at different places don't throw away each other's meshes.
+    pub fn evict_unused(&mut self) {
+        if self.meshes.len() > MAX_CACHED_MESHES {
+            self.meshes
+                .retain(|key, _| self.used_this_frame.contains(key));
+        }
+        self.used_this_frame.clear();
+    }
+
+    /// Draws the visible chunks. The render pass should already have the tile pipeline, vertex
+    /// buffer and index buffer set.
+    pub fn draw(&self, render_pass: &mut RenderPass, index_count: u32, show_roads: bool) {
+        for key in &self.visible_chunks {
+            let mesh = &self.meshes[key];
+            let instance_count = if show_roads {
+                mesh.instance_count
+            } else {
+                mesh.tile_instance_count
+            };
+
+            render_pass.set_vertex_buffer(1, mesh.buffer.slice(..));
+            render_pass.draw_indexed(0..index_count, 0, 0..instance_count);
+        }
+    }
+}
+
+/// The number of chunk meshes to keep before the ones that aren't visible are thrown away.
+const MAX_CACHED_MESHES: usize = 4096;
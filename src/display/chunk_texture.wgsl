@@ -0,0 +1,210 @@
+struct GlobalUniform {
+    window_size_px: vec2<f32>,
+    world_to_clip_scale: vec2<f32>,
+    world_to_clip_offset: vec2<f32>,
+    screen_to_world_scale: vec2<f32>,
+    screen_to_world_offset: vec2<f32>,
+    camera_zoom: f32,
+    time_s: f32,
+    sprites_enabled: u32,
+    // See shader.wgsl for why the padding looks like this.
+    padding: f32,
+    viewport_origin_px: vec2<f32>,
+    camera_chunk: vec2<i32>,
+    padding_2: vec2<f32>,
+};
+
+@group(0) @binding(0)
+var<uniform> global_uniform: GlobalUniform;
+
+struct Sprite {
+    uv_position: vec2<f32>,
+    uv_size: vec2<f32>,
+    frame_count: u32,
+    // 0 means that the sprite isn't animated.
+    frame_length: f32,
+    // Nonzero if the tile has a sprite rather than just a flat color.
+    is_present: u32,
+    padding: u32,
+    // The tile's flat color in linear rgb, whether or not it has a sprite.
+    color: vec4<f32>,
+};
+
+struct SpriteTable {
+    // Indexed by tile ID.
+    sprites: array<Sprite, 8>,
+};
+
+@group(0) @binding(1)
+var tile_atlas: texture_2d<f32>;
+@group(0) @binding(2)
+var tile_atlas_sampler: sampler;
+@group(0) @binding(3)
+var<uniform> sprite_table: SpriteTable;
+
+struct ChunkTextureUniform {
+    // The color of each tile type in linear rgb, indexed by tile ID, followed by the road color.
+    palette: array<vec4<f32>, 8>,
+    road_size: f32,
+    // Nonzero if roads should be drawn.
+    show_roads: u32,
+    padding: vec2<u32>,
+};
+
+@group(1) @binding(0)
+var<uniform> chunk_texture_uniform: ChunkTextureUniform;
+
+// The ID of each tile in the chunk, with the highest bit set if the tile has a road on it. At full
+// detail, there is a border one texel wide around the chunk holding the tiles of the neighbouring
+// chunks (or NO_TILE if they haven't been generated). When the chunk is drawn at a lower level of
+// detail, the texture is smaller, has no border, and each texel covers a square of tiles.
+@group(2) @binding(0)
+var chunk_tiles: texture_2d<u32>;
+
+struct ChunkInstanceInput {
+    @location(0) chunk: vec2<i32>,
+};
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    // The position within the chunk, in tiles.
+    @location(0) tile_coords: vec2<f32>,
+};
+
+const CHUNK_SIDE_SIZE: f32 = 32.0;
+const TILE_WIDTH: f32 = 128.0;
+const ROAD_FLAG: u32 = 128u;
+const ROAD_PALETTE_INDEX: u32 = 7u;
+const NO_TILE: u32 = 0x7fu;
+
+// Whether the tile with the given ID should be drawn with its sprite.
+fn uses_sprite(tile_id: u32) -> bool {
+    return global_uniform.sprites_enabled != 0u && tile_id < 8u && sprite_table.sprites[tile_id].is_present != 0u;
+}
+
+// Finds the texture coordinates in the tile atlas for a point in a tile, where (0, 0) is the top
+// left corner of the tile and (1, 1) is the bottom right.
+fn sprite_uv(tile_id: u32, uv_in_tile: vec2<f32>) -> vec2<f32> {
+    let sprite = sprite_table.sprites[tile_id];
+    var frame = 0u;
+    if sprite.frame_length > 0.0 {
+        frame = u32(global_uniform.time_s / sprite.frame_length) % sprite.frame_count;
+    }
+    // Stay just inside the sprite so that the neighbouring sprite doesn't bleed in at the edges.
+    let clamped_uv = clamp(uv_in_tile, vec2<f32>(0.0), vec2<f32>(0.999));
+    return sprite.uv_position + vec2<f32>(f32(frame) * sprite.uv_size.x, 0.0) + clamped_uv * sprite.uv_size;
+}
+
+// Finds the color of a point in a tile with the given ID, from its sprite or its flat color.
+fn tile_color(tile_id: u32, uv_in_tile: vec2<f32>) -> vec3<f32> {
+    if uses_sprite(tile_id) {
+        return textureSampleLevel(tile_atlas, tile_atlas_sampler, sprite_uv(tile_id, uv_in_tile), 0.0).rgb;
+    }
+    return sprite_table.sprites[tile_id].color.rgb;
+}
+
+// Whether a point is in the rounded off part of a tile's corner, for drawing it as the neighbouring
+// terrain. The point is relative to the center of the tile.
+fn is_in_cut_corner(offset_from_center: vec2<f32>) -> bool {
+    return length(offset_from_center) > 0.5;
+}
+
+@vertex
+fn vert_main(
+    @builtin(vertex_index) vertex_index: u32,
+    instance: ChunkInstanceInput,
+) -> VertexOutput {
+    // Two triangles making up a square, with (0, 0) at the bottom left corner.
+    var corners = array<vec2<f32>, 6>(
+        vec2<f32>(0.0, 0.0),
+        vec2<f32>(1.0, 0.0),
+        vec2<f32>(1.0, 1.0),
+        vec2<f32>(0.0, 0.0),
+        vec2<f32>(1.0, 1.0),
+        vec2<f32>(0.0, 1.0),
+    );
+    let corner = corners[vertex_index];
+
+    var out: VertexOutput;
+    out.tile_coords = corner * CHUNK_SIDE_SIZE;
+
+    // See shader.wgsl for why the chunks are subtracted first.
+    let chunks_from_camera = vec2<f32>(instance.chunk - global_uniform.camera_chunk);
+    let world_pos = (chunks_from_camera + corner) * CHUNK_SIDE_SIZE * TILE_WIDTH;
+    out.clip_position = vec4<f32>(
+        world_pos * global_uniform.world_to_clip_scale + global_uniform.world_to_clip_offset,
+        0.0,
+        1.0,
+    );
+
+    return out;
+}
+
+// Finds the ID of the tile at the given coordinates within a full detail chunk texture, without the
+// road flag. The coordinates can go one tile past the edges of the chunk, into the border.
+fn neighbour_tile_id(tile: vec2<i32>) -> u32 {
+    return textureLoad(chunk_tiles, tile + 1, 0).r & ~ROAD_FLAG;
+}
+
+// Finds the ID of the tile that a point should be drawn as if it's in the rounded off corner of a
+// full detail tile, or NO_TILE if it isn't. This matches the rules in autotile.rs.
+fn corner_tile_id(tile: vec2<i32>, tile_id: u32, offset_from_center: vec2<f32>) -> u32 {
+    if !is_in_cut_corner(offset_from_center) {
+        return NO_TILE;
+    }
+    let direction = vec2<i32>(select(vec2<f32>(-1.0), vec2<f32>(1.0), offset_from_center >= vec2<f32>(0.0)));
+    let corner_tile = neighbour_tile_id(tile + vec2<i32>(direction.x, 0));
+    if corner_tile == NO_TILE
+        || corner_tile == tile_id
+        || neighbour_tile_id(tile + vec2<i32>(0, direction.y)) != corner_tile
+        || neighbour_tile_id(tile + direction) != corner_tile {
+        return NO_TILE;
+    }
+    return corner_tile;
+}
+
+// Finds the color of a point in a full detail tile, from its sprite or the palette.
+fn full_detail_color(tile_id: u32, tile_coords: vec2<f32>) -> vec4<f32> {
+    if uses_sprite(tile_id) {
+        // The y axis points up in the world but down in the atlas.
+        let uv_in_tile = vec2<f32>(fract(tile_coords.x), 1.0 - fract(tile_coords.y));
+        return vec4<f32>(tile_color(tile_id, uv_in_tile), 1.0);
+    }
+    return vec4<f32>(chunk_texture_uniform.palette[tile_id].rgb, 1.0);
+}
+
+@fragment
+fn frag_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let texture_size = vec2<i32>(textureDimensions(chunk_tiles));
+    let is_full_detail = texture_size.x == i32(CHUNK_SIDE_SIZE) + 2;
+    var texel: u32;
+    var tile = vec2<i32>(0);
+    if is_full_detail {
+        tile = clamp(vec2<i32>(floor(in.tile_coords)), vec2<i32>(0), vec2<i32>(i32(CHUNK_SIDE_SIZE) - 1));
+        texel = textureLoad(chunk_tiles, tile + 1, 0).r;
+    } else {
+        let texel_coords = in.tile_coords / CHUNK_SIDE_SIZE * vec2<f32>(texture_size);
+        texel = textureLoad(chunk_tiles, clamp(vec2<i32>(floor(texel_coords)), vec2<i32>(0), texture_size - 1), 0).r;
+    }
+
+    // Roads are squares in the middle of their tiles.
+    let offset_from_tile_center = abs(fract(in.tile_coords) - 0.5);
+    let is_road = (texel & ROAD_FLAG) != 0u
+        && chunk_texture_uniform.show_roads != 0u
+        && all(offset_from_tile_center < vec2<f32>(chunk_texture_uniform.road_size / 2.0));
+
+    if is_road {
+        return vec4<f32>(chunk_texture_uniform.palette[ROAD_PALETTE_INDEX].rgb, 1.0);
+    }
+
+    let tile_id = texel & ~ROAD_FLAG;
+    // Sprites and smooth borders are only drawn at full detail, when each texel is a single tile.
+    if is_full_detail {
+        let corner_tile = corner_tile_id(tile, tile_id, fract(in.tile_coords) - 0.5);
+        if corner_tile != NO_TILE {
+            return full_detail_color(corner_tile, in.tile_coords);
+        }
+        return full_detail_color(tile_id, in.tile_coords);
+    }
+    return vec4<f32>(chunk_texture_uniform.palette[tile_id].rgb, 1.0);
+}
@@ -0,0 +1,395 @@
+use crate::display::autotile;
+use crate::display::get_linear_rgb;
+use crate::display::instance_buffer::InstanceBuffer;
+use crate::display::tile_render_instance::{ROAD_COLOR, ROAD_SIZE};
+use crate::position::{ChunkPos, TilePos};
+use crate::world::{Chunk, Tile, World};
+use std::collections::{HashMap, HashSet};
+use wgpu::util::{BufferInitDescriptor, DeviceExt};
+use wgpu::{
+    BindGroup, BindGroupLayout, Buffer, BufferAddress, Device, Queue, RenderPass, RenderPipeline,
+    Texture, TextureFormat, VertexAttribute, VertexBufferLayout, VertexFormat, VertexStepMode,
+};
+
+/// An alternative to drawing every tile as its own instance. Each chunk's tile IDs are uploaded
+/// into a small texture, and the chunk is drawn as a single square that looks up the color of each
+/// tile in a palette.
+pub struct ChunkTextureRenderer {
+    pipeline: RenderPipeline,
+    uniform: ChunkTextureUniform,
+    uniform_buffer: Buffer,
+    uniform_bind_group: BindGroup,
+    texture_bind_group_layout: BindGroupLayout,
+    /// The textures for each chunk and level of detail.
+    textures: HashMap<(ChunkPos, u32), ChunkTexture>,
+    /// The chunks (and their levels of detail) that were visible the last time `prepare` was
+    /// called, in drawing order.
+    visible_chunks: Vec<(ChunkPos, u32)>,
+    /// The positions of the visible chunks, in the same order as `visible_chunks`.
+    instance_buffer: InstanceBuffer,
+    /// The chunks that were visible in any viewport since the last call to `evict_unused`.
+    used_this_frame: HashSet<(ChunkPos, u32)>,
+}
+
+struct ChunkTexture {
+    texture: Texture,
+    bind_group: BindGroup,
+    /// The revisions of the chunk and its neighbours when the texture was last filled, since the
+    /// border of the texture holds the neighbours' tiles.
+    revisions: [Option<u64>; 9],
+    /// The level of detail that the texture was created for. Higher levels have smaller textures.
+    lod_level: u32,
+}
+
+impl ChunkTextureRenderer {
+    pub fn new(
+        device: &Device,
+        surface_format: TextureFormat,
+        global_uniform_layout: &BindGroupLayout,
+    ) -> Self {
+        let uniform = ChunkTextureUniform::new();
+        let uniform_buffer = device.create_buffer_init(&BufferInitDescriptor {
+            label: Some("Chunk texture uniform buffer"),
+            contents: bytemuck::cast_slice(&[uniform]),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+        let uniform_bind_group_layout =
+            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                label: Some("Chunk texture uniform bind group layout"),
+                entries: &[wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                }],
+            });
+        let uniform_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Chunk texture uniform bind group"),
+            layout: &uniform_bind_group_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: uniform_buffer.as_entire_binding(),
+            }],
+        });
+
+        let texture_bind_group_layout =
+            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                label: Some("Chunk texture bind group layout"),
+                entries: &[wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Uint,
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                }],
+            });
+
+        let shader = device.create_shader_module(wgpu::include_wgsl!("chunk_texture.wgsl"));
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Chunk texture pipeline layout"),
+            bind_group_layouts: &[
+                global_uniform_layout,
+                &uniform_bind_group_layout,
+                &texture_bind_group_layout,
+            ],
+            push_constant_ranges: &[],
+        });
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Chunk texture pipeline"),
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: "vert_main",
+                buffers: &[ChunkRenderInstance::layout()],
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: "frag_main",
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: surface_format,
+                    blend: Some(wgpu::BlendState::REPLACE),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+            }),
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::TriangleList,
+                cull_mode: Some(wgpu::Face::Back),
+                ..Default::default()
+            },
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+            cache: None,
+        });
+
+        ChunkTextureRenderer {
+            pipeline,
+            uniform,
+            uniform_buffer,
+            uniform_bind_group,
+            texture_bind_group_layout,
+            textures: HashMap::new(),
+            visible_chunks: vec![],
+            instance_buffer: InstanceBuffer::new(device),
+            used_this_frame: HashSet::new(),
+        }
+    }
+
+    /// Makes sure that every generated chunk between the given bottom left and top right chunks
+    /// has an up-to-date texture at the given level of detail, and remembers them as the chunks to
+    /// draw.
+    pub fn prepare(
+        &mut self,
+        device: &Device,
+        queue: &Queue,
+        world: &World,
+        [bottom_left_chunk_pos, top_right_chunk_pos]: [ChunkPos; 2],
+        show_roads: bool,
+        lod_level: u32,
+    ) {
+        if (self.uniform.show_roads != 0) != show_roads {
+            self.uniform.show_roads = u32::from(show_roads);
+            queue.write_buffer(
+                &self.uniform_buffer,
+                0,
+                bytemuck::cast_slice(&[self.uniform]),
+            );
+        }
+
+        self.visible_chunks.clear();
+        let mut instances = vec![];
+
+        for chunk_coords in ChunkPos::rect(bottom_left_chunk_pos, top_right_chunk_pos) {
+            // Chunks are generated a few at a time, so the ones at the edges of the screen
+            // might not be ready yet.
+            let Some(chunk) = world.chunks.get(&chunk_coords) else {
+                continue;
+            };
+
+            let key = (chunk_coords, lod_level);
+            let chunk_texture = self.textures.entry(key).or_insert_with(|| {
+                ChunkTexture::new(device, &self.texture_bind_group_layout, lod_level)
+            });
+            let revisions = autotile::neighbourhood_revisions(world, chunk_coords);
+            if chunk_texture.revisions != revisions {
+                chunk_texture.fill(queue, world, chunk_coords, chunk);
+                chunk_texture.revisions = revisions;
+            }
+
+            instances.push(ChunkRenderInstance {
+                chunk: [chunk_coords.x, chunk_coords.y],
+            });
+            self.visible_chunks.push(key);
+            self.used_this_frame.insert(key);
+        }
+
+        self.instance_buffer
+            .write_data(queue, device, bytemuck::cast_slice(&instances));
+    }
+
+    /// Throws away the textures of chunks that no viewport could see this frame, once there are too
+    /// many of them. This is called once per frame after every viewport has been prepared, so that
+    /// viewports looking at different places don't throw away each other's textures.
+    pub fn evict_unused(&mut self) {
+        if self.textures.len() > MAX_CACHED_TEXTURES {
+            self.textures
+                .retain(|key, _| self.used_this_frame.contains(key));
+        }
+        self.used_this_frame.clear();
+    }
+
+    /// Draws the chunks from the last call to `prepare`. The global uniform bind group must already
+    /// be set at index 0.
+    pub fn draw(&self, render_pass: &mut RenderPass) {
+        render_pass.set_pipeline(&self.pipeline);
+        render_pass.set_bind_group(1, &self.uniform_bind_group, &[]);
+        render_pass.set_vertex_buffer(0, self.instance_buffer.buffer.slice(..));
+
+        for (index, key) in self.visible_chunks.iter().enumerate() {
+            let index = index as u32;
+            render_pass.set_bind_group(2, &self.textures[key].bind_group, &[]);
+            render_pass.draw(0..6, index..index + 1);
+        }
+    }
+}
+
+impl ChunkTexture {
+    fn new(device: &Device, layout: &BindGroupLayout, lod_level: u32) -> Self {
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Chunk texture"),
+            size: Self::size(lod_level),
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: TextureFormat::R8Uint,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+            view_formats: &[],
+        });
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Chunk texture bind group"),
+            layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: wgpu::BindingResource::TextureView(&view),
+            }],
+        });
+
+        ChunkTexture {
+            texture,
+            bind_group,
+            // No chunk will ever get this far, so this makes sure that the texture gets filled.
+            revisions: [Some(u64::MAX); 9],
+            lod_level,
+        }
+    }
+
+    /// At full detail, the texture has a border one tile wide holding the tiles of the neighbouring
+    /// chunks, so that the shader can shape the borders between terrain types.
+    fn size(lod_level: u32) -> wgpu::Extent3d {
+        let side_size = if lod_level == 0 {
+            Chunk::side_size() as u32 + 2
+        } else {
+            Chunk::side_size() as u32 >> lod_level
+        };
+        wgpu::Extent3d {
+            width: side_size,
+            height: side_size,
+            depth_or_array_layers: 1,
+        }
+    }
+
+    /// Uploads the tiles of the chunk (and the border around it) into the texture. Above LOD level
+    /// 0, the chunk's LOD summary is uploaded instead, without any roads or border.
+    fn fill(&mut self, queue: &Queue, world: &World, chunk_coords: ChunkPos, chunk: &Chunk) {
+        let size = Self::size(self.lod_level);
+        let side_size = size.width as usize;
+        let mut texels = vec![0; side_size * side_size];
+        if self.lod_level == 0 {
+            let bottom_left_tile = chunk_coords.bottom_left_tile();
+            for x in 0..side_size {
+                for y in 0..side_size {
+                    // The texture has a border of tiles from the neighbouring chunks.
+                    let coords = bottom_left_tile
+                        + TilePos {
+                            x: x as i64 - 1,
+                            y: y as i64 - 1,
+                        };
+                    let is_in_chunk = (1..=Chunk::side_size() as usize).contains(&x)
+                        && (1..=Chunk::side_size() as usize).contains(&y);
+                    texels[y * side_size + x] = if is_in_chunk {
+                        let road_flag = if chunk.roads[x - 1][y - 1] {
+                            ROAD_FLAG
+                        } else {
+                            0
+                        };
+                        chunk.tiles[x - 1][y - 1].id() | road_flag
+                    } else {
+                        world.tile(coords).map_or(NO_TILE, Tile::id)
+                    };
+                }
+            }
+        } else {
+            let summary = chunk.lod_summary(self.lod_level);
+            for x in 0..side_size {
+                for y in 0..side_size {
+                    texels[y * side_size + x] = summary.tile(x, y).id();
+                }
+            }
+        }
+
+        queue.write_texture(
+            wgpu::ImageCopyTexture {
+                texture: &self.texture,
+                mip_level: 0,
+                origin: wgpu::Origin3d::ZERO,
+                aspect: wgpu::TextureAspect::All,
+            },
+            &texels,
+            wgpu::ImageDataLayout {
+                offset: 0,
+                bytes_per_row: Some(size.width),
+                rows_per_image: Some(size.height),
+            },
+            size,
+        );
+    }
+}
+
+/// The data shared by every chunk drawn with the chunk texture pipeline.
+#[repr(C)]
+#[derive(Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+struct ChunkTextureUniform {
+    /// The color of each tile type in linear rgba, indexed by `Tile::id`. The last entry is the
+    /// road color.
+    palette: [[f32; 4]; PALETTE_SIZE],
+    /// The width of a road as a fraction of the width of a tile.
+    road_size: f32,
+    /// 1 if roads should be drawn, 0 otherwise.
+    show_roads: u32,
+    // WASM requires buffer bindings to be 16-bit aligned, so we need to add some padding.
+    padding: [u32; 2],
+}
+
+impl ChunkTextureUniform {
+    fn new() -> Self {
+        let mut palette = [[0.0; 4]; PALETTE_SIZE];
+        for (id, color) in palette.iter_mut().enumerate() {
+            if let Some(tile) = Tile::from_id(id as u8) {
+                let [r, g, b] = get_linear_rgb(tile.color());
+                *color = [r, g, b, 1.0];
+            }
+        }
+        let [r, g, b] = get_linear_rgb(ROAD_COLOR);
+        palette[PALETTE_SIZE - 1] = [r, g, b, 1.0];
+
+        ChunkTextureUniform {
+            palette,
+            road_size: ROAD_SIZE,
+            show_roads: 1,
+            padding: [0; 2],
+        }
+    }
+}
+
+/// The position of a chunk drawn as a single square, by the chunk texture or overlay pipelines.
+#[repr(C)]
+#[derive(Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct ChunkRenderInstance {
+    /// The coordinates of the chunk. These are integers so that the shaders can work out how far
+    /// away the chunk is from the camera exactly.
+    pub chunk: [i32; 2],
+}
+
+impl ChunkRenderInstance {
+    pub fn layout() -> VertexBufferLayout<'static> {
+        VertexBufferLayout {
+            array_stride: size_of::<ChunkRenderInstance>() as BufferAddress,
+            step_mode: VertexStepMode::Instance,
+            attributes: &[VertexAttribute {
+                offset: 0,
+                shader_location: 0,
+                format: VertexFormat::Sint32x2,
+            }],
+        }
+    }
+}
+
+/// The number of entries in the palette. This must match the array length in `chunk_texture.wgsl`,
+/// and be more than the highest tile ID.
+const PALETTE_SIZE: usize = 8;
+/// Set in a chunk texture's texels for tiles that have a road on them.
+const ROAD_FLAG: u8 = 0x80;
+/// Used in the border of a chunk texture for tiles in chunks that haven't been generated yet.
+const NO_TILE: u8 = 0x7f;
+/// The number of chunk textures to keep before the ones that aren't visible are thrown away.
+const MAX_CACHED_TEXTURES: usize = 4096;
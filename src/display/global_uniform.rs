@@ -1,62 +1,121 @@
-use wgpu::util::{BufferInitDescriptor, DeviceExt};
-use wgpu::{BufferSize, Device};
+use crate::display::tile_atlas::TileAtlas;
+use wgpu::{BufferAddress, BufferSize, Device};
 
+/// The uniform data and resources shared by every pipeline. As well as the uniform itself, the
+/// bind group holds the tile atlas (binding 1), its sampler (binding 2) and its sprite table
+/// (binding 3).
+///
+/// The uniform buffer has a slot for each viewport and one for the HUD, since they each need their
+/// own camera and size. The slot is chosen with a dynamic offset when the bind group is set, using
+/// `offset`.
 pub struct GlobalUniform {
-    data: GlobalUniformData,
     buffer: wgpu::Buffer,
+    /// The distance between the starts of two slots, which has to be a multiple of the device's
+    /// uniform offset alignment.
+    slot_stride: BufferAddress,
     pub bind_group: wgpu::BindGroup,
     pub bind_group_layout: wgpu::BindGroupLayout,
 }
 
 impl GlobalUniform {
-    pub fn new(device: &Device, data: GlobalUniformData) -> Self {
-        let buffer = device.create_buffer_init(&BufferInitDescriptor {
+    pub fn new(device: &Device, slot_count: usize, tile_atlas: &TileAtlas) -> Self {
+        let data_size = size_of::<GlobalUniformData>() as BufferAddress;
+        let slot_stride = data_size.next_multiple_of(BufferAddress::from(
+            device.limits().min_uniform_buffer_offset_alignment,
+        ));
+        let buffer = device.create_buffer(&wgpu::BufferDescriptor {
             label: Some("Global uniform buffer"),
-            contents: bytemuck::cast_slice(&[data]),
+            size: slot_stride * slot_count as BufferAddress,
             usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
         });
 
         let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
-            entries: &[wgpu::BindGroupLayoutEntry {
-                binding: 0,
-                visibility: wgpu::ShaderStages::VERTEX,
-                ty: wgpu::BindingType::Buffer {
-                    ty: wgpu::BufferBindingType::Uniform,
-                    has_dynamic_offset: false,
-                    min_binding_size: None,
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::VERTEX_FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: true,
+                        min_binding_size: BufferSize::new(data_size),
+                    },
+                    count: None,
                 },
-                count: None,
-            }],
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 3,
+                    visibility: wgpu::ShaderStages::VERTEX_FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+            ],
             label: Some("Global uniform bind group layout"),
         });
 
         let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
             layout: &bind_group_layout,
-            entries: &[wgpu::BindGroupEntry {
-                binding: 0,
-                resource: buffer.as_entire_binding(),
-            }],
+            entries: &[
+                // Only one slot is visible at a time, at the dynamic offset.
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::Buffer(wgpu::BufferBinding {
+                        buffer: &buffer,
+                        offset: 0,
+                        size: BufferSize::new(data_size),
+                    }),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::TextureView(&tile_atlas.view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: wgpu::BindingResource::Sampler(&tile_atlas.sampler),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 3,
+                    resource: tile_atlas.sprite_table_buffer.as_entire_binding(),
+                },
+            ],
             label: Some("Global uniform bind group"),
         });
 
         GlobalUniform {
-            data,
             buffer,
+            slot_stride,
             bind_group,
             bind_group_layout,
         }
     }
 
-    pub fn write_data(&mut self, queue: &wgpu::Queue, data: GlobalUniformData) {
-        self.data = data;
-
+    pub fn write_data(&self, queue: &wgpu::Queue, slot: usize, data: GlobalUniformData) {
         let data = [data];
         let raw_data: &[u8] = bytemuck::cast_slice(&data);
 
         let mut staging_buffer = queue
             .write_buffer_with(
                 &self.buffer,
-                0,
+                BufferAddress::from(self.offset(slot)),
                 BufferSize::try_from(raw_data.len() as u64)
                     .expect("the global uniform should contain data"),
             )
@@ -64,28 +123,56 @@ impl GlobalUniform {
         staging_buffer.copy_from_slice(raw_data);
     }
 
-    pub fn data(&self) -> &GlobalUniformData {
-        &self.data
+    /// The dynamic offset to set the bind group with to use a slot.
+    pub fn offset(&self, slot: usize) -> u32 {
+        (self.slot_stride * slot as BufferAddress) as u32
     }
 }
 
 #[repr(C)]
 #[derive(Debug, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
 pub struct GlobalUniformData {
+    /// The size of the area being drawn to, which is the whole window for the HUD but only part of
+    /// it for a viewport.
     pub window_size_px: [f32; 2],
-    pub camera_pos: [f32; 2],
+    /// The camera's `ViewTransform::world_to_clip`. The shaders work out positions relative to the
+    /// camera's chunk (`camera_chunk`), so that f32s are only needed for things near the camera.
+    pub world_to_clip_scale: [f32; 2],
+    pub world_to_clip_offset: [f32; 2],
+    /// The camera's `ViewTransform::screen_to_relative_world`, for positions relative to the top
+    /// left corner of the area being drawn to.
+    pub screen_to_world_scale: [f32; 2],
+    pub screen_to_world_offset: [f32; 2],
     pub camera_zoom: f32,
+    /// The number of seconds since the program started, for animations.
+    pub time_s: f32,
+    /// 1 if tiles should be drawn with their sprites, or 0 if they should be flat colors.
+    pub sprites_enabled: u32,
     // WASM requires buffer bindings to be 16-bit aligned, so we need to add some padding.
-    pub padding: [f32; 3],
+    pub padding: f32,
+    /// The top left corner of the area being drawn to, in pixels from the top left corner of the
+    /// window.
+    pub viewport_origin_px: [f32; 2],
+    pub camera_chunk: [i32; 2],
+    // Rounds the size up to a multiple of 16 bytes, for the same reason as `padding`.
+    pub padding_2: [f32; 2],
 }
 
 impl Default for GlobalUniformData {
     fn default() -> Self {
         GlobalUniformData {
             window_size_px: [1.0, 1.0],
-            camera_pos: [0.0, 0.0],
+            world_to_clip_scale: [1.0, 1.0],
+            world_to_clip_offset: [0.0, 0.0],
+            screen_to_world_scale: [1.0, 1.0],
+            screen_to_world_offset: [0.0, 0.0],
             camera_zoom: 1.0,
-            padding: [0.0; 3],
+            time_s: 0.0,
+            sprites_enabled: 1,
+            padding: 0.0,
+            viewport_origin_px: [0.0, 0.0],
+            camera_chunk: [0, 0],
+            padding_2: [0.0; 2],
         }
     }
 }
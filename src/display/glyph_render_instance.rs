@@ -0,0 +1,53 @@
+use wgpu::{BufferAddress, VertexAttribute, VertexBufferLayout, VertexFormat, VertexStepMode};
+
+/// A rectangle drawn on top of the world in screen space, textured with part of the font atlas.
+#[repr(C)]
+#[derive(Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct GlyphRenderInstance {
+    /// The position of the top left corner of the rectangle, in pixels from the top left corner of the window.
+    pub position: [f32; 2],
+    /// The width and height of the rectangle in pixels.
+    pub size: [f32; 2],
+    /// The texture coordinates of the top left corner of the glyph in the font atlas.
+    pub uv_position: [f32; 2],
+    /// The width and height of the glyph in the font atlas, in texture coordinates.
+    pub uv_size: [f32; 2],
+    /// The color of the rectangle in linear rgba.
+    pub color: [f32; 4],
+}
+
+impl GlyphRenderInstance {
+    pub fn layout() -> VertexBufferLayout<'static> {
+        VertexBufferLayout {
+            array_stride: size_of::<GlyphRenderInstance>() as BufferAddress,
+            step_mode: VertexStepMode::Instance,
+            attributes: &[
+                VertexAttribute {
+                    offset: 0,
+                    shader_location: 0,
+                    format: VertexFormat::Float32x2,
+                },
+                VertexAttribute {
+                    offset: size_of::<[f32; 2]>() as BufferAddress,
+                    shader_location: 1,
+                    format: VertexFormat::Float32x2,
+                },
+                VertexAttribute {
+                    offset: size_of::<[f32; 4]>() as BufferAddress,
+                    shader_location: 2,
+                    format: VertexFormat::Float32x2,
+                },
+                VertexAttribute {
+                    offset: size_of::<[f32; 6]>() as BufferAddress,
+                    shader_location: 3,
+                    format: VertexFormat::Float32x2,
+                },
+                VertexAttribute {
+                    offset: size_of::<[f32; 8]>() as BufferAddress,
+                    shader_location: 4,
+                    format: VertexFormat::Float32x4,
+                },
+            ],
+        }
+    }
+}
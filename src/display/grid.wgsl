@@ -0,0 +1,79 @@
+struct GlobalUniform {
+    window_size_px: vec2<f32>,
+    world_to_clip_scale: vec2<f32>,
+    world_to_clip_offset: vec2<f32>,
+    screen_to_world_scale: vec2<f32>,
+    screen_to_world_offset: vec2<f32>,
+    camera_zoom: f32,
+    time_s: f32,
+    sprites_enabled: u32,
+    // See shader.wgsl for why the padding looks like this.
+    padding: f32,
+    viewport_origin_px: vec2<f32>,
+    camera_chunk: vec2<i32>,
+    padding_2: vec2<f32>,
+};
+
+@group(0) @binding(0)
+var<uniform> global_uniform: GlobalUniform;
+
+struct GridUniform {
+    // Nonzero if the lines between tiles should be drawn.
+    show_tile_lines: u32,
+    // Nonzero if the lines between chunks should be drawn.
+    show_chunk_lines: u32,
+    padding: vec2<u32>,
+};
+
+@group(1) @binding(0)
+var<uniform> grid_uniform: GridUniform;
+
+const TILE_WIDTH: f32 = 128.0;
+const CHUNK_SIDE_SIZE: f32 = 32.0;
+// Line widths are in pixels on the screen, so they stay the same at every zoom level.
+const TILE_LINE_WIDTH_PX: f32 = 1.5;
+const CHUNK_LINE_WIDTH_PX: f32 = 3.0;
+// Tile lines are hidden when the tiles get smaller than this on the screen, since they would cover
+// everything else.
+const MIN_TILE_LINE_SPACING_PX: f32 = 8.0;
+const TILE_LINE_COLOR: vec4<f32> = vec4<f32>(0.0, 0.0, 0.0, 0.35);
+const CHUNK_LINE_COLOR: vec4<f32> = vec4<f32>(1.0, 0.1, 0.6, 0.9);
+
+@vertex
+fn vert_main(@builtin(vertex_index) vertex_index: u32) -> @builtin(position) vec4<f32> {
+    // A single triangle that covers the whole screen.
+    var corners = array<vec2<f32>, 3>(
+        vec2<f32>(-1.0, -1.0),
+        vec2<f32>(3.0, -1.0),
+        vec2<f32>(-1.0, 3.0),
+    );
+    return vec4<f32>(corners[vertex_index], 0.0, 1.0);
+}
+
+// The distance (in pixels on the screen) from a point to the nearest line of a grid whose lines are
+// `spacing` pixels apart in the world.
+fn distance_to_line_px(world_pos: vec2<f32>, spacing: f32) -> f32 {
+    let offset = abs(world_pos / spacing - round(world_pos / spacing)) * spacing;
+    return min(offset.x, offset.y) * global_uniform.camera_zoom;
+}
+
+@fragment
+fn frag_main(@builtin(position) screen_pos: vec4<f32>) -> @location(0) vec4<f32> {
+    // The fragment position is relative to the window rather than the viewport. The position in
+    // the world is relative to the bottom left corner of the camera's chunk, which is on both
+    // grids, so the lines still line up.
+    let viewport_pos = screen_pos.xy - global_uniform.viewport_origin_px;
+    let world_pos = viewport_pos * global_uniform.screen_to_world_scale
+        + global_uniform.screen_to_world_offset;
+
+    if grid_uniform.show_chunk_lines != 0u
+        && distance_to_line_px(world_pos, TILE_WIDTH * CHUNK_SIDE_SIZE) < CHUNK_LINE_WIDTH_PX / 2.0 {
+        return CHUNK_LINE_COLOR;
+    }
+    if grid_uniform.show_tile_lines != 0u
+        && TILE_WIDTH * global_uniform.camera_zoom >= MIN_TILE_LINE_SPACING_PX
+        && distance_to_line_px(world_pos, TILE_WIDTH) < TILE_LINE_WIDTH_PX / 2.0 {
+        return TILE_LINE_COLOR;
+    }
+    discard;
+}
@@ -0,0 +1,132 @@
+use wgpu::util::{BufferInitDescriptor, DeviceExt};
+use wgpu::{BindGroup, BindGroupLayout, Buffer, Device, Queue, RenderPass, RenderPipeline};
+
+/// Draws lines along the edges of the tiles and chunks. Rather than building geometry for every
+/// line, a single triangle covers the screen and the shader works out whether each pixel is on a
+/// line, which keeps the lines the same width in pixels at any zoom.
+pub struct GridRenderer {
+    pipeline: RenderPipeline,
+    uniform: GridUniform,
+    uniform_buffer: Buffer,
+    uniform_bind_group: BindGroup,
+}
+
+impl GridRenderer {
+    pub fn new(
+        device: &Device,
+        surface_format: wgpu::TextureFormat,
+        global_uniform_layout: &BindGroupLayout,
+    ) -> Self {
+        let uniform = GridUniform::default();
+        let uniform_buffer = device.create_buffer_init(&BufferInitDescriptor {
+            label: Some("Grid uniform buffer"),
+            contents: bytemuck::cast_slice(&[uniform]),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+        let uniform_bind_group_layout =
+            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                label: Some("Grid uniform bind group layout"),
+                entries: &[wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                }],
+            });
+        let uniform_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Grid uniform bind group"),
+            layout: &uniform_bind_group_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: uniform_buffer.as_entire_binding(),
+            }],
+        });
+
+        let shader = device.create_shader_module(wgpu::include_wgsl!("grid.wgsl"));
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Grid pipeline layout"),
+            bind_group_layouts: &[global_uniform_layout, &uniform_bind_group_layout],
+            push_constant_ranges: &[],
+        });
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Grid pipeline"),
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: "vert_main",
+                buffers: &[],
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: "frag_main",
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: surface_format,
+                    blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+            }),
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::TriangleList,
+                cull_mode: None,
+                ..Default::default()
+            },
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+            cache: None,
+        });
+
+        GridRenderer {
+            pipeline,
+            uniform,
+            uniform_buffer,
+            uniform_bind_group,
+        }
+    }
+
+    /// Chooses which lines will be drawn.
+    pub fn prepare(&mut self, queue: &Queue, show_tile_lines: bool, show_chunk_lines: bool) {
+        let uniform = GridUniform {
+            show_tile_lines: u32::from(show_tile_lines),
+            show_chunk_lines: u32::from(show_chunk_lines),
+            padding: [0; 2],
+        };
+        if uniform != self.uniform {
+            self.uniform = uniform;
+            queue.write_buffer(
+                &self.uniform_buffer,
+                0,
+                bytemuck::cast_slice(&[self.uniform]),
+            );
+        }
+    }
+
+    /// Draws the lines chosen in the last call to `prepare`, if there are any. The global uniform
+    /// bind group must already be set at index 0.
+    pub fn draw(&self, render_pass: &mut RenderPass) {
+        if self.uniform.show_tile_lines == 0 && self.uniform.show_chunk_lines == 0 {
+            return;
+        }
+
+        render_pass.set_pipeline(&self.pipeline);
+        render_pass.set_bind_group(1, &self.uniform_bind_group, &[]);
+        render_pass.draw(0..3, 0..1);
+    }
+}
+
+#[repr(C)]
+#[derive(Copy, Clone, Default, PartialEq, Eq, bytemuck::Pod, bytemuck::Zeroable)]
+struct GridUniform {
+    /// 1 if the lines between tiles should be drawn, 0 otherwise.
+    show_tile_lines: u32,
+    /// 1 if the lines between chunks should be drawn, 0 otherwise.
+    show_chunk_lines: u32,
+    // WASM requires buffer bindings to be 16-bit aligned, so we need to add some padding.
+    padding: [u32; 2],
+}
@@ -0,0 +1,124 @@
+use crate::display::font::{GLYPH_HEIGHT, GLYPH_WIDTH};
+use crate::display::get_linear_rgb;
+use crate::display::glyph_render_instance::GlyphRenderInstance;
+use crate::display::text_renderer::{glyph_uv, solid_uv};
+use std::collections::VecDeque;
+use web_time::{Duration, Instant};
+
+/// Collects everything that should be drawn on top of the world for a single frame. Positions
+/// are in pixels from the top left corner of the window.
+#[derive(Default)]
+pub struct Hud {
+    glyph_instances: Vec<GlyphRenderInstance>,
+}
+
+impl Hud {
+    /// Draws a single line of text, with `pos` being the top left corner of the first character.
+    pub fn text(&mut self, pos: [f32; 2], string: &str, color: [u8; 3]) {
+        // A drop shadow keeps the text readable on top of bright tiles.
+        self.push_text(
+            [pos[0] + TEXT_SCALE, pos[1] + TEXT_SCALE],
+            string,
+            [0.0, 0.0, 0.0, 0.8],
+        );
+
+        let [r, g, b] = get_linear_rgb(color);
+        self.push_text(pos, string, [r, g, b, 1.0]);
+    }
+
+    /// Draws a solid rectangle, with `pos` being its top left corner.
+    pub fn rect(&mut self, pos: [f32; 2], size: [f32; 2], color: [u8; 3], alpha: f32) {
+        let [r, g, b] = get_linear_rgb(color);
+        let (uv_position, uv_size) = solid_uv();
+
+        self.glyph_instances.push(GlyphRenderInstance {
+            position: pos,
+            size,
+            uv_position,
+            uv_size,
+            color: [r, g, b, alpha],
+        });
+    }
+
+    /// Draws everything in another HUD on top of what's already in this one.
+    pub fn append(&mut self, other: &Hud) {
+        self.glyph_instances
+            .extend_from_slice(&other.glyph_instances);
+    }
+
+    /// The width of a line of text in pixels.
+    pub fn text_width(string: &str) -> f32 {
+        string.chars().count() as f32 * GLYPH_WIDTH as f32 * TEXT_SCALE
+    }
+
+    /// The height of a line of text in pixels, not including the gap between lines.
+    pub fn text_height() -> f32 {
+        GLYPH_HEIGHT as f32 * TEXT_SCALE
+    }
+
+    /// The vertical distance between two lines of text.
+    pub fn line_height() -> f32 {
+        (GLYPH_HEIGHT as f32 + 2.0) * TEXT_SCALE
+    }
+
+    /// Removes everything from the HUD.
+    pub fn clear(&mut self) {
+        self.glyph_instances.clear();
+    }
+
+    pub fn glyph_instances(&self) -> &[GlyphRenderInstance] {
+        &self.glyph_instances
+    }
+
+    fn push_text(&mut self, pos: [f32; 2], string: &str, color: [f32; 4]) {
+        let glyph_size = [
+            GLYPH_WIDTH as f32 * TEXT_SCALE,
+            GLYPH_HEIGHT as f32 * TEXT_SCALE,
+        ];
+
+        for (index, character) in string.chars().enumerate() {
+            // Spaces are invisible, so there's no point in drawing them.
+            if character == ' ' {
+                continue;
+            }
+
+            let (uv_position, uv_size) = glyph_uv(character);
+            self.glyph_instances.push(GlyphRenderInstance {
+                position: [pos[0] + index as f32 * glyph_size[0], pos[1]],
+                size: glyph_size,
+                uv_position,
+                uv_size,
+                color,
+            });
+        }
+    }
+}
+
+/// Measures the number of frames rendered per second.
+#[derive(Default)]
+pub struct FpsCounter {
+    /// The times at which frames were rendered within the last second.
+    frame_times: VecDeque<Instant>,
+}
+
+impl FpsCounter {
+    pub fn record_frame(&mut self) {
+        let now = Instant::now();
+        self.frame_times.push_back(now);
+
+        while self
+            .frame_times
+            .front()
+            .is_some_and(|&time| now.duration_since(time) > Duration::from_secs(1))
+        {
+            self.frame_times.pop_front();
+        }
+    }
+
+    pub fn fps(&self) -> usize {
+        self.frame_times.len()
+    }
+}
+
+/// The number of screen pixels used for each pixel of the font.
+const TEXT_SCALE: f32 = 2.0;
@@ -0,0 +1,217 @@
+use crate::position::{ChunkPos, PixelPos};
+use crate::world::{Chunk, Tile};
+use winit::dpi::PhysicalSize;
+
+/// Where the minimap is on the screen and which part of the world it shows. The minimap sits in the
+/// bottom right corner of the window and shows every generated chunk.
+///
+/// The minimap is split into `MINIMAP_SIDE_CELLS` cells on each side, with one pixel per cell. Each
+/// cell covers a square of 2^`cell_level` chunks on a side, aligned to multiples of that size, so
+/// the minimap shows one pixel per chunk until the generated chunks no longer fit, and then zooms out
+/// by powers of two until they do.
+#[derive(Copy, Clone, Debug)]
+pub struct MinimapLayout {
+    /// The top left corner of the minimap, in pixels from the top left corner of the window.
+    pub top_left: [f32; 2],
+    /// The cell shown in the bottom left corner of the minimap. Cell x, y covers the chunks from
+    /// x * 2^`cell_level`, y * 2^`cell_level` upwards.
+    pub bottom_left_cell: [i32; 2],
+    pub cell_level: u32,
+}
+
+impl MinimapLayout {
+    /// Lays out the minimap to show everything within `generated_bounds` (the bottom left and top
+    /// right generated chunks), or the area around the camera if no chunks have been generated.
+    pub fn new(
+        window_size: PhysicalSize<u32>,
+        generated_bounds: Option<[ChunkPos; 2]>,
+        camera_pos: &PixelPos,
+    ) -> Self {
+        let [bottom_left, top_right] =
+            generated_bounds.unwrap_or([camera_pos.chunk(), camera_pos.chunk()]);
+        let side_cells = i64::from(MINIMAP_SIDE_CELLS);
+        let span = |level: u32| {
+            let [min, max] = [bottom_left, top_right].map(|chunk| Self::cell(chunk, level));
+            [max[0] - min[0] + 1, max[1] - min[1] + 1]
+        };
+        // Every i32 chunk coordinate fits in 2^32 / 2^24 = 256 cells at level 24, so this always
+        // finds a level.
+        let cell_level = (0..=MAX_CELL_LEVEL)
+            .find(|&level| span(level).iter().all(|&span| span <= side_cells))
+            .unwrap_or(MAX_CELL_LEVEL);
+
+        // Center the generated chunks on the minimap, without going past the edge of the world.
+        let min_cell = Self::cell(bottom_left, cell_level);
+        let span = span(cell_level);
+        let bottom_left_cell = [0, 1].map(|axis| {
+            let lowest_cell = i64::from(i32::MIN) >> cell_level;
+            let highest_cell = (i64::from(i32::MAX) >> cell_level) - side_cells + 1;
+            let centered = min_cell[axis] - (side_cells - span[axis]) / 2;
+            centered.clamp(lowest_cell, highest_cell) as i32
+        });
+
+        MinimapLayout {
+            top_left: [
+                window_size.width as f32 - Self::size_px() - MINIMAP_MARGIN_PX,
+                window_size.height as f32 - Self::size_px() - MINIMAP_MARGIN_PX,
+            ],
+            bottom_left_cell,
+            cell_level,
+        }
+    }
+
+    /// The width and height of the minimap on the screen.
+    pub fn size_px() -> f32 {
+        MINIMAP_SIDE_CELLS as f32
+    }
+
+    /// The cell that a chunk is in at this layout's level.
+    pub fn cell_of(&self, chunk: ChunkPos) -> [i32; 2] {
+        Self::cell(chunk, self.cell_level).map(|coordinate| coordinate as i32)
+    }
+
+    /// Whether a position on the screen (in pixels from the top left corner of the window) is over
+    /// the minimap.
+    pub fn contains(&self, screen_pos: [f32; 2]) -> bool {
+        (0..2).all(|axis| {
+            screen_pos[axis] >= self.top_left[axis]
+                && screen_pos[axis] < self.top_left[axis] + Self::size_px()
+        })
+    }
+
+    /// Converts a position on the screen into the position in the world that the minimap shows
+    /// there.
+    pub fn screen_to_world(&self, screen_pos: [f32; 2]) -> PixelPos {
+        let world_px_per_px = self.world_px_per_cell();
+
+        // The y axis points up in the world but down on the screen.
+        self.bottom_left()
+            + [
+                f64::from(screen_pos[0] - self.top_left[0]) * world_px_per_px,
+                f64::from(self.top_left[1] + Self::size_px() - screen_pos[1]) * world_px_per_px,
+            ]
+    }
+
+    /// The inverse of `screen_to_world`. The result may be outside of the minimap.
+    pub fn world_to_screen(&self, world_pos: &PixelPos) -> [f32; 2] {
+        let px_per_world_px = 1.0 / self.world_px_per_cell();
+        let [dx, dy] = *world_pos - self.bottom_left();
+
+        [
+            self.top_left[0] + (dx * px_per_world_px) as f32,
+            self.top_left[1] + Self::size_px() - (dy * px_per_world_px) as f32,
+        ]
+    }
+
+    /// The position in the world shown at the bottom left corner of the minimap.
+    fn bottom_left(&self) -> PixelPos {
+        // Cells are aligned to multiples of their size, so this is always a chunk in the world.
+        let chunk = self
+            .bottom_left_cell
+            .map(|cell| (i64::from(cell) << self.cell_level) as i32);
+        ChunkPos {
+            x: chunk[0],
+            y: chunk[1],
+        }
+        .bottom_left()
+    }
+
+    /// The width of a cell in the world, in pixels.
+    fn world_px_per_cell(&self) -> f64 {
+        f64::from(Chunk::side_size())
+            * f64::from(Tile::width_px())
+            * f64::from(1 << self.cell_level)
+    }
+
+    fn cell(chunk: ChunkPos, level: u32) -> [i64; 2] {
+        [chunk.x, chunk.y].map(|coordinate| i64::from(coordinate) >> level)
+    }
+}
+
+/// The number of cells shown along each side of the minimap, which is also its size in pixels.
+pub const MINIMAP_SIDE_CELLS: i32 = 256;
+/// The level at which the whole world fits on the minimap.
+const MAX_CELL_LEVEL: u32 = 24;
+/// The distance between the minimap and the edges of the window.
+const MINIMAP_MARGIN_PX: f32 = 8.0;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WINDOW_SIZE: PhysicalSize<u32> = PhysicalSize::new(800, 600);
+
+    fn layout_for(bottom_left: ChunkPos, top_right: ChunkPos) -> MinimapLayout {
+        MinimapLayout::new(
+            WINDOW_SIZE,
+            Some([bottom_left, top_right]),
+            &PixelPos::default(),
+        )
+    }
+
+    #[test]
+    fn small_worlds_are_shown_at_one_pixel_per_chunk() {
+        let layout = layout_for(ChunkPos { x: -10, y: -5 }, ChunkPos { x: 9, y: 4 });
+
+        assert_eq!(layout.cell_level, 0);
+        assert_eq!(layout.bottom_left_cell, [-128, -128]);
+    }
+
+    #[test]
+    fn big_worlds_are_zoomed_out_until_they_fit() {
+        let layout = layout_for(ChunkPos { x: 0, y: 0 }, ChunkPos { x: 1000, y: 3 });
+
+        assert_eq!(layout.cell_level, 2);
+        assert!(layout.cell_of(ChunkPos { x: 1000, y: 3 })[0] < layout.bottom_left_cell[0] + 256);
+        assert!(layout.cell_of(ChunkPos { x: 0, y: 0 })[0] >= layout.bottom_left_cell[0]);
+    }
+
+    #[test]
+    fn chunks_at_the_edges_of_the_world_fit() {
+        let bottom_left = ChunkPos {
+            x: i32::MIN,
+            y: i32::MIN,
+        };
+        let top_right = ChunkPos {
+            x: i32::MAX,
+            y: 100_000_000,
+        };
+        let layout = layout_for(bottom_left, top_right);
+
+        assert_eq!(layout.cell_level, MAX_CELL_LEVEL);
+        assert_eq!(layout.bottom_left_cell, [-128, -128]);
+        assert_eq!(layout.cell_of(top_right), [127, 5]);
+
+        let far_corner = layout_for(top_right, top_right);
+        assert_eq!(far_corner.cell_level, 0);
+        assert_eq!(far_corner.bottom_left_cell[0], i32::MAX - 255);
+    }
+
+    #[test]
+    fn screen_positions_survive_a_round_trip_through_the_world() {
+        for (bottom_left, top_right) in [
+            (ChunkPos { x: -3, y: 2 }, ChunkPos { x: 40, y: 7 }),
+            (ChunkPos { x: 0, y: 0 }, ChunkPos { x: 5000, y: 5000 }),
+            (
+                ChunkPos {
+                    x: i32::MIN,
+                    y: i32::MIN,
+                },
+                ChunkPos {
+                    x: i32::MAX,
+                    y: i32::MAX,
+                },
+            ),
+        ] {
+            let layout = layout_for(bottom_left, top_right);
+            for screen_pos in [[600.0, 400.0], [700.5, 433.25], [543.0, 591.0]] {
+                let [x, y] = layout.world_to_screen(&layout.screen_to_world(screen_pos));
+                assert!(
+                    (x - screen_pos[0]).abs() < 1e-3 && (y - screen_pos[1]).abs() < 1e-3,
+                    "{screen_pos:?} came back as {:?}",
+                    [x, y]
+                );
+            }
+        }
+    }
+}
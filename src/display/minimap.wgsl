@@ -0,0 +1,74 @@
+struct GlobalUniform {
+    window_size_px: vec2<f32>,
+    world_to_clip_scale: vec2<f32>,
+    world_to_clip_offset: vec2<f32>,
+    screen_to_world_scale: vec2<f32>,
+    screen_to_world_offset: vec2<f32>,
+    camera_zoom: f32,
+    time_s: f32,
+    sprites_enabled: u32,
+    // See shader.wgsl for why the padding looks like this.
+    padding: f32,
+    viewport_origin_px: vec2<f32>,
+    camera_chunk: vec2<i32>,
+    padding_2: vec2<f32>,
+};
+
+@group(0) @binding(0)
+var<uniform> global_uniform: GlobalUniform;
+
+struct MinimapUniform {
+    // The top left corner of the minimap, in pixels from the top left corner of the window.
+    top_left_px: vec2<f32>,
+    size_px: f32,
+    padding: f32,
+    // The cell (counting from the world origin) in the bottom left corner of the minimap. There is one
+    // texel per cell.
+    first_texel: vec2<i32>,
+    padding_2: vec2<i32>,
+};
+
+@group(1) @binding(0)
+var<uniform> minimap_uniform: MinimapUniform;
+
+// The colors of the cells, with each cell at its coordinates modulo the size of the minimap so that
+// the texture wraps around in both directions. Texels with an alpha of 0 haven't been generated.
+@group(2) @binding(0)
+var minimap_colors: texture_2d<f32>;
+
+// The color shown where there are no generated chunks.
+const BACKGROUND_COLOR: vec4<f32> = vec4<f32>(0.0, 0.0, 0.0, 0.6);
+
+@vertex
+fn vert_main(@builtin(vertex_index) vertex_index: u32) -> @builtin(position) vec4<f32> {
+    // Two triangles making up a square, with (0, 0) at the top left corner.
+    var corners = array<vec2<f32>, 6>(
+        vec2<f32>(0.0, 0.0),
+        vec2<f32>(1.0, 0.0),
+        vec2<f32>(1.0, 1.0),
+        vec2<f32>(0.0, 0.0),
+        vec2<f32>(1.0, 1.0),
+        vec2<f32>(0.0, 1.0),
+    );
+    let screen_pos = minimap_uniform.top_left_px + corners[vertex_index] * minimap_uniform.size_px;
+
+    // Convert from pixels with y pointing down to normalized coordinates (-1 to 1) with y pointing up.
+    let normalized = screen_pos / global_uniform.window_size_px * 2.0 - 1.0;
+    return vec4<f32>(normalized.x, -normalized.y, 0.0, 1.0);
+}
+
+@fragment
+fn frag_main(@builtin(position) screen_pos: vec4<f32>) -> @location(0) vec4<f32> {
+    let texture_size = vec2<i32>(textureDimensions(minimap_colors));
+    // How far across the minimap the pixel is, from 0 to 1, with y pointing up like the world.
+    let fraction = (screen_pos.xy - minimap_uniform.top_left_px) / minimap_uniform.size_px;
+    let texel = minimap_uniform.first_texel
+        + vec2<i32>(floor(vec2<f32>(fraction.x, 1.0 - fraction.y) * vec2<f32>(texture_size)));
+    let wrapped_texel = ((texel % texture_size) + texture_size) % texture_size;
+
+    let color = textureLoad(minimap_colors, wrapped_texel, 0);
+    if color.a == 0.0 {
+        return BACKGROUND_COLOR;
+    }
+    return color;
+}
@@ -0,0 +1,265 @@
+use crate::display::minimap::{MinimapLayout, MINIMAP_SIDE_CELLS};
+use crate::position::ChunkPos;
+use crate::world::{Chunk, World};
+use std::collections::HashMap;
+use wgpu::util::{BufferInitDescriptor, DeviceExt};
+use wgpu::{
+    BindGroup, BindGroupLayout, Buffer, Device, Queue, RenderPass, RenderPipeline, Texture,
+    TextureFormat,
+};
+
+/// Draws the minimap. The minimap's colors live in a texture with a texel for each cell of the
+/// minimap (see `MinimapLayout`), which wraps around in both directions: each cell has a texel at its
+/// coordinates modulo the size of the minimap. Since all of the generated chunks fit on the minimap,
+/// no two of their cells share a texel, so when a chunk is generated or changed, only its cell needs
+/// to be filled rather than the whole texture. The whole texture is only refilled when the minimap
+/// zooms out or chunks are removed (when the world is replaced).
+pub struct MinimapRenderer {
+    pipeline: RenderPipeline,
+    uniform_buffer: Buffer,
+    uniform_bind_group: BindGroup,
+    texture: Texture,
+    texture_bind_group: BindGroup,
+    /// The revisions of the chunks that have been drawn into the texture.
+    drawn_chunks: HashMap<ChunkPos, u64>,
+    /// The cell level that the texture was filled at.
+    cell_level: u32,
+}
+
+impl MinimapRenderer {
+    pub fn new(
+        device: &Device,
+        surface_format: TextureFormat,
+        global_uniform_layout: &BindGroupLayout,
+    ) -> Self {
+        let uniform_buffer = device.create_buffer_init(&BufferInitDescriptor {
+            label: Some("Minimap uniform buffer"),
+            contents: bytemuck::cast_slice(&[MinimapUniform::default()]),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+        let uniform_bind_group_layout =
+            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                label: Some("Minimap uniform bind group layout"),
+                entries: &[wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::VERTEX_FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                }],
+            });
+        let uniform_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Minimap uniform bind group"),
+            layout: &uniform_bind_group_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: uniform_buffer.as_entire_binding(),
+            }],
+        });
+
+        let (texture, texture_bind_group_layout, texture_bind_group) = create_texture(device);
+
+        let shader = device.create_shader_module(wgpu::include_wgsl!("minimap.wgsl"));
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Minimap pipeline layout"),
+            bind_group_layouts: &[
+                global_uniform_layout,
+                &uniform_bind_group_layout,
+                &texture_bind_group_layout,
+            ],
+            push_constant_ranges: &[],
+        });
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Minimap pipeline"),
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: "vert_main",
+                buffers: &[],
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: "frag_main",
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: surface_format,
+                    blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+            }),
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::TriangleList,
+                cull_mode: None,
+                ..Default::default()
+            },
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+            cache: None,
+        });
+
+        MinimapRenderer {
+            pipeline,
+            uniform_buffer,
+            uniform_bind_group,
+            texture,
+            texture_bind_group,
+            drawn_chunks: HashMap::new(),
+            cell_level: 0,
+        }
+    }
+
+    /// Fills the cells of the chunks that have been generated or changed since the last call, and
+    /// moves the minimap to where the layout says it should be.
+    pub fn prepare(&mut self, queue: &Queue, world: &World, layout: &MinimapLayout) {
+        let uniform = MinimapUniform {
+            top_left_px: layout.top_left,
+            size_px: MinimapLayout::size_px(),
+            padding: 0.0,
+            first_texel: layout.bottom_left_cell,
+            padding_2: [0; 2],
+        };
+        queue.write_buffer(&self.uniform_buffer, 0, bytemuck::cast_slice(&[uniform]));
+
+        // Cells that have chunks which aren't there any more have to be cleared, and the texture
+        // can't tell which other chunks share the cell, so it's simplest to start again.
+        let removed_chunks = self
+            .drawn_chunks
+            .keys()
+            .any(|chunk_coords| !world.chunks.contains_key(chunk_coords));
+        if removed_chunks || layout.cell_level != self.cell_level {
+            self.drawn_chunks.clear();
+            self.cell_level = layout.cell_level;
+            self.clear(queue);
+        }
+
+        for (&chunk_coords, chunk) in &world.chunks {
+            if self.drawn_chunks.get(&chunk_coords) != Some(&chunk.revision()) {
+                self.fill_cell(queue, layout.cell_of(chunk_coords), chunk);
+                self.drawn_chunks.insert(chunk_coords, chunk.revision());
+            }
+        }
+    }
+
+    /// Colors a chunk's cell with the most common tile in the chunk. When a cell covers more than
+    /// one chunk, the last chunk filled in wins.
+    fn fill_cell(&self, queue: &Queue, cell: [i32; 2], chunk: &Chunk) {
+        let [r, g, b] = chunk.lod_summary(Chunk::max_lod_level()).tile(0, 0).color();
+        let texel = cell.map(|coordinate| coordinate.rem_euclid(MINIMAP_SIDE_CELLS) as u32);
+        queue.write_texture(
+            wgpu::ImageCopyTexture {
+                texture: &self.texture,
+                mip_level: 0,
+                origin: wgpu::Origin3d {
+                    x: texel[0],
+                    y: texel[1],
+                    z: 0,
+                },
+                aspect: wgpu::TextureAspect::All,
+            },
+            &[r, g, b, u8::MAX],
+            wgpu::ImageDataLayout {
+                offset: 0,
+                bytes_per_row: Some(4),
+                rows_per_image: Some(1),
+            },
+            wgpu::Extent3d {
+                width: 1,
+                height: 1,
+                depth_or_array_layers: 1,
+            },
+        );
+    }
+
+    /// Makes every cell transparent, as if there were no chunks.
+    fn clear(&self, queue: &Queue) {
+        let side_texels = MINIMAP_SIDE_CELLS as u32;
+        queue.write_texture(
+            self.texture.as_image_copy(),
+            &vec![0; (side_texels * side_texels * 4) as usize],
+            wgpu::ImageDataLayout {
+                offset: 0,
+                bytes_per_row: Some(side_texels * 4),
+                rows_per_image: Some(side_texels),
+            },
+            wgpu::Extent3d {
+                width: side_texels,
+                height: side_texels,
+                depth_or_array_layers: 1,
+            },
+        );
+    }
+
+    /// Draws the minimap as prepared by the last call to `prepare`. The global uniform bind group
+    /// must already be set at index 0.
+    pub fn draw(&self, render_pass: &mut RenderPass) {
+        render_pass.set_pipeline(&self.pipeline);
+        render_pass.set_bind_group(1, &self.uniform_bind_group, &[]);
+        render_pass.set_bind_group(2, &self.texture_bind_group, &[]);
+        render_pass.draw(0..6, 0..1);
+    }
+}
+
+/// Creates the texture that holds the minimap's colors, along with the bind group for reading it.
+fn create_texture(device: &Device) -> (Texture, BindGroupLayout, BindGroup) {
+    let side_texels = MINIMAP_SIDE_CELLS as u32;
+    let texture = device.create_texture(&wgpu::TextureDescriptor {
+        label: Some("Minimap texture"),
+        size: wgpu::Extent3d {
+            width: side_texels,
+            height: side_texels,
+            depth_or_array_layers: 1,
+        },
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D2,
+        // The tile colors are in standard rgb.
+        format: TextureFormat::Rgba8UnormSrgb,
+        usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+        view_formats: &[],
+    });
+    let texture_bind_group_layout =
+        device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Minimap texture bind group layout"),
+            entries: &[wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Texture {
+                    sample_type: wgpu::TextureSampleType::Float { filterable: false },
+                    view_dimension: wgpu::TextureViewDimension::D2,
+                    multisampled: false,
+                },
+                count: None,
+            }],
+        });
+    let texture_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+        label: Some("Minimap texture bind group"),
+        layout: &texture_bind_group_layout,
+        entries: &[wgpu::BindGroupEntry {
+            binding: 0,
+            resource: wgpu::BindingResource::TextureView(
+                &texture.create_view(&wgpu::TextureViewDescriptor::default()),
+            ),
+        }],
+    });
+
+    (texture, texture_bind_group_layout, texture_bind_group)
+}
+
+#[repr(C)]
+#[derive(Copy, Clone, Default, bytemuck::Pod, bytemuck::Zeroable)]
+struct MinimapUniform {
+    /// The top left corner of the minimap, in pixels from the top left corner of the window.
+    top_left_px: [f32; 2],
+    size_px: f32,
+    padding: f32,
+    /// The cell (counting from the world origin rather than the corner of the texture) in the
+    /// bottom left corner of the minimap.
+    first_texel: [i32; 2],
+    // WASM requires buffer bindings to be 16-bit aligned, so we need to add some padding.
+    padding_2: [i32; 2],
+}
@@ -1,21 +1,46 @@
+mod autotile;
+mod chunk_mesh_cache;
+mod chunk_texture_renderer;
+mod font;
 mod global_uniform;
+mod glyph_render_instance;
+mod grid_renderer;
+mod hud;
 mod instance_buffer;
+mod minimap;
+mod minimap_renderer;
+mod overlay;
+mod overlay_renderer;
+mod text_renderer;
+mod tile_atlas;
 mod tile_render_instance;
 
+use crate::display::chunk_mesh_cache::ChunkMeshCache;
+use crate::display::chunk_texture_renderer::ChunkTextureRenderer;
 use crate::display::global_uniform::GlobalUniformData;
-use crate::position::PositionMode;
-use crate::ui::Ui;
-use crate::world::World;
+use crate::display::grid_renderer::GridRenderer;
+use crate::display::hud::FpsCounter;
+use crate::display::minimap_renderer::MinimapRenderer;
+use crate::display::overlay_renderer::OverlayRenderer;
+use crate::display::text_renderer::TextRenderer;
+use crate::display::tile_atlas::TileAtlas;
+use crate::position::ChunkPos;
+use crate::ui::{Camera, Ui, Viewport, MAX_VIEWPORTS};
+use crate::world::{Chunk, Tile, World};
 use global_uniform::GlobalUniform;
+pub use hud::Hud;
 use instance_buffer::InstanceBuffer;
+pub use minimap::MinimapLayout;
+pub use overlay::OverlayRegistry;
 use std::iter::once;
 use tile_render_instance::TileRenderInstance;
+use web_time::Instant;
 use wgpu::util::{BufferInitDescriptor, DeviceExt};
 use wgpu::{
     Buffer, BufferAddress, Device, PresentMode, Queue, RenderPipeline, Surface,
     SurfaceConfiguration, VertexAttribute, VertexBufferLayout, VertexFormat, VertexStepMode,
 };
-use winit::dpi::PhysicalSize;
+use winit::dpi::{PhysicalPosition, PhysicalSize};
 use winit::event_loop::EventLoop;
 use winit::window::{Window, WindowBuilder};
 
@@ -28,8 +53,19 @@ pub struct Display<'a> {
     render_pipeline: RenderPipeline,
     vertex_buffer: Buffer,
     index_buffer: Buffer,
+    /// Holds the settlements, which change too often to be worth caching.
     instance_buffer: InstanceBuffer,
+    chunk_meshes: ChunkMeshCache,
+    chunk_texture_renderer: ChunkTextureRenderer,
+    overlay_renderer: OverlayRenderer,
+    grid_renderer: GridRenderer,
+    minimap_renderer: MinimapRenderer,
     global_uniform: GlobalUniform,
+    text_renderer: TextRenderer,
+    hud: Hud,
+    fps_counter: FpsCounter,
+    /// When the display was created, which animations are timed from.
+    start_time: Instant,
 }
 
 impl<'a> Display<'a> {
@@ -108,14 +144,9 @@ impl<'a> Display<'a> {
         // Initialize the surface. Doing this in the constructor is necessary for WASM and decreases the startup time for desktop.
         surface.configure(&device, &config);
 
-        let global_uniform = GlobalUniform::new(
-            &device,
-            GlobalUniformData {
-                window_size_px: [config.width as f32, config.height as f32],
-                // Just initialize to something - everything else should be overwritten before it's used.
-                ..Default::default()
-            },
-        );
+        let tile_atlas = TileAtlas::load(&device, &queue);
+        // Each viewport has its own slot in the global uniform, after the HUD's.
+        let global_uniform = GlobalUniform::new(&device, MAX_VIEWPORTS + 1, &tile_atlas);
 
         let shader = device.create_shader_module(wgpu::include_wgsl!("shader.wgsl"));
         let render_pipeline_layout =
@@ -192,6 +223,25 @@ impl<'a> Display<'a> {
 
         let instance_buffer = InstanceBuffer::new(&device);
 
+        let chunk_texture_renderer =
+            ChunkTextureRenderer::new(&device, config.format, &global_uniform.bind_group_layout);
+
+        let overlay_renderer =
+            OverlayRenderer::new(&device, config.format, &global_uniform.bind_group_layout);
+
+        let grid_renderer =
+            GridRenderer::new(&device, config.format, &global_uniform.bind_group_layout);
+
+        let minimap_renderer =
+            MinimapRenderer::new(&device, config.format, &global_uniform.bind_group_layout);
+
+        let text_renderer = TextRenderer::new(
+            &device,
+            &queue,
+            config.format,
+            &global_uniform.bind_group_layout,
+        );
+
         Self {
             window,
             surface,
@@ -202,7 +252,16 @@ impl<'a> Display<'a> {
             vertex_buffer,
             index_buffer,
             instance_buffer,
+            chunk_meshes: ChunkMeshCache::default(),
+            chunk_texture_renderer,
+            overlay_renderer,
+            grid_renderer,
+            minimap_renderer,
             global_uniform,
+            text_renderer,
+            hud: Hud::default(),
+            fps_counter: FpsCounter::default(),
+            start_time: Instant::now(),
         }
     }
 
@@ -219,13 +278,6 @@ impl<'a> Display<'a> {
             self.config.width = new_size.width;
             self.config.height = new_size.height;
             self.configure_surface();
-            self.global_uniform.write_data(
-                &self.queue,
-                GlobalUniformData {
-                    window_size_px: [new_size.width as f32, new_size.height as f32],
-                    ..*self.global_uniform.data()
-                },
-            );
         }
     }
 
@@ -238,76 +290,460 @@ impl<'a> Display<'a> {
         let view = output
             .texture
             .create_view(&wgpu::TextureViewDescriptor::default());
+        let time_s = self.start_time.elapsed().as_secs_f32();
+
+        self.grid_renderer.prepare(
+            &self.queue,
+            ui.render_settings.show_tile_grid,
+            ui.render_settings.show_chunk_grid,
+        );
+        for (index, viewport) in ui.viewports().iter().enumerate() {
+            // The first viewport clears the whole window, and the rest draw over their own parts.
+            let load = if index == 0 {
+                wgpu::LoadOp::Clear(BACKGROUND_COLOR)
+            } else {
+                wgpu::LoadOp::Load
+            };
+            let uniform_slot = HUD_UNIFORM_SLOT + 1 + index;
+            self.render_viewport(ui, world, &view, uniform_slot, viewport, load, time_s);
+        }
+        self.chunk_meshes.evict_unused();
+        self.chunk_texture_renderer.evict_unused();
+        self.overlay_renderer.evict_unused();
+
+        // The HUD goes on top of everything else, and covers the whole window.
+        let minimap_layout = self.prepare_hud(ui, world);
+        self.global_uniform.write_data(
+            &self.queue,
+            HUD_UNIFORM_SLOT,
+            GlobalUniformData {
+                window_size_px: [self.config.width as f32, self.config.height as f32],
+                time_s,
+                ..Default::default()
+            },
+        );
         let mut encoder = self
             .device
             .create_command_encoder(&wgpu::CommandEncoderDescriptor {
-                label: Some("Render encoder"),
+                label: Some("HUD render encoder"),
             });
+        let mut render_pass = begin_render_pass(&mut encoder, &view, wgpu::LoadOp::Load);
+        render_pass.set_bind_group(
+            0,
+            &self.global_uniform.bind_group,
+            &[self.global_uniform.offset(HUD_UNIFORM_SLOT)],
+        );
+        if minimap_layout.is_some() {
+            self.minimap_renderer.draw(&mut render_pass);
+        }
+        self.text_renderer.draw(&mut render_pass);
 
-        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
-            label: Some("Render pass"),
-            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-                view: &view,
-                resolve_target: None,
-                ops: wgpu::Operations {
-                    load: wgpu::LoadOp::Clear(wgpu::Color {
-                        r: 0.1,
-                        g: 0.2,
-                        b: 0.3,
-                        a: 1.0,
-                    }),
-                    store: wgpu::StoreOp::Store,
-                },
-            })],
-            depth_stencil_attachment: None,
-            occlusion_query_set: None,
-            timestamp_writes: None,
-        });
+        // We have to explicitly end the render pass by dropping it before calling encoder.finish().
+        drop(render_pass);
 
-        let tile_instances = TileRenderInstance::vec_from_world(
-            world,
-            &ui.camera.pos,
-            self.config.width as i32,
-            self.config.height as i32,
-            ui.camera.zoom_multiplier(),
-        );
+        self.queue.submit(once(encoder.finish()));
+        output.present();
 
-        self.instance_buffer.write_data(
-            &self.queue,
-            &self.device,
-            bytemuck::cast_slice(&tile_instances),
-        );
+        Ok(())
+    }
+
+    /// Draws the world as a viewport's camera sees it, into the viewport's part of the window,
+    /// using the given slot of the global uniform.
+    ///
+    /// Each viewport is submitted separately, since the renderers only hold the chunks for one
+    /// camera at a time and writes to the queue only take effect on the next submission.
+    // Splitting the arguments up any further would only make the call harder to follow.
+    #[expect(clippy::too_many_arguments)]
+    fn render_viewport(
+        &mut self,
+        ui: &Ui,
+        world: &World,
+        view: &wgpu::TextureView,
+        uniform_slot: usize,
+        viewport: &Viewport,
+        load: wgpu::LoadOp<wgpu::Color>,
+        time_s: f32,
+    ) {
+        let (origin, size) = self.clamp_to_surface(viewport);
+        let camera = &viewport.camera;
+        let settlement_count = self.prepare_tiles(ui, world, camera, size);
+        let transform = camera.view_transform(size);
+        let (world_to_clip_scale, world_to_clip_offset) = transform.world_to_clip();
+        let (screen_to_world_scale, screen_to_world_offset) = transform.screen_to_relative_world();
 
         self.global_uniform.write_data(
             &self.queue,
+            uniform_slot,
             GlobalUniformData {
-                camera_pos: [
-                    ui.camera.pos.x(PositionMode::Pixels) as f32,
-                    ui.camera.pos.y(PositionMode::Pixels) as f32,
-                ],
-                camera_zoom: ui.camera.zoom_multiplier(),
-                ..*self.global_uniform.data()
+                window_size_px: [size.width as f32, size.height as f32],
+                world_to_clip_scale,
+                world_to_clip_offset,
+                screen_to_world_scale,
+                screen_to_world_offset,
+                camera_zoom: camera.zoom_multiplier(),
+                time_s,
+                sprites_enabled: u32::from(ui.render_settings.show_sprites),
+                viewport_origin_px: [origin.x as f32, origin.y as f32],
+                camera_chunk: [transform.origin_chunk.x, transform.origin_chunk.y],
+                ..Default::default()
             },
         );
 
+        let mut encoder = self
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("Render encoder"),
+            });
+        let mut render_pass = begin_render_pass(&mut encoder, view, load);
+        render_pass.set_viewport(
+            origin.x as f32,
+            origin.y as f32,
+            size.width as f32,
+            size.height as f32,
+            0.0,
+            1.0,
+        );
+        render_pass.set_scissor_rect(origin.x, origin.y, size.width, size.height);
+
+        render_pass.set_bind_group(
+            0,
+            &self.global_uniform.bind_group,
+            &[self.global_uniform.offset(uniform_slot)],
+        );
+        if ui.render_settings.tile_render_mode == TileRenderMode::Textures {
+            self.chunk_texture_renderer.draw(&mut render_pass);
+        }
+
         render_pass.set_pipeline(&self.render_pipeline);
-        render_pass.set_bind_group(0, &self.global_uniform.bind_group, &[]);
         render_pass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
-        render_pass.set_vertex_buffer(1, self.instance_buffer.buffer.slice(..));
         render_pass.set_index_buffer(self.index_buffer.slice(..), wgpu::IndexFormat::Uint16);
-        render_pass.draw_indexed(
-            0..(TILE_INDICES.len() as u32),
-            0,
-            0..(tile_instances.len() as u32),
-        );
+        if ui.render_settings.tile_render_mode == TileRenderMode::Instances {
+            self.chunk_meshes.draw(
+                &mut render_pass,
+                TILE_INDICES.len() as u32,
+                ui.render_settings.show_roads,
+            );
+        }
 
-        // We have to explicitly end the render pass by dropping it before calling encoder.finish().
-        drop(render_pass);
+        // The overlay goes above the terrain and roads, but below the settlements.
+        self.overlay_renderer.draw(&mut render_pass);
 
+        // Settlements go above everything else in the world. The overlay pipeline may have replaced
+        // the tile pipeline and vertex buffer, so they're set again.
+        render_pass.set_pipeline(&self.render_pipeline);
+        render_pass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
+        render_pass.set_vertex_buffer(1, self.instance_buffer.buffer.slice(..));
+        render_pass.draw_indexed(0..(TILE_INDICES.len() as u32), 0, 0..settlement_count);
+
+        self.grid_renderer.draw(&mut render_pass);
+
+        drop(render_pass);
         self.queue.submit(once(encoder.finish()));
-        output.present();
+    }
 
-        Ok(())
+    /// The top left corner and size of a viewport, shrunk to fit inside the surface. They only
+    /// differ from the viewport's own while the window is being resized.
+    fn clamp_to_surface(&self, viewport: &Viewport) -> (PhysicalPosition<u32>, PhysicalSize<u32>) {
+        let x = viewport.origin.x.min(self.config.width - 1);
+        let y = viewport.origin.y.min(self.config.height - 1);
+        let size = PhysicalSize::new(
+            viewport.size.width.min(self.config.width - x),
+            viewport.size.height.min(self.config.height - y),
+        );
+        (PhysicalPosition::new(x, y), size)
+    }
+
+    /// Fills the HUD with everything that goes on top of the world, and prepares the minimap.
+    /// Returns where the minimap is, or None if it's hidden.
+    fn prepare_hud(&mut self, ui: &Ui, world: &World) -> Option<MinimapLayout> {
+        self.fps_counter.record_frame();
+        self.hud.clear();
+        self.hud.text(
+            [HUD_MARGIN_PX, HUD_MARGIN_PX],
+            &format!("FPS: {}", self.fps_counter.fps()),
+            HUD_TEXT_COLOR,
+        );
+        ui.draw_hud(
+            &mut self.hud,
+            [HUD_MARGIN_PX, HUD_MARGIN_PX + Hud::line_height()],
+            world,
+        );
+        if ui.render_settings.show_chunk_grid {
+            for viewport in ui.viewports() {
+                self.draw_chunk_labels(viewport);
+            }
+        }
+        if ui.viewports().len() > 1 {
+            self.draw_viewport_borders(ui);
+        }
+        let minimap_layout = ui.minimap_layout();
+        if let Some(layout) = &minimap_layout {
+            self.minimap_renderer.prepare(&self.queue, world, layout);
+            self.draw_minimap_frame(ui, layout);
+        }
+        ui.render_settings.overlays.draw_legend(
+            &mut self.hud,
+            [
+                HUD_MARGIN_PX,
+                self.config.height as f32 - HUD_MARGIN_PX - OverlayRegistry::legend_height(),
+            ],
+        );
+        self.text_renderer
+            .prepare(&self.queue, &self.device, &self.hud);
+
+        minimap_layout
+    }
+
+    /// Outlines each viewport, so that it's clear where one ends and the next begins. The active
+    /// viewport is highlighted.
+    fn draw_viewport_borders(&mut self, ui: &Ui) {
+        for (index, viewport) in ui.viewports().iter().enumerate() {
+            let color = if index == ui.active_viewport() {
+                ACTIVE_VIEWPORT_BORDER_COLOR
+            } else {
+                VIEWPORT_BORDER_COLOR
+            };
+            let [left, top] = [viewport.origin.x as f32, viewport.origin.y as f32];
+            draw_outline(
+                &mut self.hud,
+                [left, top],
+                [
+                    left + viewport.size.width as f32,
+                    top + viewport.size.height as f32,
+                ],
+                color,
+            );
+        }
+    }
+
+    /// Outlines the minimap, and the part of the world that each viewport can see on it. The active
+    /// viewport's part is highlighted.
+    fn draw_minimap_frame(&mut self, ui: &Ui, layout: &MinimapLayout) {
+        let size = MinimapLayout::size_px();
+        let [left, top] = layout.top_left;
+        draw_outline(
+            &mut self.hud,
+            [
+                left - MINIMAP_BORDER_WIDTH_PX,
+                top - MINIMAP_BORDER_WIDTH_PX,
+            ],
+            [
+                left + size + MINIMAP_BORDER_WIDTH_PX,
+                top + size + MINIMAP_BORDER_WIDTH_PX,
+            ],
+            MINIMAP_BORDER_COLOR,
+        );
+
+        for (index, viewport) in ui.viewports().iter().enumerate() {
+            // The visible part of the world might be bigger than the minimap when zoomed far out.
+            let (bottom_left, top_right) = viewport.camera.visible_bounds(viewport.size);
+            let [left_px, bottom_px] = layout.world_to_screen(&bottom_left);
+            let [right_px, top_px] = layout.world_to_screen(&top_right);
+            let (top_left, bottom_right) = ([left_px, top_px], [right_px, bottom_px]);
+            let clamp = |pos: [f32; 2]| {
+                [
+                    pos[0].clamp(left, left + size),
+                    pos[1].clamp(top, top + size),
+                ]
+            };
+            let color = if index == ui.active_viewport() {
+                HUD_TEXT_COLOR
+            } else {
+                VIEWPORT_BORDER_COLOR
+            };
+            draw_outline(&mut self.hud, clamp(top_left), clamp(bottom_right), color);
+        }
+    }
+
+    /// Labels the top left corner of every chunk visible in a viewport with its coordinates, as long
+    /// as the chunks are big enough on the screen for the labels to fit.
+    fn draw_chunk_labels(&mut self, viewport: &Viewport) {
+        let camera = &viewport.camera;
+        let chunk_width_on_screen_px =
+            (Tile::width_px() * Chunk::side_size()) as f32 * camera.zoom_multiplier();
+        if chunk_width_on_screen_px < MIN_CHUNK_LABEL_SPACING_PX {
+            return;
+        }
+
+        let [bottom_left_chunk_pos, top_right_chunk_pos] =
+            visible_chunk_range(camera, viewport.size);
+
+        for chunk_pos in ChunkPos::rect(bottom_left_chunk_pos, top_right_chunk_pos) {
+            // The top left corner of a chunk is the bottom left corner of the one above it.
+            let corner = (chunk_pos + ChunkPos { x: 0, y: 1 }).bottom_left();
+            let Some([corner_x, corner_y]) = viewport.world_to_screen(&corner) else {
+                continue;
+            };
+            let label_pos = PhysicalPosition::new(
+                f64::from(corner_x + CHUNK_LABEL_OFFSET_PX),
+                f64::from(corner_y + CHUNK_LABEL_OFFSET_PX),
+            );
+            // The HUD isn't clipped to the viewports, so labels that would start in another
+            // viewport are left out.
+            if !viewport.contains(label_pos) {
+                continue;
+            }
+            self.hud.text(
+                [label_pos.x as f32, label_pos.y as f32],
+                &format!("({}, {})", chunk_pos.x, chunk_pos.y),
+                HUD_TEXT_COLOR,
+            );
+        }
+    }
+
+    /// Brings the meshes or textures of the chunks visible to a camera up to date and uploads the
+    /// settlements, returning the number of settlement instances. `size` is the size of the area
+    /// that the camera is drawn into.
+    fn prepare_tiles(
+        &mut self,
+        ui: &Ui,
+        world: &World,
+        camera: &Camera,
+        size: PhysicalSize<u32>,
+    ) -> u32 {
+        let visible_bounds = camera.visible_bounds(size);
+        let [bottom_left_chunk_pos, top_right_chunk_pos] = visible_chunk_range(camera, size);
+        let lod_level = lod_level(camera.zoom_multiplier());
+        match ui.render_settings.tile_render_mode {
+            TileRenderMode::Instances => self.chunk_meshes.update(
+                &self.device,
+                world,
+                bottom_left_chunk_pos,
+                top_right_chunk_pos,
+                lod_level,
+            ),
+            TileRenderMode::Textures => self.chunk_texture_renderer.prepare(
+                &self.device,
+                &self.queue,
+                world,
+                [bottom_left_chunk_pos, top_right_chunk_pos],
+                ui.render_settings.show_roads,
+                lod_level,
+            ),
+        }
+        self.overlay_renderer.prepare(
+            &self.device,
+            &self.queue,
+            world,
+            [bottom_left_chunk_pos, top_right_chunk_pos],
+            &ui.render_settings.overlays,
+            lod_level,
+        );
+
+        let settlement_instances = if ui.render_settings.show_settlements {
+            TileRenderInstance::vec_from_settlements(world, &visible_bounds)
+        } else {
+            vec![]
+        };
+        self.instance_buffer.write_data(
+            &self.queue,
+            &self.device,
+            bytemuck::cast_slice(&settlement_instances),
+        );
+
+        settlement_instances.len() as u32
+    }
+}
+
+/// The bottom left and top right chunks that are at least partly visible to a camera drawing into
+/// an area of the given size.
+fn visible_chunk_range(camera: &Camera, size: PhysicalSize<u32>) -> [ChunkPos; 2] {
+    let (bottom_left, top_right) = camera.visible_bounds(size);
+    [bottom_left.chunk(), top_right.chunk()]
+}
+
+/// Starts a render pass that draws to the whole of `view`, either clearing it first or drawing over
+/// what's already there.
+fn begin_render_pass<'pass>(
+    encoder: &'pass mut wgpu::CommandEncoder,
+    view: &'pass wgpu::TextureView,
+    load: wgpu::LoadOp<wgpu::Color>,
+) -> wgpu::RenderPass<'pass> {
+    encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+        label: Some("Render pass"),
+        color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+            view,
+            resolve_target: None,
+            ops: wgpu::Operations {
+                load,
+                store: wgpu::StoreOp::Store,
+            },
+        })],
+        depth_stencil_attachment: None,
+        occlusion_query_set: None,
+        timestamp_writes: None,
+    })
+}
+
+/// Draws the outline of a rectangle in the HUD, one pixel wide, given its top left and bottom right
+/// corners.
+fn draw_outline(hud: &mut Hud, top_left: [f32; 2], bottom_right: [f32; 2], color: [u8; 3]) {
+    let [left, top] = top_left;
+    let [right, bottom] = bottom_right;
+    let width = right - left;
+    let height = bottom - top;
+    hud.rect([left, top], [width, 1.0], color, 1.0);
+    hud.rect([left, bottom - 1.0], [width, 1.0], color, 1.0);
+    hud.rect([left, top], [1.0, height], color, 1.0);
+    hud.rect([right - 1.0, top], [1.0, height], color, 1.0);
+}
+
+/// Settings that control what gets drawn, which can be changed at runtime.
+// These are all independent toggles, so there's no better way to store them.
+#[expect(clippy::struct_excessive_bools)]
+pub struct RenderSettings {
+    pub show_roads: bool,
+    pub show_settlements: bool,
+    /// Whether tiles are drawn with the sprites from the tile atlas rather than flat colors.
+    pub show_sprites: bool,
+    pub tile_render_mode: TileRenderMode,
+    /// Whether lines are drawn between the tiles. They're hidden when zoomed far out regardless.
+    pub show_tile_grid: bool,
+    /// Whether lines are drawn between the chunks, labeled with the chunks' coordinates.
+    pub show_chunk_grid: bool,
+    /// The scalar fields that can be drawn on top of the terrain, and which one is shown.
+    pub overlays: OverlayRegistry,
+    /// Whether the minimap of the generated chunks is shown in the corner of the window.
+    pub show_minimap: bool,
+}
+
+impl Default for RenderSettings {
+    fn default() -> Self {
+        RenderSettings {
+            show_roads: true,
+            show_settlements: true,
+            show_sprites: true,
+            tile_render_mode: TileRenderMode::Instances,
+            show_tile_grid: false,
+            show_chunk_grid: false,
+            overlays: OverlayRegistry::default(),
+            show_minimap: true,
+        }
+    }
+}
+
+/// The ways that the tiles can be drawn. They should look the same, but perform differently.
+#[derive(Copy, Clone, PartialEq, Eq)]
+pub enum TileRenderMode {
+    /// Every tile is drawn as its own square, from instances that are cached per chunk.
+    Instances,
+    /// Every chunk is drawn as a single square, colored from a texture holding its tile IDs.
+    Textures,
+}
+
+impl TileRenderMode {
+    pub fn next(self) -> Self {
+        match self {
+            TileRenderMode::Instances => TileRenderMode::Textures,
+            TileRenderMode::Textures => TileRenderMode::Instances,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            TileRenderMode::Instances => "Instances",
+            TileRenderMode::Textures => "Textures",
+        }
     }
 }
 
@@ -333,6 +769,32 @@ impl Vertex {
 
 const TILE_INDICES: &[u16] = &[0, 1, 2, 0, 2, 3];
 
+/// The smallest width (in pixels on the screen) that a tile is drawn at before switching to a lower
+/// level of detail.
+const LOD_MIN_TILE_WIDTH_PX: f32 = 2.0;
+/// The smallest width (in pixels on the screen) that a chunk can be drawn at and still have its
+/// coordinates labeled.
+const MIN_CHUNK_LABEL_SPACING_PX: f32 = 160.0;
+/// The distance between a chunk label and the corner of its chunk.
+const CHUNK_LABEL_OFFSET_PX: f32 = 6.0;
+/// The distance between the HUD and the edges of the window.
+const HUD_MARGIN_PX: f32 = 8.0;
+pub const HUD_TEXT_COLOR: [u8; 3] = [255, 255, 255];
+/// The width of the border around the minimap.
+const MINIMAP_BORDER_WIDTH_PX: f32 = 1.0;
+const MINIMAP_BORDER_COLOR: [u8; 3] = [0, 0, 0];
+const VIEWPORT_BORDER_COLOR: [u8; 3] = [90, 90, 90];
+const ACTIVE_VIEWPORT_BORDER_COLOR: [u8; 3] = [255, 200, 60];
+/// The color that the window is cleared to before anything is drawn.
+const BACKGROUND_COLOR: wgpu::Color = wgpu::Color {
+    r: 0.1,
+    g: 0.2,
+    b: 0.3,
+    a: 1.0,
+};
+/// The slot in the global uniform that the HUD is drawn with. The viewports use the slots after it.
+const HUD_UNIFORM_SLOT: usize = 0;
+
 pub fn create_window(event_loop: &EventLoop<()>) -> Window {
     let window = WindowBuilder::new()
         .build(event_loop)
@@ -360,6 +822,16 @@ pub fn create_window(event_loop: &EventLoop<()>) -> Window {
     window
 }
 
+/// Chooses how much to simplify the chunks when drawing them at the given zoom, so that time isn't
+/// wasted drawing tiles that are too small to see.
+fn lod_level(camera_zoom: f32) -> u32 {
+    let tile_width_on_screen_px = Tile::width_px() as f32 * camera_zoom;
+    let level = (LOD_MIN_TILE_WIDTH_PX / tile_width_on_screen_px)
+        .log2()
+        .ceil();
+    level.clamp(0.0, Chunk::max_lod_level() as f32) as u32
+}
+
 /// Converts a color value from the standard srgb format (eg (0, 255, 127)) to the linear rgb format that wgpu expects.
 /// See [the Wikipedia article on sRGB](https://en.wikipedia.org/wiki/SRGB#From_sRGB_to_CIE_XYZ).
 #[must_use]
@@ -0,0 +1,235 @@
+use crate::display::{Hud, HUD_TEXT_COLOR};
+use crate::position::TilePos;
+use crate::world::World;
+
+/// The scalar fields that can be drawn on top of the terrain as colored overlays, and which one (if
+/// any) is being shown. Anything that can work out a number for a tile from the world can publish
+/// an overlay with `register`.
+pub struct OverlayRegistry {
+    overlays: Vec<Overlay>,
+    /// The index of the overlay being shown, or None if no overlay is shown.
+    active: Option<usize>,
+    /// How opaque the overlay is, from 0 (invisible) to 1 (hides the terrain completely).
+    opacity: f32,
+}
+
+/// A per-tile value that can be drawn as an overlay.
+pub struct Overlay {
+    pub name: &'static str,
+    pub color_map: ColorMap,
+    /// The values at the two ends of the color map. Values outside of this range are clamped.
+    pub range: [f32; 2],
+    /// Finds the value for a tile, or None if the tile has no value (for example, because its chunk
+    /// hasn't been generated yet).
+    field: OverlayField,
+}
+
+type OverlayField = Box<dyn Fn(&World, TilePos) -> Option<f32>>;
+
+impl Default for OverlayRegistry {
+    fn default() -> Self {
+        let mut registry = OverlayRegistry {
+            overlays: vec![],
+            active: None,
+            opacity: DEFAULT_OPACITY,
+        };
+
+        // Sea level is at 0, so it goes in the middle of the diverging color map.
+        registry.register(
+            "Elevation",
+            ColorMap::Diverging,
+            [-0.5, 0.5],
+            World::elevation,
+        );
+        registry.register(
+            "Raw noise",
+            ColorMap::Greyscale,
+            [-1.0, 1.0],
+            |world, coords| {
+                Some(world.elevation(coords)? - world.generator_params().elevation_offset)
+            },
+        );
+        registry.register(
+            "Population density",
+            ColorMap::Viridis,
+            [0.0, 1.0],
+            population_density,
+        );
+
+        registry
+    }
+}
+
+impl OverlayRegistry {
+    /// Adds an overlay that can be cycled to. `field` is called for every tile that the overlay is
+    /// drawn over, and should be quick.
+    pub fn register(
+        &mut self,
+        name: &'static str,
+        color_map: ColorMap,
+        range: [f32; 2],
+        field: impl Fn(&World, TilePos) -> Option<f32> + 'static,
+    ) {
+        self.overlays.push(Overlay {
+            name,
+            color_map,
+            range,
+            field: Box::new(field),
+        });
+    }
+
+    /// Shows the next overlay, or the previous one if `forwards` is false. Cycling past the last
+    /// overlay (or before the first) hides the overlays.
+    pub fn cycle(&mut self, forwards: bool) {
+        let count = self.overlays.len();
+        self.active = match (self.active, forwards) {
+            (None, true) if count > 0 => Some(0),
+            (None, false) if count > 0 => Some(count - 1),
+            (Some(index), true) if index + 1 < count => Some(index + 1),
+            (Some(index), false) if index > 0 => Some(index - 1),
+            _ => None,
+        };
+    }
+
+    /// The overlay being shown, if any.
+    pub fn active(&self) -> Option<&Overlay> {
+        self.overlays.get(self.active?)
+    }
+
+    /// The index of the overlay being shown, which changes whenever a different overlay is shown.
+    pub fn active_index(&self) -> Option<usize> {
+        self.active
+    }
+
+    pub fn opacity(&self) -> f32 {
+        self.opacity
+    }
+
+    /// Changes the opacity by the given amount, keeping it between 0 and 1.
+    pub fn change_opacity(&mut self, change: f32) {
+        self.opacity = (self.opacity + change).clamp(0.0, 1.0);
+    }
+
+    /// Draws a key for the overlay being shown, with `pos` being its top left corner. Does nothing
+    /// if no overlay is shown.
+    pub fn draw_legend(&self, hud: &mut Hud, pos: [f32; 2]) {
+        let Some(overlay) = self.active() else {
+            return;
+        };
+
+        hud.text(
+            pos,
+            &format!("{} ({:.0}% opacity)", overlay.name, self.opacity * 100.0),
+            HUD_TEXT_COLOR,
+        );
+
+        let bar_top = pos[1] + Hud::line_height();
+        let step_width = LEGEND_WIDTH_PX / LEGEND_STEPS as f32;
+        for step in 0..LEGEND_STEPS {
+            let fraction = step as f32 / (LEGEND_STEPS - 1) as f32;
+            hud.rect(
+                [pos[0] + step as f32 * step_width, bar_top],
+                [step_width, LEGEND_BAR_HEIGHT_PX],
+                overlay.color_map.color(fraction),
+                1.0,
+            );
+        }
+
+        let labels_top = bar_top + LEGEND_BAR_HEIGHT_PX + LEGEND_GAP_PX;
+        let [min, max] = overlay.range;
+        let max_label = format!("{max:.2}");
+        hud.text([pos[0], labels_top], &format!("{min:.2}"), HUD_TEXT_COLOR);
+        hud.text(
+            [
+                pos[0] + LEGEND_WIDTH_PX - Hud::text_width(&max_label),
+                labels_top,
+            ],
+            &max_label,
+            HUD_TEXT_COLOR,
+        );
+    }
+
+    /// The height of the legend drawn by `draw_legend`, in pixels.
+    pub fn legend_height() -> f32 {
+        Hud::line_height() + LEGEND_BAR_HEIGHT_PX + LEGEND_GAP_PX + Hud::text_height()
+    }
+}
+
+impl Overlay {
+    pub fn value(&self, world: &World, coords: TilePos) -> Option<f32> {
+        (self.field)(world, coords)
+    }
+
+    /// The color (in standard rgb) that a value is drawn as.
+    pub fn color(&self, value: f32) -> [u8; 3] {
+        let [min, max] = self.range;
+        self.color_map.color((value - min) / (max - min))
+    }
+}
+
+/// The ways that overlay values can be turned into colors.
+#[derive(Copy, Clone)]
+pub enum ColorMap {
+    /// Goes from dark purple through blue and green to yellow. Good for values that only go one way.
+    Viridis,
+    /// Goes from black to white.
+    Greyscale,
+    /// Goes from blue through white to red. Good for values that go either side of a middle point.
+    Diverging,
+}
+
+impl ColorMap {
+    /// The color (in standard rgb) at a point along the map, where 0 is the start and 1 is the end.
+    pub fn color(self, fraction: f32) -> [u8; 3] {
+        let stops: &[[u8; 3]] = match self {
+            ColorMap::Viridis => &[
+                [68, 1, 84],
+                [59, 82, 139],
+                [33, 145, 140],
+                [94, 201, 98],
+                [253, 231, 37],
+            ],
+            ColorMap::Greyscale => &[[0, 0, 0], [255, 255, 255]],
+            ColorMap::Diverging => &[[59, 76, 192], [221, 221, 221], [180, 4, 38]],
+        };
+
+        // Find the two stops that the point is between, and blend them.
+        let position = fraction.clamp(0.0, 1.0) * (stops.len() - 1) as f32;
+        let index = (position.floor() as usize).min(stops.len() - 2);
+        let blend = position - index as f32;
+        let [start, end] = [stops[index], stops[index + 1]];
+        [0, 1, 2].map(|channel| {
+            (f32::from(start[channel]) * (1.0 - blend) + f32::from(end[channel]) * blend).round()
+                as u8
+        })
+    }
+}
+
+/// The number of people per tile in the area around a tile, counting every settlement within
+/// `DENSITY_RADIUS` tiles.
+fn population_density(world: &World, coords: TilePos) -> Option<f32> {
+    world.tile(coords)?;
+
+    let radius_squared = DENSITY_RADIUS * DENSITY_RADIUS;
+    let population: u32 = world
+        .settlements
+        .iter()
+        .filter(|settlement| {
+            coords.distance_squared(settlement.position) <= i64::from(radius_squared)
+        })
+        .map(|settlement| settlement.population)
+        .sum();
+
+    let area = std::f32::consts::PI * radius_squared as f32;
+    Some(population as f32 / area)
+}
+
+const DEFAULT_OPACITY: f32 = 0.6;
+/// How far away a settlement can be from a tile and still count towards its population density.
+const DENSITY_RADIUS: i32 = 12;
+const LEGEND_WIDTH_PX: f32 = 256.0;
+const LEGEND_BAR_HEIGHT_PX: f32 = 16.0;
+/// The space between the color bar and the labels under it.
+const LEGEND_GAP_PX: f32 = 4.0;
+/// The number of rectangles that the color bar is made of.
+const LEGEND_STEPS: usize = 64;
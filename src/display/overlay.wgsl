@@ -0,0 +1,84 @@
+struct GlobalUniform {
+    window_size_px: vec2<f32>,
+    world_to_clip_scale: vec2<f32>,
+    world_to_clip_offset: vec2<f32>,
+    screen_to_world_scale: vec2<f32>,
+    screen_to_world_offset: vec2<f32>,
+    camera_zoom: f32,
+    time_s: f32,
+    sprites_enabled: u32,
+    // See shader.wgsl for why the padding looks like this.
+    padding: f32,
+    viewport_origin_px: vec2<f32>,
+    camera_chunk: vec2<i32>,
+    padding_2: vec2<f32>,
+};
+
+@group(0) @binding(0)
+var<uniform> global_uniform: GlobalUniform;
+
+struct OverlayUniform {
+    opacity: f32,
+    padding: vec3<f32>,
+};
+
+@group(1) @binding(0)
+var<uniform> overlay_uniform: OverlayUniform;
+
+// The overlay's color for each tile in the chunk, with an alpha of 0 for tiles without a value.
+// When the chunk is drawn at a lower level of detail, the texture is smaller and each texel covers a
+// square of tiles.
+@group(2) @binding(0)
+var overlay_colors: texture_2d<f32>;
+
+struct ChunkInstanceInput {
+    @location(0) chunk: vec2<i32>,
+};
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    // The position within the chunk, in tiles.
+    @location(0) tile_coords: vec2<f32>,
+};
+
+const CHUNK_SIDE_SIZE: f32 = 32.0;
+const TILE_WIDTH: f32 = 128.0;
+
+@vertex
+fn vert_main(
+    @builtin(vertex_index) vertex_index: u32,
+    instance: ChunkInstanceInput,
+) -> VertexOutput {
+    // Two triangles making up a square, with (0, 0) at the bottom left corner.
+    var corners = array<vec2<f32>, 6>(
+        vec2<f32>(0.0, 0.0),
+        vec2<f32>(1.0, 0.0),
+        vec2<f32>(1.0, 1.0),
+        vec2<f32>(0.0, 0.0),
+        vec2<f32>(1.0, 1.0),
+        vec2<f32>(0.0, 1.0),
+    );
+    let corner = corners[vertex_index];
+
+    var out: VertexOutput;
+    out.tile_coords = corner * CHUNK_SIDE_SIZE;
+
+    // See shader.wgsl for why the chunks are subtracted first.
+    let chunks_from_camera = vec2<f32>(instance.chunk - global_uniform.camera_chunk);
+    let world_pos = (chunks_from_camera + corner) * CHUNK_SIDE_SIZE * TILE_WIDTH;
+    out.clip_position = vec4<f32>(
+        world_pos * global_uniform.world_to_clip_scale + global_uniform.world_to_clip_offset,
+        0.0,
+        1.0,
+    );
+
+    return out;
+}
+
+@fragment
+fn frag_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let texture_size = vec2<i32>(textureDimensions(overlay_colors));
+    let texel_coords = in.tile_coords / CHUNK_SIDE_SIZE * vec2<f32>(texture_size);
+    let color = textureLoad(overlay_colors, clamp(vec2<i32>(floor(texel_coords)), vec2<i32>(0), texture_size - 1), 0);
+    return vec4<f32>(color.rgb, color.a * overlay_uniform.opacity);
+}
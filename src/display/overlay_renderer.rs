@@ -0,0 +1,333 @@
+use crate::display::chunk_texture_renderer::ChunkRenderInstance;
+use crate::display::instance_buffer::InstanceBuffer;
+use crate::display::overlay::{Overlay, OverlayRegistry};
+use crate::position::{ChunkPos, TilePos};
+use crate::world::{Chunk, World};
+use std::collections::{HashMap, HashSet};
+use web_time::{Duration, Instant};
+use wgpu::util::{BufferInitDescriptor, DeviceExt};
+use wgpu::{
+    BindGroup, BindGroupLayout, Buffer, Device, Queue, RenderPass, RenderPipeline, Texture,
+    TextureFormat,
+};
+
+/// Draws the active overlay from `OverlayRegistry` on top of the terrain. The overlay's colors
+/// are worked out on the CPU and uploaded into a small texture for each chunk, which is blended
+/// over the chunk as a single square.
+pub struct OverlayRenderer {
+    pipeline: RenderPipeline,
+    uniform_buffer: Buffer,
+    uniform_bind_group: BindGroup,
+    texture_bind_group_layout: BindGroupLayout,
+    /// The textures for each chunk and level of detail.
+    textures: HashMap<(ChunkPos, u32), OverlayTexture>,
+    /// The chunks (and their levels of detail) that were visible the last time `prepare` was
+    /// called, in drawing order.
+    visible_chunks: Vec<(ChunkPos, u32)>,
+    /// The positions of the visible chunks, in the same order as `visible_chunks`.
+    instance_buffer: InstanceBuffer,
+    /// The chunks that were visible in any viewport since the last call to `evict_unused`.
+    used_this_frame: HashSet<(ChunkPos, u32)>,
+}
+
+struct OverlayTexture {
+    texture: Texture,
+    bind_group: BindGroup,
+    /// The overlay that the texture was last filled with, as an index into the registry.
+    overlay_index: Option<usize>,
+    /// The revision of the chunk that the texture was last filled from.
+    revision: u64,
+    /// When the texture was last filled. Overlays can depend on more than just the chunk's tiles
+    /// (such as the settlements around it), so textures are refilled every so often regardless.
+    filled_at: Instant,
+    /// The level of detail that the texture was created for. Higher levels have smaller textures.
+    lod_level: u32,
+}
+
+impl OverlayRenderer {
+    pub fn new(
+        device: &Device,
+        surface_format: TextureFormat,
+        global_uniform_layout: &BindGroupLayout,
+    ) -> Self {
+        let uniform_buffer = device.create_buffer_init(&BufferInitDescriptor {
+            label: Some("Overlay uniform buffer"),
+            contents: bytemuck::cast_slice(&[OverlayUniform {
+                opacity: 0.0,
+                padding: [0.0; 3],
+            }]),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+        let uniform_bind_group_layout =
+            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                label: Some("Overlay uniform bind group layout"),
+                entries: &[wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                }],
+            });
+        let uniform_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Overlay uniform bind group"),
+            layout: &uniform_bind_group_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: uniform_buffer.as_entire_binding(),
+            }],
+        });
+
+        let texture_bind_group_layout =
+            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                label: Some("Overlay texture bind group layout"),
+                entries: &[wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: false },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                }],
+            });
+
+        let shader = device.create_shader_module(wgpu::include_wgsl!("overlay.wgsl"));
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Overlay pipeline layout"),
+            bind_group_layouts: &[
+                global_uniform_layout,
+                &uniform_bind_group_layout,
+                &texture_bind_group_layout,
+            ],
+            push_constant_ranges: &[],
+        });
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Overlay pipeline"),
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: "vert_main",
+                buffers: &[ChunkRenderInstance::layout()],
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: "frag_main",
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: surface_format,
+                    blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+            }),
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::TriangleList,
+                cull_mode: Some(wgpu::Face::Back),
+                ..Default::default()
+            },
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+            cache: None,
+        });
+
+        OverlayRenderer {
+            pipeline,
+            uniform_buffer,
+            uniform_bind_group,
+            texture_bind_group_layout,
+            textures: HashMap::new(),
+            visible_chunks: vec![],
+            instance_buffer: InstanceBuffer::new(device),
+            used_this_frame: HashSet::new(),
+        }
+    }
+
+    /// Makes sure that every generated chunk between the given bottom left and top right chunks
+    /// has an up-to-date texture of the active overlay, and remembers them as the chunks to draw.
+    /// Nothing will be drawn if no overlay is active.
+    pub fn prepare(
+        &mut self,
+        device: &Device,
+        queue: &Queue,
+        world: &World,
+        [bottom_left_chunk_pos, top_right_chunk_pos]: [ChunkPos; 2],
+        overlays: &OverlayRegistry,
+        lod_level: u32,
+    ) {
+        self.visible_chunks.clear();
+        let Some(overlay) = overlays.active() else {
+            return;
+        };
+
+        let uniform = OverlayUniform {
+            opacity: overlays.opacity(),
+            padding: [0.0; 3],
+        };
+        queue.write_buffer(&self.uniform_buffer, 0, bytemuck::cast_slice(&[uniform]));
+
+        let mut instances = vec![];
+        for chunk_coords in ChunkPos::rect(bottom_left_chunk_pos, top_right_chunk_pos) {
+            // Chunks that haven't been generated yet are left out, like in the terrain.
+            let Some(chunk) = world.chunks.get(&chunk_coords) else {
+                continue;
+            };
+
+            let key = (chunk_coords, lod_level);
+            let overlay_texture = self.textures.entry(key).or_insert_with(|| {
+                OverlayTexture::new(device, &self.texture_bind_group_layout, lod_level)
+            });
+            if overlay_texture.overlay_index != overlays.active_index()
+                || overlay_texture.revision != chunk.revision()
+                || overlay_texture.filled_at.elapsed() > OVERLAY_REFRESH_INTERVAL
+            {
+                overlay_texture.fill(queue, world, chunk_coords, overlay);
+                overlay_texture.overlay_index = overlays.active_index();
+                overlay_texture.revision = chunk.revision();
+            }
+
+            instances.push(ChunkRenderInstance {
+                chunk: [chunk_coords.x, chunk_coords.y],
+            });
+            self.visible_chunks.push(key);
+            self.used_this_frame.insert(key);
+        }
+
+        self.instance_buffer
+            .write_data(queue, device, bytemuck::cast_slice(&instances));
+    }
+
+    /// Throws away the textures of chunks that no viewport could see this frame, once there are too
+    /// many of them. This is called once per frame after every viewport has been prepared, so that
+    /// viewports looking at different places don't throw away each other's textures.
+    pub fn evict_unused(&mut self) {
+        if self.textures.len() > MAX_CACHED_TEXTURES {
+            self.textures
+                .retain(|key, _| self.used_this_frame.contains(key));
+        }
+        self.used_this_frame.clear();
+    }
+
+    /// Draws the chunks from the last call to `prepare`. The global uniform bind group must already
+    /// be set at index 0.
+    pub fn draw(&self, render_pass: &mut RenderPass) {
+        if self.visible_chunks.is_empty() {
+            return;
+        }
+
+        render_pass.set_pipeline(&self.pipeline);
+        render_pass.set_bind_group(1, &self.uniform_bind_group, &[]);
+        render_pass.set_vertex_buffer(0, self.instance_buffer.buffer.slice(..));
+
+        for (index, key) in self.visible_chunks.iter().enumerate() {
+            let index = index as u32;
+            render_pass.set_bind_group(2, &self.textures[key].bind_group, &[]);
+            render_pass.draw(0..6, index..index + 1);
+        }
+    }
+}
+
+impl OverlayTexture {
+    fn new(device: &Device, layout: &BindGroupLayout, lod_level: u32) -> Self {
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Overlay texture"),
+            size: Self::size(lod_level),
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            // The colors from the color maps are in standard rgb.
+            format: TextureFormat::Rgba8UnormSrgb,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+            view_formats: &[],
+        });
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Overlay texture bind group"),
+            layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: wgpu::BindingResource::TextureView(&view),
+            }],
+        });
+
+        OverlayTexture {
+            texture,
+            bind_group,
+            overlay_index: None,
+            // No chunk will ever get this far, so this makes sure that the texture gets filled.
+            revision: u64::MAX,
+            filled_at: Instant::now(),
+            lod_level,
+        }
+    }
+
+    fn size(lod_level: u32) -> wgpu::Extent3d {
+        let side_size = Chunk::side_size() as u32 >> lod_level;
+        wgpu::Extent3d {
+            width: side_size,
+            height: side_size,
+            depth_or_array_layers: 1,
+        }
+    }
+
+    /// Colors the texture with the overlay's values for the chunk. Above LOD level 0, each texel
+    /// covers a square of tiles and is colored from the tile in the middle of the square. Tiles
+    /// without a value are left transparent.
+    fn fill(&mut self, queue: &Queue, world: &World, chunk_coords: ChunkPos, overlay: &Overlay) {
+        let size = Self::size(self.lod_level);
+        let side_size = size.width as usize;
+        let block_size = 1 << self.lod_level;
+        let bottom_left_tile = chunk_coords.bottom_left_tile();
+
+        let mut texels = vec![0; side_size * side_size * 4];
+        for x in 0..side_size {
+            for y in 0..side_size {
+                let coords = bottom_left_tile
+                    + TilePos {
+                        x: x as i64 * block_size + block_size / 2,
+                        y: y as i64 * block_size + block_size / 2,
+                    };
+                if let Some(value) = overlay.value(world, coords) {
+                    let [r, g, b] = overlay.color(value);
+                    let index = (y * side_size + x) * 4;
+                    texels[index..index + 4].copy_from_slice(&[r, g, b, u8::MAX]);
+                }
+            }
+        }
+
+        queue.write_texture(
+            wgpu::ImageCopyTexture {
+                texture: &self.texture,
+                mip_level: 0,
+                origin: wgpu::Origin3d::ZERO,
+                aspect: wgpu::TextureAspect::All,
+            },
+            &texels,
+            wgpu::ImageDataLayout {
+                offset: 0,
+                bytes_per_row: Some(size.width * 4),
+                rows_per_image: Some(size.height),
+            },
+            size,
+        );
+        self.filled_at = Instant::now();
+    }
+}
+
+#[repr(C)]
+#[derive(Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+struct OverlayUniform {
+    opacity: f32,
+    // WASM requires buffer bindings to be 16-bit aligned, so we need to add some padding.
+    padding: [f32; 3],
+}
+
+/// How often the overlay textures are refilled, even if their chunks haven't changed.
+const OVERLAY_REFRESH_INTERVAL: Duration = Duration::from_secs(1);
+/// The number of overlay textures to keep before the ones that aren't visible are thrown away.
+const MAX_CACHED_TEXTURES: usize = 4096;
@@ -1,31 +1,125 @@
 struct GlobalUniform {
     window_size_px: vec2<f32>,
-    camera_pos: vec2<f32>,
+    // The camera's view transform (`ViewTransform` in camera.rs) as a scale and offset, both for
+    // going from the world to clip space and for going from the screen back to the world.
+    // Positions in the world are relative to the camera's chunk (camera_chunk) so that they stay
+    // precise far from the origin.
+    world_to_clip_scale: vec2<f32>,
+    world_to_clip_offset: vec2<f32>,
+    screen_to_world_scale: vec2<f32>,
+    screen_to_world_offset: vec2<f32>,
     camera_zoom: f32,
-    // Padding - WASM wants 16-byte alignment, but it doesn't like it when we're exactly 16-bit aligned
-    // for some reason. I don't know why, but it seems to want the shader uniform to be 16-bit aligned
-    // minus 4 bits with the actual data representation in Rust actually 16-bit aligned, with those 4
-    // bits present.
-    padding: vec2<f32>
+    time_s: f32,
+    // Nonzero if tiles should be drawn with their sprites.
+    sprites_enabled: u32,
+    // Padding - WASM wants uniforms to be 16-byte aligned, so this has to match the padding in
+    // GlobalUniformData.
+    padding: f32,
+    // The top left corner of the viewport being drawn, in pixels from the top left corner of the
+    // window.
+    viewport_origin_px: vec2<f32>,
+    camera_chunk: vec2<i32>,
+    padding_2: vec2<f32>,
 };
 
 @group(0) @binding(0)
 var<uniform> global_uniform: GlobalUniform;
 
+struct Sprite {
+    uv_position: vec2<f32>,
+    uv_size: vec2<f32>,
+    frame_count: u32,
+    // 0 means that the sprite isn't animated.
+    frame_length: f32,
+    // Nonzero if the tile has a sprite rather than just a flat color.
+    is_present: u32,
+    padding: u32,
+    // The tile's flat color in linear rgb, whether or not it has a sprite.
+    color: vec4<f32>,
+};
+
+struct SpriteTable {
+    // Indexed by tile ID.
+    sprites: array<Sprite, 8>,
+};
+
+@group(0) @binding(1)
+var tile_atlas: texture_2d<f32>;
+@group(0) @binding(2)
+var tile_atlas_sampler: sampler;
+@group(0) @binding(3)
+var<uniform> sprite_table: SpriteTable;
+
 struct VertexInput {
     @location(0) position: vec2<f32>,
 };
 
 struct TileInstanceInput {
+    // The position of the bottom left corner of the tile within its chunk.
     @location(1) position: vec2<f32>,
     @location(2) color: vec3<f32>,
+    @location(3) size: f32,
+    // The ID of the tile whose sprite should be drawn, or NO_SPRITE to use the color instead.
+    @location(4) sprite: u32,
+    // The IDs of the tiles that each corner should be drawn as, one byte per corner in the order
+    // given by corner_tiles in autotile.rs, or NO_CORNER_TILE to draw the corner normally.
+    @location(5) corner_tiles: u32,
+    @location(6) chunk: vec2<i32>,
 };
 
 struct VertexOutput {
     @builtin(position) clip_position: vec4<f32>,
     @location(0) color: vec3<f32>,
+    // The position within the tile, from (0, 0) at the bottom left corner to (1, 1) at the top right.
+    @location(1) uv_in_tile: vec2<f32>,
+    @location(2) @interpolate(flat) sprite: u32,
+    @location(3) @interpolate(flat) corner_tiles: u32,
 };
 
+const NO_SPRITE: u32 = 0xffffffffu;
+const NO_CORNER_TILE: u32 = 0xffu;
+
+// Whether the tile with the given ID should be drawn with its sprite.
+fn uses_sprite(tile_id: u32) -> bool {
+    return global_uniform.sprites_enabled != 0u && tile_id < 8u && sprite_table.sprites[tile_id].is_present != 0u;
+}
+
+// Finds the texture coordinates in the tile atlas for a point in a tile, where (0, 0) is the top
+// left corner of the tile and (1, 1) is the bottom right.
+fn sprite_uv(tile_id: u32, uv_in_tile: vec2<f32>) -> vec2<f32> {
+    let sprite = sprite_table.sprites[tile_id];
+    var frame = 0u;
+    if sprite.frame_length > 0.0 {
+        frame = u32(global_uniform.time_s / sprite.frame_length) % sprite.frame_count;
+    }
+    // Stay just inside the sprite so that the neighbouring sprite doesn't bleed in at the edges.
+    let clamped_uv = clamp(uv_in_tile, vec2<f32>(0.0), vec2<f32>(0.999));
+    return sprite.uv_position + vec2<f32>(f32(frame) * sprite.uv_size.x, 0.0) + clamped_uv * sprite.uv_size;
+}
+
+// Finds the color of a point in a tile with the given ID, from its sprite or its flat color.
+fn tile_color(tile_id: u32, uv_in_tile: vec2<f32>) -> vec3<f32> {
+    if uses_sprite(tile_id) {
+        return textureSampleLevel(tile_atlas, tile_atlas_sampler, sprite_uv(tile_id, uv_in_tile), 0.0).rgb;
+    }
+    return sprite_table.sprites[tile_id].color.rgb;
+}
+
+// Finds which corner of a tile a point is in, as an index into the corners in autotile.rs. The
+// point is relative to the center of the tile.
+fn corner_index(offset_from_center: vec2<f32>) -> u32 {
+    if offset_from_center.y >= 0.0 {
+        return select(1u, 0u, offset_from_center.x >= 0.0);
+    }
+    return select(2u, 3u, offset_from_center.x >= 0.0);
+}
+
+// Whether a point is in the rounded off part of a tile's corner, for drawing it as the neighbouring
+// terrain. The point is relative to the center of the tile.
+fn is_in_cut_corner(offset_from_center: vec2<f32>) -> bool {
+    return length(offset_from_center) > 0.5;
+}
+
 @vertex
 fn vert_main(
     model: VertexInput,
@@ -33,20 +127,41 @@ fn vert_main(
 ) -> VertexOutput {
     var out: VertexOutput;
     out.color = instance.color;
+    out.uv_in_tile = model.position.xy / 128.0;
+    out.sprite = instance.sprite;
+    out.corner_tiles = instance.corner_tiles;
 
-    var camera_coords: vec2<f32> = instance.position.xy + model.position.xy - global_uniform.camera_pos.xy;
-    camera_coords = camera_coords.xy * global_uniform.camera_zoom;
-
-    // Convert from pixel coordinates to normalized coordinates (-1 to 1).
-    camera_coords.x = camera_coords.x / global_uniform.window_size_px.x * 2;
-    camera_coords.y = camera_coords.y / global_uniform.window_size_px.y * 2;
+    // Scale the square around the center of the tile.
+    let tile_width = 128.0;
+    let chunk_width = tile_width * 32.0;
+    let vertex_offset = (model.position.xy - tile_width / 2.0) * instance.size + tile_width / 2.0;
 
-    out.clip_position = vec4<f32>(camera_coords, 0.0, 1.0);
+    // The chunks are subtracted as integers first, so that only the distance from the camera's chunk
+    // has to fit in an f32.
+    let chunks_from_camera = vec2<f32>(instance.chunk - global_uniform.camera_chunk);
+    let world_pos = chunks_from_camera * chunk_width + instance.position.xy + vertex_offset;
+    out.clip_position = vec4<f32>(
+        world_pos * global_uniform.world_to_clip_scale + global_uniform.world_to_clip_offset,
+        0.0,
+        1.0,
+    );
 
     return out;
 }
 
 @fragment
 fn frag_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    // The y axis points up in the world but down in the atlas.
+    let sprite_uv_in_tile = vec2<f32>(in.uv_in_tile.x, 1.0 - in.uv_in_tile.y);
+
+    let offset_from_center = in.uv_in_tile - 0.5;
+    let corner_tile = (in.corner_tiles >> (corner_index(offset_from_center) * 8u)) & 0xffu;
+    if corner_tile != NO_CORNER_TILE && is_in_cut_corner(offset_from_center) {
+        return vec4<f32>(tile_color(corner_tile, sprite_uv_in_tile), 1.0);
+    }
+
+    if in.sprite != NO_SPRITE && uses_sprite(in.sprite) {
+        return vec4<f32>(tile_color(in.sprite, sprite_uv_in_tile), 1.0);
+    }
     return vec4<f32>(in.color, 1.0);
 }
@@ -0,0 +1,71 @@
+struct GlobalUniform {
+    window_size_px: vec2<f32>,
+    world_to_clip_scale: vec2<f32>,
+    world_to_clip_offset: vec2<f32>,
+    screen_to_world_scale: vec2<f32>,
+    screen_to_world_offset: vec2<f32>,
+    camera_zoom: f32,
+    time_s: f32,
+    sprites_enabled: u32,
+    // See shader.wgsl for why the padding looks like this.
+    padding: f32,
+    viewport_origin_px: vec2<f32>,
+    camera_chunk: vec2<i32>,
+    padding_2: vec2<f32>,
+};
+
+@group(0) @binding(0)
+var<uniform> global_uniform: GlobalUniform;
+
+@group(1) @binding(0)
+var font_atlas: texture_2d<f32>;
+@group(1) @binding(1)
+var font_sampler: sampler;
+
+struct GlyphInstanceInput {
+    @location(0) position: vec2<f32>,
+    @location(1) size: vec2<f32>,
+    @location(2) uv_position: vec2<f32>,
+    @location(3) uv_size: vec2<f32>,
+    @location(4) color: vec4<f32>,
+};
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+    @location(1) color: vec4<f32>,
+};
+
+@vertex
+fn vert_main(
+    @builtin(vertex_index) vertex_index: u32,
+    instance: GlyphInstanceInput,
+) -> VertexOutput {
+    // Two triangles making up a rectangle, with (0, 0) at the top left corner.
+    var corners = array<vec2<f32>, 6>(
+        vec2<f32>(0.0, 0.0),
+        vec2<f32>(0.0, 1.0),
+        vec2<f32>(1.0, 1.0),
+        vec2<f32>(0.0, 0.0),
+        vec2<f32>(1.0, 1.0),
+        vec2<f32>(1.0, 0.0),
+    );
+    let corner = corners[vertex_index];
+
+    var out: VertexOutput;
+    out.uv = instance.uv_position + corner * instance.uv_size;
+    out.color = instance.color;
+
+    // Convert from pixels from the top left corner to normalized coordinates (-1 to 1, with y pointing up).
+    let screen_coords = instance.position + corner * instance.size;
+    let normalized_coords = screen_coords / global_uniform.window_size_px * 2.0 - 1.0;
+    out.clip_position = vec4<f32>(normalized_coords.x, -normalized_coords.y, 0.0, 1.0);
+
+    return out;
+}
+
+@fragment
+fn frag_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let coverage = textureSample(font_atlas, font_sampler, in.uv).r;
+    return vec4<f32>(in.color.rgb, in.color.a * coverage);
+}
@@ -0,0 +1,238 @@
+use crate::display::font::{characters, glyph, GLYPH_HEIGHT, GLYPH_WIDTH};
+use crate::display::glyph_render_instance::GlyphRenderInstance;
+use crate::display::hud::Hud;
+use crate::display::instance_buffer::InstanceBuffer;
+use wgpu::{BindGroup, Device, Queue, RenderPass, RenderPipeline, TextureFormat};
+
+/// Draws the HUD on top of the world. Glyphs are drawn as textured rectangles, using a texture
+/// atlas that is generated from the bitmap font at startup.
+pub struct TextRenderer {
+    pipeline: RenderPipeline,
+    atlas_bind_group: BindGroup,
+    instance_buffer: InstanceBuffer,
+    instance_count: u32,
+}
+
+impl TextRenderer {
+    pub fn new(
+        device: &Device,
+        queue: &Queue,
+        surface_format: TextureFormat,
+        global_uniform_layout: &wgpu::BindGroupLayout,
+    ) -> Self {
+        let (atlas_bind_group, atlas_bind_group_layout) = create_atlas_bind_group(device, queue);
+
+        let shader = device.create_shader_module(wgpu::include_wgsl!("text.wgsl"));
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Text pipeline layout"),
+            bind_group_layouts: &[global_uniform_layout, &atlas_bind_group_layout],
+            push_constant_ranges: &[],
+        });
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Text pipeline"),
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: "vert_main",
+                buffers: &[GlyphRenderInstance::layout()],
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: "frag_main",
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: surface_format,
+                    blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+            }),
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::TriangleList,
+                cull_mode: None,
+                ..Default::default()
+            },
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+            cache: None,
+        });
+
+        TextRenderer {
+            pipeline,
+            atlas_bind_group,
+            instance_buffer: InstanceBuffer::new(device),
+            instance_count: 0,
+        }
+    }
+
+    /// Uploads the contents of the HUD so that they can be drawn.
+    pub fn prepare(&mut self, queue: &Queue, device: &Device, hud: &Hud) {
+        let instances = hud.glyph_instances();
+        self.instance_buffer
+            .write_data(queue, device, bytemuck::cast_slice(instances));
+        self.instance_count = instances.len() as u32;
+    }
+
+    /// Draws whatever was last passed to `prepare`. The global uniform bind group must already be
+    /// set at index 0.
+    pub fn draw(&self, render_pass: &mut RenderPass) {
+        if self.instance_count == 0 {
+            return;
+        }
+
+        render_pass.set_pipeline(&self.pipeline);
+        render_pass.set_bind_group(1, &self.atlas_bind_group, &[]);
+        render_pass.set_vertex_buffer(0, self.instance_buffer.buffer.slice(..));
+        render_pass.draw(0..6, 0..self.instance_count);
+    }
+}
+
+/// Creates the font atlas texture and the bind group that the text shader reads it through.
+fn create_atlas_bind_group(device: &Device, queue: &Queue) -> (BindGroup, wgpu::BindGroupLayout) {
+    let atlas_size = wgpu::Extent3d {
+        width: ATLAS_COLUMNS * GLYPH_WIDTH,
+        height: ATLAS_ROWS * GLYPH_HEIGHT,
+        depth_or_array_layers: 1,
+    };
+    let atlas = device.create_texture(&wgpu::TextureDescriptor {
+        label: Some("Font atlas"),
+        size: atlas_size,
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D2,
+        format: TextureFormat::R8Unorm,
+        usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+        view_formats: &[],
+    });
+    queue.write_texture(
+        wgpu::ImageCopyTexture {
+            texture: &atlas,
+            mip_level: 0,
+            origin: wgpu::Origin3d::ZERO,
+            aspect: wgpu::TextureAspect::All,
+        },
+        &build_atlas_pixels(),
+        wgpu::ImageDataLayout {
+            offset: 0,
+            bytes_per_row: Some(atlas_size.width),
+            rows_per_image: Some(atlas_size.height),
+        },
+        atlas_size,
+    );
+
+    let atlas_view = atlas.create_view(&wgpu::TextureViewDescriptor::default());
+    // Nearest neighbour filtering keeps the glyphs crisp when they're scaled up.
+    let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+        label: Some("Font atlas sampler"),
+        mag_filter: wgpu::FilterMode::Nearest,
+        min_filter: wgpu::FilterMode::Nearest,
+        ..Default::default()
+    });
+
+    let atlas_bind_group_layout =
+        device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Font atlas bind group layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+            ],
+        });
+    let atlas_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+        label: Some("Font atlas bind group"),
+        layout: &atlas_bind_group_layout,
+        entries: &[
+            wgpu::BindGroupEntry {
+                binding: 0,
+                resource: wgpu::BindingResource::TextureView(&atlas_view),
+            },
+            wgpu::BindGroupEntry {
+                binding: 1,
+                resource: wgpu::BindingResource::Sampler(&sampler),
+            },
+        ],
+    });
+
+    (atlas_bind_group, atlas_bind_group_layout)
+}
+
+/// Returns the position and size of a character's glyph in the font atlas, in texture coordinates.
+/// Characters that aren't in the font are replaced by a question mark.
+pub fn glyph_uv(character: char) -> ([f32; 2], [f32; 2]) {
+    let index = characters()
+        .position(|font_character| font_character == character)
+        .or_else(|| characters().position(|font_character| font_character == '?'))
+        .unwrap_or_default();
+
+    cell_uv(index as u32)
+}
+
+/// Returns the position and size of a fully opaque cell in the font atlas, in texture coordinates.
+/// This is used to draw solid rectangles with the text pipeline.
+pub fn solid_uv() -> ([f32; 2], [f32; 2]) {
+    cell_uv(SOLID_CELL_INDEX)
+}
+
+fn cell_uv(index: u32) -> ([f32; 2], [f32; 2]) {
+    let column = index % ATLAS_COLUMNS;
+    let row = index / ATLAS_COLUMNS;
+
+    (
+        [
+            column as f32 / ATLAS_COLUMNS as f32,
+            row as f32 / ATLAS_ROWS as f32,
+        ],
+        [1.0 / ATLAS_COLUMNS as f32, 1.0 / ATLAS_ROWS as f32],
+    )
+}
+
+/// Renders every glyph in the font (plus the solid cell) into a single-channel image.
+fn build_atlas_pixels() -> Vec<u8> {
+    let atlas_width = ATLAS_COLUMNS * GLYPH_WIDTH;
+    let mut pixels = vec![0; (atlas_width * ATLAS_ROWS * GLYPH_HEIGHT) as usize];
+
+    let mut draw_cell = |index: u32, rows: &[u8; GLYPH_HEIGHT as usize]| {
+        let cell_x = index % ATLAS_COLUMNS * GLYPH_WIDTH;
+        let cell_y = index / ATLAS_COLUMNS * GLYPH_HEIGHT;
+
+        for (row_index, row) in rows.iter().enumerate() {
+            for column in 0..GLYPH_WIDTH {
+                if row & (0x80 >> column) != 0 {
+                    let x = cell_x + column;
+                    let y = cell_y + row_index as u32;
+                    pixels[(y * atlas_width + x) as usize] = u8::MAX;
+                }
+            }
+        }
+    };
+
+    for (index, character) in characters().enumerate() {
+        if let Some(rows) = glyph(character) {
+            draw_cell(index as u32, rows);
+        }
+    }
+    draw_cell(SOLID_CELL_INDEX, &[u8::MAX; GLYPH_HEIGHT as usize]);
+
+    pixels
+}
+
+/// The number of cells in each row of the font atlas.
+const ATLAS_COLUMNS: u32 = 16;
+/// The number of rows of cells in the font atlas. There needs to be room for every glyph plus the solid cell.
+const ATLAS_ROWS: u32 = 6;
+/// The index of the fully opaque cell in the font atlas, which comes right after the last glyph.
+const SOLID_CELL_INDEX: u32 = 95;
@@ -0,0 +1,277 @@
+use crate::display::get_linear_rgb;
+use crate::world::Tile;
+use serde::Deserialize;
+use std::collections::HashMap;
+use wgpu::util::{BufferInitDescriptor, DeviceExt};
+use wgpu::{Buffer, Device, Queue, Sampler, TextureView};
+
+/// The sprites that tiles are drawn with, all packed into a single texture. The atlas is loaded
+/// from a PNG and a manifest describing where each tile's sprite is. Tiles without a sprite (or
+/// every tile, if the atlas couldn't be loaded) are drawn with their flat colors instead.
+pub struct TileAtlas {
+    pub view: TextureView,
+    pub sampler: Sampler,
+    /// Holds a `SpriteTable` describing where each tile's sprite is in the atlas.
+    pub sprite_table_buffer: Buffer,
+}
+
+impl TileAtlas {
+    pub fn load(device: &Device, queue: &Queue) -> Self {
+        let (image, sprite_table) = match read_atlas_files().and_then(|(png, manifest)| {
+            let image = AtlasImage::decode(&png)?;
+            let sprite_table = SpriteTable::from_manifest(&manifest, &image)?;
+            Ok((image, sprite_table))
+        }) {
+            Ok(atlas) => atlas,
+            Err(error) => {
+                log::warn!("Couldn't load the tile atlas, so tiles will be flat colors: {error}");
+                (AtlasImage::blank(), SpriteTable::empty())
+            }
+        };
+
+        let size = wgpu::Extent3d {
+            width: image.width,
+            height: image.height,
+            depth_or_array_layers: 1,
+        };
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Tile atlas"),
+            size,
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: wgpu::TextureFormat::Rgba8UnormSrgb,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+            view_formats: &[],
+        });
+        queue.write_texture(
+            wgpu::ImageCopyTexture {
+                texture: &texture,
+                mip_level: 0,
+                origin: wgpu::Origin3d::ZERO,
+                aspect: wgpu::TextureAspect::All,
+            },
+            &image.pixels,
+            wgpu::ImageDataLayout {
+                offset: 0,
+                bytes_per_row: Some(image.width * 4),
+                rows_per_image: Some(image.height),
+            },
+            size,
+        );
+
+        // The sprites are pixel art, so they should stay crisp when they're scaled up.
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("Tile atlas sampler"),
+            mag_filter: wgpu::FilterMode::Nearest,
+            min_filter: wgpu::FilterMode::Nearest,
+            ..Default::default()
+        });
+
+        let sprite_table_buffer = device.create_buffer_init(&BufferInitDescriptor {
+            label: Some("Sprite table buffer"),
+            contents: bytemuck::cast_slice(&[sprite_table]),
+            usage: wgpu::BufferUsages::UNIFORM,
+        });
+
+        TileAtlas {
+            view: texture.create_view(&wgpu::TextureViewDescriptor::default()),
+            sampler,
+            sprite_table_buffer,
+        }
+    }
+}
+
+/// Reads the atlas image and manifest from the assets directory.
+#[cfg(not(target_arch = "wasm32"))]
+fn read_atlas_files() -> Result<(Vec<u8>, String), String> {
+    let png = std::fs::read(ATLAS_IMAGE_PATH)
+        .map_err(|error| format!("couldn't read {ATLAS_IMAGE_PATH}: {error}"))?;
+    let manifest = std::fs::read_to_string(ATLAS_MANIFEST_PATH)
+        .map_err(|error| format!("couldn't read {ATLAS_MANIFEST_PATH}: {error}"))?;
+    Ok((png, manifest))
+}
+
+/// There's no file system on the web, so the atlas is built into the program instead.
+#[cfg(target_arch = "wasm32")]
+#[expect(clippy::unnecessary_wraps)]
+fn read_atlas_files() -> Result<(Vec<u8>, String), String> {
+    Ok((
+        include_bytes!("../../assets/tiles.png").to_vec(),
+        include_str!("../../assets/tiles.toml").to_string(),
+    ))
+}
+
+/// The pixels of the atlas in 8-bit RGBA.
+struct AtlasImage {
+    width: u32,
+    height: u32,
+    pixels: Vec<u8>,
+}
+
+impl AtlasImage {
+    fn decode(png_data: &[u8]) -> Result<Self, String> {
+        let mut decoder = png::Decoder::new(png_data);
+        // Expand palettes and low bit depths so that every image ends up as 8-bit channels.
+        decoder.set_transformations(png::Transformations::normalize_to_color8());
+        let mut reader = decoder
+            .read_info()
+            .map_err(|error| format!("invalid atlas image: {error}"))?;
+        let mut buffer = vec![0; reader.output_buffer_size()];
+        let info = reader
+            .next_frame(&mut buffer)
+            .map_err(|error| format!("invalid atlas image: {error}"))?;
+        buffer.truncate(info.buffer_size());
+
+        let pixels = match info.color_type {
+            png::ColorType::Rgba => buffer,
+            png::ColorType::Rgb => buffer
+                .chunks_exact(3)
+                .flat_map(|rgb| [rgb[0], rgb[1], rgb[2], u8::MAX])
+                .collect(),
+            png::ColorType::GrayscaleAlpha => buffer
+                .chunks_exact(2)
+                .flat_map(|gray_alpha| [gray_alpha[0], gray_alpha[0], gray_alpha[0], gray_alpha[1]])
+                .collect(),
+            png::ColorType::Grayscale => buffer
+                .iter()
+                .flat_map(|&gray| [gray, gray, gray, u8::MAX])
+                .collect(),
+            png::ColorType::Indexed => {
+                return Err("indexed atlas images aren't supported".to_string());
+            }
+        };
+
+        Ok(AtlasImage {
+            width: info.width,
+            height: info.height,
+            pixels,
+        })
+    }
+
+    /// A single white pixel, for when there are no sprites.
+    fn blank() -> Self {
+        AtlasImage {
+            width: 1,
+            height: 1,
+            pixels: vec![u8::MAX; 4],
+        }
+    }
+}
+
+/// The contents of the atlas manifest file.
+#[derive(Deserialize)]
+struct AtlasManifest {
+    /// The width and height of every sprite, in pixels.
+    sprite_size: u32,
+    /// The sprite for each tile, keyed by the name of the tile.
+    tiles: HashMap<String, SpriteManifest>,
+}
+
+#[derive(Deserialize)]
+struct SpriteManifest {
+    /// The top left corner of the sprite's first frame, in pixels.
+    position: [u32; 2],
+    /// The number of frames in the sprite's animation. Frames go from left to right.
+    #[serde(default = "default_frame_count")]
+    frames: u32,
+    /// The number of seconds that each frame is shown for.
+    #[serde(default)]
+    frame_length: f32,
+}
+
+fn default_frame_count() -> u32 {
+    1
+}
+
+/// Where each tile's sprite is in the atlas, indexed by `Tile::id`. This has the same layout as
+/// the sprite table in the tile shaders.
+#[repr(C)]
+#[derive(Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+struct SpriteTable {
+    sprites: [Sprite; SPRITE_TABLE_SIZE],
+}
+
+#[repr(C)]
+#[derive(Copy, Clone, Default, bytemuck::Pod, bytemuck::Zeroable)]
+struct Sprite {
+    /// The top left corner of the first frame, in texture coordinates.
+    uv_position: [f32; 2],
+    /// The size of a frame, in texture coordinates.
+    uv_size: [f32; 2],
+    frame_count: u32,
+    /// The number of seconds that each frame is shown for. 0 means the sprite isn't animated.
+    frame_length: f32,
+    /// 1 if the tile has a sprite, or 0 if it should be drawn with its flat color instead.
+    is_present: u32,
+    padding: u32,
+    /// The tile's flat color in linear rgb. This is filled in whether or not the tile has a
+    /// sprite, so that the shaders can draw the corners of neighbouring tiles.
+    color: [f32; 4],
+}
+
+impl SpriteTable {
+    fn empty() -> Self {
+        let mut sprites = [Sprite::default(); SPRITE_TABLE_SIZE];
+        for (id, sprite) in (0..).zip(&mut sprites) {
+            if let Some(tile) = Tile::from_id(id) {
+                let [red, green, blue] = get_linear_rgb(tile.color());
+                sprite.color = [red, green, blue, 1.0];
+            }
+        }
+        SpriteTable { sprites }
+    }
+
+    fn from_manifest(manifest_text: &str, image: &AtlasImage) -> Result<Self, String> {
+        let manifest: AtlasManifest = toml::from_str(manifest_text)
+            .map_err(|error| format!("invalid atlas manifest: {error}"))?;
+
+        let mut table = SpriteTable::empty();
+        for (name, sprite) in &manifest.tiles {
+            let tile = (0..SPRITE_TABLE_SIZE as u8)
+                .filter_map(Tile::from_id)
+                .find(|tile| format!("{tile:?}") == *name)
+                .ok_or_else(|| {
+                    format!("the atlas manifest has a sprite for an unknown tile, {name}")
+                })?;
+
+            let [x, y] = sprite.position;
+            let frame_count = sprite.frames.max(1);
+            if x + manifest.sprite_size * frame_count > image.width
+                || y + manifest.sprite_size > image.height
+            {
+                return Err(format!(
+                    "the sprite for {name} doesn't fit in the atlas image"
+                ));
+            }
+
+            let sprite_entry = &mut table.sprites[tile.id() as usize];
+            *sprite_entry = Sprite {
+                uv_position: [
+                    x as f32 / image.width as f32,
+                    y as f32 / image.height as f32,
+                ],
+                uv_size: [
+                    manifest.sprite_size as f32 / image.width as f32,
+                    manifest.sprite_size as f32 / image.height as f32,
+                ],
+                frame_count,
+                frame_length: sprite.frame_length.max(0.0),
+                is_present: 1,
+                padding: 0,
+                color: sprite_entry.color,
+            };
+        }
+
+        Ok(table)
+    }
+}
+
+/// The number of entries in the sprite table. This must match the array length in the tile
+/// shaders, and be more than the highest tile ID.
+const SPRITE_TABLE_SIZE: usize = 8;
+/// Where the atlas is loaded from, relative to the working directory.
+#[cfg(not(target_arch = "wasm32"))]
+const ATLAS_IMAGE_PATH: &str = "assets/tiles.png";
+#[cfg(not(target_arch = "wasm32"))]
+const ATLAS_MANIFEST_PATH: &str = "assets/tiles.toml";
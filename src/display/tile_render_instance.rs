@@ -1,15 +1,29 @@
+use crate::display::autotile;
 use crate::display::get_linear_rgb;
-use crate::position::{IntChunkCoordinates, Position, PositionMode};
-use crate::world::{Tile, World};
+use crate::position::{ChunkPos, PixelPos, TileIndex};
+use crate::world::{Chunk, Tile, World};
 use wgpu::{BufferAddress, VertexAttribute, VertexBufferLayout, VertexFormat, VertexStepMode};
 
 #[repr(C)]
 #[derive(Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
 pub struct TileRenderInstance {
-    /// The position of the center of the tile (x/y) in pixel coordinates.
+    /// The position of the bottom left corner of the tile (x/y) in pixels from the bottom left
+    /// corner of its chunk.
     position: [f32; 2],
     /// The color of the tile in linear rgb.
     color: [f32; 3],
+    /// The width of the square to draw, as a fraction of the width of a tile. The square stays
+    /// centered on the tile regardless of its size.
+    size: f32,
+    /// The ID of the tile whose sprite should be drawn, or `NO_SPRITE` to draw a flat color.
+    sprite: u32,
+    /// The IDs of the tiles that the corners of the square should be drawn as, one byte per
+    /// corner in the order of `autotile::corner_tiles`. `NO_CORNER_TILE` means the corner is drawn
+    /// normally.
+    corner_tiles: u32,
+    /// The coordinates of the tile's chunk. These are integers so that the shader can work out how
+    /// far away the tile is from the camera exactly, however far the chunk is from the origin.
+    chunk: [i32; 2],
 }
 
 impl TileRenderInstance {
@@ -28,68 +42,216 @@ impl TileRenderInstance {
                     shader_location: 2,
                     format: VertexFormat::Float32x3,
                 },
+                VertexAttribute {
+                    offset: size_of::<[f32; 5]>() as BufferAddress,
+                    shader_location: 3,
+                    format: VertexFormat::Float32,
+                },
+                VertexAttribute {
+                    offset: size_of::<[f32; 6]>() as BufferAddress,
+                    shader_location: 4,
+                    format: VertexFormat::Uint32,
+                },
+                VertexAttribute {
+                    offset: size_of::<[f32; 7]>() as BufferAddress,
+                    shader_location: 5,
+                    format: VertexFormat::Uint32,
+                },
+                VertexAttribute {
+                    offset: size_of::<[f32; 8]>() as BufferAddress,
+                    shader_location: 6,
+                    format: VertexFormat::Sint32x2,
+                },
             ],
         }
     }
 
-    pub fn vec_from_world(
+    /// Creates the instances for a single chunk: one for every tile, followed by one for every
+    /// road. Also returns the number of tile instances, so that the roads can be left out when
+    /// drawing.
+    ///
+    /// Above LOD level 0, the chunk is drawn from its LOD summary instead, without any roads or
+    /// autotiling.
+    pub fn vec_from_chunk(
         world: &World,
-        screen_center_pos: &Position,
-        screen_width: i32,
-        screen_height: i32,
-        camera_zoom: f32,
-    ) -> Vec<TileRenderInstance> {
+        chunk_coords: ChunkPos,
+        chunk: &Chunk,
+        lod_level: u32,
+    ) -> (Vec<TileRenderInstance>, usize) {
+        if lod_level > 0 {
+            let instances = Self::vec_from_lod_summary(chunk_coords, chunk, lod_level);
+            let tile_instance_count = instances.len();
+            return (instances, tile_instance_count);
+        }
+
         let mut tile_render_instances = vec![];
 
-        let screen_tile_width =
-            f64::from(screen_width) / f64::from(camera_zoom) / f64::from(Tile::width_px());
-        let screen_tile_height =
-            f64::from(screen_height) / f64::from(camera_zoom) / f64::from(Tile::width_px());
-
-        let screen_left_edge = screen_center_pos.x(PositionMode::Tiles) - (screen_tile_width / 2.0);
-        let screen_right_edge =
-            screen_center_pos.x(PositionMode::Tiles) + (screen_tile_width / 2.0);
-        let screen_bottom_edge =
-            screen_center_pos.y(PositionMode::Tiles) - (screen_tile_height / 2.0);
-        let screen_top_edge = screen_center_pos.y(PositionMode::Tiles) + (screen_tile_height / 2.0);
-
-        let bottom_left_chunk_pos =
-            Position::new(screen_left_edge, screen_bottom_edge, PositionMode::Tiles)
-                .into_int_chunk_coords();
-        let top_right_chunk_pos =
-            Position::new(screen_right_edge, screen_top_edge, PositionMode::Tiles)
-                .into_int_chunk_coords();
+        let tile_width_px = Tile::width_px() as f32;
+        let tile_position = |x_within_chunk: usize, y_within_chunk: usize| {
+            [
+                x_within_chunk as f32 * tile_width_px,
+                y_within_chunk as f32 * tile_width_px,
+            ]
+        };
+        let instance_chunk = [chunk_coords.x, chunk_coords.y];
+
+        for (x_within_chunk, column) in chunk.tiles.iter().enumerate() {
+            for (y_within_chunk, tile) in column.iter().enumerate() {
+                let coords = chunk_coords.tile(TileIndex {
+                    x: x_within_chunk,
+                    y: y_within_chunk,
+                });
+                let corner_tiles = autotile::corner_tiles(world, coords);
+                tile_render_instances.push(TileRenderInstance {
+                    position: tile_position(x_within_chunk, y_within_chunk),
+                    color: get_linear_rgb(tile.color()),
+                    size: 1.0,
+                    sprite: u32::from(tile.id()),
+                    corner_tiles: u32::from_le_bytes(
+                        corner_tiles.map(|tile| tile.map_or(NO_CORNER_TILE, Tile::id)),
+                    ),
+                    chunk: instance_chunk,
+                });
+            }
+        }
+        let tile_instance_count = tile_render_instances.len();
+
+        // Roads are drawn after (and therefore above) the terrain.
+        let road_color = get_linear_rgb(ROAD_COLOR);
+        for (x_within_chunk, column) in chunk.roads.iter().enumerate() {
+            for (y_within_chunk, _) in column.iter().enumerate().filter(|(_, &road)| road) {
+                tile_render_instances.push(TileRenderInstance {
+                    position: tile_position(x_within_chunk, y_within_chunk),
+                    color: road_color,
+                    size: ROAD_SIZE,
+                    sprite: NO_SPRITE,
+                    corner_tiles: NO_CORNER_TILES,
+                    chunk: instance_chunk,
+                });
+            }
+        }
+
+        (tile_render_instances, tile_instance_count)
+    }
 
+    /// Creates one instance for every tile in the chunk's LOD summary at the given level.
+    fn vec_from_lod_summary(
+        chunk_coords: ChunkPos,
+        chunk: &Chunk,
+        lod_level: u32,
+    ) -> Vec<TileRenderInstance> {
+        let summary = chunk.lod_summary(lod_level);
+        let block_size = 1 << lod_level;
         let tile_width_px = f64::from(Tile::width_px());
 
-        for chunk_x in bottom_left_chunk_pos.x..=top_right_chunk_pos.x {
-            for chunk_y in bottom_left_chunk_pos.y..=top_right_chunk_pos.y {
-                if let Some(chunk) = &world.chunks.get(&IntChunkCoordinates {
-                    x: chunk_x,
-                    y: chunk_y,
-                }) {
-                    let chunk_pos =
-                        Position::new(f64::from(chunk_x), f64::from(chunk_y), PositionMode::Chunks);
-
-                    for (x_within_chunk, column) in chunk.tiles.iter().enumerate() {
-                        for (y_within_chunk, tile) in column.iter().enumerate() {
-                            tile_render_instances.push(TileRenderInstance {
-                                position: [
-                                    ((x_within_chunk as f64 + chunk_pos.x(PositionMode::Tiles))
-                                        * tile_width_px) as f32,
-                                    ((y_within_chunk as f64 + chunk_pos.y(PositionMode::Tiles))
-                                        * tile_width_px) as f32,
-                                ],
-                                color: get_linear_rgb(tile.color()),
-                            });
-                        }
-                    }
-                } else {
-                    println!("Tried to render ungenerated chunk!");
-                }
+        let mut tile_render_instances = vec![];
+        for x in 0..summary.side_size() {
+            for y in 0..summary.side_size() {
+                // Instances are scaled around the center of the tile at their position, so the
+                // position has to be moved for the block to cover the right tiles.
+                let center_offset = (block_size - 1) as f64 / 2.0;
+                tile_render_instances.push(TileRenderInstance {
+                    position: [
+                        (((x * block_size) as f64 + center_offset) * tile_width_px) as f32,
+                        (((y * block_size) as f64 + center_offset) * tile_width_px) as f32,
+                    ],
+                    color: get_linear_rgb(summary.tile(x, y).color()),
+                    size: block_size as f32,
+                    // Sprites would be too small to see at this level of detail anyway.
+                    sprite: NO_SPRITE,
+                    corner_tiles: NO_CORNER_TILES,
+                    chunk: [chunk_coords.x, chunk_coords.y],
+                });
             }
         }
 
         tile_render_instances
     }
+
+    /// Creates instances for every settlement that could be visible between the given bottom left
+    /// and top right corners of the screen, as found by `Camera::visible_bounds`.
+    pub fn vec_from_settlements(
+        world: &World,
+        (bottom_left, top_right): &(PixelPos, PixelPos),
+    ) -> Vec<TileRenderInstance> {
+        let screen_left_edge = bottom_left.tile_x();
+        let screen_bottom_edge = bottom_left.tile_y();
+        let screen_right_edge = top_right.tile_x();
+        let screen_top_edge = top_right.tile_y();
+        let mut tile_render_instances = vec![];
+        let tile_width_px = Tile::width_px() as f32;
+        let settlement_color = get_linear_rgb(SETTLEMENT_COLOR);
+        for settlement in &world.settlements {
+            let settlement_pos = settlement.position;
+            if (settlement_pos.x as f64) < screen_left_edge - MAX_SETTLEMENT_SIZE
+                || settlement_pos.x as f64 > screen_right_edge + MAX_SETTLEMENT_SIZE
+                || (settlement_pos.y as f64) < screen_bottom_edge - MAX_SETTLEMENT_SIZE
+                || settlement_pos.y as f64 > screen_top_edge + MAX_SETTLEMENT_SIZE
+            {
+                continue;
+            }
+
+            let chunk = settlement_pos.chunk();
+            let index_in_chunk = settlement_pos.index_in_chunk();
+            tile_render_instances.push(TileRenderInstance {
+                position: [
+                    index_in_chunk.x as f32 * tile_width_px,
+                    index_in_chunk.y as f32 * tile_width_px,
+                ],
+                color: settlement_color,
+                // Settlements get bigger as they grow.
+                size: (1.0 + f64::from(settlement.population) / POPULATION_PER_SETTLEMENT_TILE)
+                    .min(MAX_SETTLEMENT_SIZE) as f32,
+                sprite: NO_SPRITE,
+                corner_tiles: NO_CORNER_TILES,
+                chunk: [chunk.x, chunk.y],
+            });
+        }
+
+        tile_render_instances
+    }
+}
+
+/// Used in place of a tile ID for instances that aren't drawn with a sprite.
+const NO_SPRITE: u32 = u32::MAX;
+/// Used in place of a tile ID for corners that are drawn normally.
+const NO_CORNER_TILE: u8 = u8::MAX;
+const NO_CORNER_TILES: u32 = u32::from_le_bytes([NO_CORNER_TILE; 4]);
+pub const ROAD_COLOR: [u8; 3] = [120, 80, 40];
+/// The width of a road as a fraction of the width of a tile.
+pub const ROAD_SIZE: f32 = 0.4;
+const SETTLEMENT_COLOR: [u8; 3] = [200, 30, 30];
+/// The number of people it takes for a settlement to be drawn one tile wider.
+const POPULATION_PER_SETTLEMENT_TILE: f64 = 100.0;
+/// The largest width (in tiles) that a settlement is drawn at.
+const MAX_SETTLEMENT_SIZE: f64 = 4.0;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn chunks_far_from_the_origin_can_be_built() {
+        let mut world = World::new();
+        for chunk_coords in [
+            ChunkPos {
+                x: 100_000_000,
+                y: 0,
+            },
+            ChunkPos {
+                x: i32::MAX,
+                y: i32::MIN,
+            },
+        ] {
+            world.generate_chunk(chunk_coords);
+            let chunk = &world.chunks[&chunk_coords];
+            let (instances, tile_instance_count) =
+                TileRenderInstance::vec_from_chunk(&world, chunk_coords, chunk, 0);
+
+            assert_eq!(tile_instance_count, 32 * 32);
+            assert!(instances
+                .iter()
+                .all(|instance| instance.chunk == [chunk_coords.x, chunk_coords.y]));
+        }
+    }
 }
@@ -1,4 +1,5 @@
 mod display;
+mod pathfinding;
 mod position;
 mod ui;
 mod utils;
@@ -6,7 +7,7 @@ mod world;
 
 use crate::display::Display;
 use crate::ui::Ui;
-use crate::world::World;
+use crate::world::{CommandHistory, World};
 use cfg_if::cfg_if;
 use std::ops::Add;
 #[cfg(target_arch = "wasm32")]
@@ -26,8 +27,9 @@ pub async fn run() {
     let mut app_state = App {
         display: Display::new(&window).await,
         next_frame_start_time: Instant::now(),
-        ui: Ui::new(),
+        ui: Ui::new(window.inner_size()),
         world: World::new(),
+        history: CommandHistory::default(),
     };
 
     // TODO: Use EventLoopExtWebSys::spawn() instead of run() on web to avoid the JS exception trick.
@@ -42,6 +44,9 @@ struct App<'a> {
     next_frame_start_time: Instant,
     ui: Ui,
     world: World,
+    /// The edits made to the world by the user, which can be undone, along with a log of everything
+    /// that has happened to the world for replaying it.
+    history: CommandHistory,
 }
 
 fn handle_winit_event(
@@ -55,6 +60,7 @@ fn handle_winit_event(
         ref mut next_frame_start_time,
         ref mut ui,
         ref mut world,
+        ref mut history,
     } = app_state;
 
     match event {
@@ -69,7 +75,10 @@ fn handle_winit_event(
                     // The other errors (Outdated, Timeout) should be resolved by the next frame.
                     Err(error) => eprintln!("{error:?}"),
                 },
-                WindowEvent::Resized(physical_size) => display.resize(*physical_size),
+                WindowEvent::Resized(physical_size) => {
+                    display.resize(*physical_size);
+                    ui.handle_resize(*physical_size);
+                }
                 WindowEvent::CloseRequested => {
                     control_flow.exit();
                     // It might take up to a few seconds to clean up, so we'll hide the window now. This makes
@@ -78,15 +87,25 @@ fn handle_winit_event(
                     display.window().set_visible(false);
                 }
                 WindowEvent::KeyboardInput { event, .. } => ui.handle_key_event(event),
+                WindowEvent::ModifiersChanged(modifiers) => {
+                    ui.handle_modifiers_changed(modifiers.state());
+                }
                 WindowEvent::MouseWheel { delta, .. } => ui.handle_scroll_event(delta),
+                WindowEvent::CursorMoved { position, .. } => ui.handle_cursor_moved(*position),
+                WindowEvent::CursorLeft { .. } => ui.handle_cursor_left(),
+                WindowEvent::MouseInput { state, button, .. } => {
+                    ui.handle_mouse_button_event(*state, *button);
+                }
                 _ => {}
             }
         }
         Event::NewEvents(cause)
             if *cause == StartCause::Poll && Instant::now() >= *next_frame_start_time =>
         {
-            ui.tick(world);
-            world.tick();
+            ui.tick(world, history);
+            for _ in 0..ui.simulation_speed() {
+                history.tick(world);
+            }
 
             display.window().request_redraw();
 
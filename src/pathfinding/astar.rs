@@ -0,0 +1,237 @@
+use crate::pathfinding::{
+    neighbours, octile_distance, tile_cost, Neighbour, PathfindingError, SearchConfig,
+};
+use crate::position::TilePos;
+use crate::world::World;
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+
+/// A path between two tiles, found by `find_path`.
+pub struct Path {
+    /// Every tile along the path, including the start and the goal.
+    pub tiles: Vec<TilePos>,
+    /// The total cost of walking the path.
+    // Roads only need the tiles so far.
+    #[cfg_attr(not(test), expect(dead_code))]
+    pub cost: u32,
+}
+
+/// Finds the cheapest path between two tiles with A*.
+pub fn find_path(
+    world: &mut World,
+    start: TilePos,
+    goal: TilePos,
+    config: &SearchConfig,
+) -> Result<Path, PathfindingError> {
+    if tile_cost(world, start, config).is_none() || tile_cost(world, goal, config).is_none() {
+        return Err(PathfindingError::Impassable);
+    }
+
+    // The open set is ordered by estimated total cost, with ties broken in favour of the
+    // highest cost so far (which means the lowest estimate of the cost left, so the node closest
+    // to the goal).
+    let mut open_set = BinaryHeap::new();
+    let mut cheapest_costs = HashMap::from([(start, 0)]);
+    let mut came_from = HashMap::new();
+    let mut expanded_nodes = 0;
+
+    open_set.push(Reverse((
+        octile_distance(start, goal),
+        Reverse(0),
+        (start.x, start.y),
+    )));
+
+    while let Some(Reverse((_, Reverse(cost), (x, y)))) = open_set.pop() {
+        let current = TilePos { x, y };
+
+        if current == goal {
+            return Ok(Path {
+                tiles: reconstruct_path(&came_from, goal),
+                cost,
+            });
+        }
+
+        // Skip stale entries - we found a cheaper way to this tile after this entry was pushed.
+        if cheapest_costs
+            .get(&current)
+            .is_some_and(|&best| cost > best)
+        {
+            continue;
+        }
+
+        expanded_nodes += 1;
+        if expanded_nodes > config.node_budget {
+            return Err(PathfindingError::BudgetExhausted);
+        }
+
+        for Neighbour {
+            coords: neighbour,
+            distance,
+            tile_cost,
+        } in neighbours(world, current, config)
+        {
+            let new_cost = cost + distance * tile_cost;
+            if cheapest_costs
+                .get(&neighbour)
+                .is_some_and(|&best| new_cost >= best)
+            {
+                continue;
+            }
+
+            cheapest_costs.insert(neighbour, new_cost);
+            came_from.insert(neighbour, current);
+            open_set.push(Reverse((
+                new_cost + octile_distance(neighbour, goal),
+                Reverse(new_cost),
+                (neighbour.x, neighbour.y),
+            )));
+        }
+    }
+
+    Err(PathfindingError::Unreachable)
+}
+
+fn reconstruct_path(came_from: &HashMap<TilePos, TilePos>, goal: TilePos) -> Vec<TilePos> {
+    let mut tiles = vec![goal];
+    let mut current = goal;
+
+    while let Some(&previous) = came_from.get(&current) {
+        tiles.push(previous);
+        current = previous;
+    }
+
+    tiles.reverse();
+    tiles
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pathfinding::tests::test_world;
+    use crate::pathfinding::{UnknownChunkPolicy, DIAGONAL_STEP_COST, STRAIGHT_STEP_COST};
+    use crate::position::ChunkPos;
+    use crate::world::Tile;
+
+    fn tile(x: i64, y: i64) -> TilePos {
+        TilePos { x, y }
+    }
+
+    fn assert_steps_are_adjacent(path: &Path) {
+        for step in path.tiles.windows(2) {
+            assert!(
+                step[0].x.abs_diff(step[1].x) <= 1 && step[0].y.abs_diff(step[1].y) <= 1,
+                "{:?} isn't next to {:?}",
+                step[0],
+                step[1]
+            );
+        }
+    }
+
+    #[test]
+    fn finds_the_shortest_path() {
+        let grass_cost = Tile::Grass
+            .movement_cost()
+            .expect("Grass should be passable");
+        // A wall between the start and the goal with a gap at the top.
+        let wall: Vec<_> = (0..10).map(|y| tile(5, y)).collect();
+        let mut world = test_world(&[ChunkPos::default()], &wall);
+
+        let path = find_path(&mut world, tile(3, 5), tile(7, 5), &SearchConfig::default())
+            .expect("There should be a path around the wall");
+
+        assert_eq!(path.tiles.first(), Some(&tile(3, 5)));
+        assert_eq!(path.tiles.last(), Some(&tile(7, 5)));
+        assert_steps_are_adjacent(&path);
+        assert!(!path.tiles.iter().any(|coords| wall.contains(coords)));
+        // Up to the gap at (5, 10) and back down. Diagonal steps can't cut past the end of the
+        // wall, so only the steps onto the columns next to it can be diagonal.
+        assert_eq!(
+            path.cost,
+            (2 * DIAGONAL_STEP_COST + 10 * STRAIGHT_STEP_COST) * grass_cost
+        );
+    }
+
+    #[test]
+    fn equally_short_paths_are_found_consistently() {
+        let grass_cost = Tile::Grass
+            .movement_cost()
+            .expect("Grass should be passable");
+        let mut world = test_world(&[ChunkPos::default()], &[]);
+
+        // There are several ways to take one diagonal step and two straight ones.
+        let paths: Vec<_> = (0..2)
+            .map(|_| {
+                find_path(&mut world, tile(0, 0), tile(3, 1), &SearchConfig::default())
+                    .expect("There should be a path over open grass")
+            })
+            .collect();
+
+        for path in &paths {
+            assert_eq!(path.tiles.len(), 4);
+            assert_steps_are_adjacent(path);
+            assert_eq!(
+                path.cost,
+                (DIAGONAL_STEP_COST + 2 * STRAIGHT_STEP_COST) * grass_cost
+            );
+        }
+        assert_eq!(paths[0].tiles, paths[1].tiles);
+    }
+
+    #[test]
+    fn walled_off_goals_are_unreachable() {
+        let goal = tile(10, 10);
+        let moat: Vec<_> = TilePos::rect(tile(8, 8), tile(12, 12))
+            .filter(|&coords| coords != goal)
+            .collect();
+        let mut world = test_world(&[ChunkPos::default()], &moat);
+
+        let result = find_path(&mut world, tile(0, 0), goal, &SearchConfig::default());
+
+        assert!(matches!(result, Err(PathfindingError::Unreachable)));
+    }
+
+    #[test]
+    fn impassable_goals_are_rejected() {
+        let mut world = test_world(&[ChunkPos::default()], &[tile(4, 4)]);
+
+        let result = find_path(&mut world, tile(0, 0), tile(4, 4), &SearchConfig::default());
+
+        assert!(matches!(result, Err(PathfindingError::Impassable)));
+    }
+
+    #[test]
+    fn paths_cross_chunk_borders() {
+        let grass_cost = Tile::Grass
+            .movement_cost()
+            .expect("Grass should be passable");
+        let mut world = test_world(&[ChunkPos { x: -1, y: 0 }, ChunkPos { x: 0, y: 0 }], &[]);
+
+        let path = find_path(
+            &mut world,
+            tile(-3, 5),
+            tile(2, 5),
+            &SearchConfig::default(),
+        )
+        .expect("There should be a path between the chunks");
+
+        assert_eq!(path.tiles.len(), 6);
+        assert_eq!(path.cost, 5 * STRAIGHT_STEP_COST * grass_cost);
+    }
+
+    #[test]
+    fn ungenerated_chunks_block_the_search_unless_allowed() {
+        let mut world = test_world(&[ChunkPos::default()], &[]);
+        let goal = tile(40, 5);
+
+        let blocked = find_path(&mut world, tile(30, 5), goal, &SearchConfig::default());
+        assert!(matches!(blocked, Err(PathfindingError::Impassable)));
+
+        let config = SearchConfig {
+            unknown_chunks: UnknownChunkPolicy::Generate,
+            ..SearchConfig::default()
+        };
+        // The generated terrain is random, so only check that the search got into the new chunk.
+        let _ = find_path(&mut world, tile(30, 5), goal, &config);
+        assert!(world.chunks.contains_key(&ChunkPos { x: 1, y: 0 }));
+    }
+}
@@ -0,0 +1,150 @@
+use crate::pathfinding::{neighbours, tile_cost, Neighbour, PathfindingError, SearchConfig};
+use crate::position::TilePos;
+use crate::world::World;
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap, HashSet};
+
+/// A map of the cheapest direction to move in from every tile to reach a single goal. Building one
+/// is more expensive than a single A* search, but it can then be shared by any number of agents
+/// heading to the same place.
+pub struct FlowField {
+    goal: TilePos,
+    /// For every tile the search reached, the cost of getting to the goal from it and the next tile
+    /// to step onto. (The goal itself has no next tile.)
+    entries: HashMap<TilePos, (u32, Option<TilePos>)>,
+}
+
+// Nothing follows a flow field yet.
+#[cfg_attr(not(test), expect(dead_code))]
+impl FlowField {
+    /// Builds a flow field by searching outwards from the goal (Dijkstra's algorithm). The search
+    /// stops once it runs out of tiles or hits the node budget, in which case tiles further away
+    /// than the search got simply won't be part of the field. Every tile that is part of the field
+    /// has its cheapest route to the goal, even if the search was cut short.
+    pub fn generate(
+        world: &mut World,
+        goal: TilePos,
+        config: &SearchConfig,
+    ) -> Result<Self, PathfindingError> {
+        if tile_cost(world, goal, config).is_none() {
+            return Err(PathfindingError::Impassable);
+        }
+
+        let mut entries = HashMap::from([(goal, (0, None))]);
+        let mut open_set = BinaryHeap::from([Reverse((0, (goal.x, goal.y)))]);
+        // The tiles whose cheapest route is known for sure. Any other tiles in `entries` have only
+        // been reached from a neighbour, and might still turn out to have a cheaper route.
+        let mut settled = HashSet::new();
+        let mut expanded_nodes = 0;
+
+        while let Some(Reverse((cost, (x, y)))) = open_set.pop() {
+            let current = TilePos { x, y };
+
+            if entries.get(&current).is_some_and(|&(best, _)| cost > best) {
+                continue;
+            }
+            settled.insert(current);
+
+            expanded_nodes += 1;
+            if expanded_nodes > config.node_budget {
+                // Leave out the tiles that the search didn't get to, rather than pointing them
+                // along routes that might not be the cheapest.
+                entries.retain(|coords, _| settled.contains(coords));
+                break;
+            }
+
+            // Movement costs depend on the tile being stepped onto, so the cost of stepping from
+            // the neighbour onto this tile is this tile's cost, not the neighbour's.
+            let Some(current_tile_cost) = tile_cost(world, current, config) else {
+                continue;
+            };
+            for Neighbour {
+                coords: neighbour,
+                distance,
+                ..
+            } in neighbours(world, current, config)
+            {
+                let new_cost = cost + distance * current_tile_cost;
+                if entries
+                    .get(&neighbour)
+                    .is_some_and(|&(best, _)| new_cost >= best)
+                {
+                    continue;
+                }
+
+                entries.insert(neighbour, (new_cost, Some(current)));
+                open_set.push(Reverse((new_cost, (neighbour.x, neighbour.y))));
+            }
+        }
+
+        Ok(FlowField { goal, entries })
+    }
+
+    pub fn goal(&self) -> TilePos {
+        self.goal
+    }
+
+    /// The next tile to step onto from the given tile to get closer to the goal. Returns None if
+    /// the tile is the goal or if the field doesn't cover it.
+    pub fn next_step(&self, from: TilePos) -> Option<TilePos> {
+        self.entries.get(&from).and_then(|&(_, next)| next)
+    }
+
+    /// The total cost of getting to the goal from the given tile, or None if the field doesn't cover it.
+    pub fn cost_to_goal(&self, from: TilePos) -> Option<u32> {
+        self.entries.get(&from).map(|&(cost, _)| cost)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pathfinding::find_path;
+    use crate::pathfinding::tests::test_world;
+    use crate::position::ChunkPos;
+
+    #[test]
+    fn every_tile_flows_to_the_goal() {
+        let goal = TilePos { x: 20, y: 20 };
+        let wall: Vec<_> = (5..30).map(|x| TilePos { x, y: 15 }).collect();
+        let mut world = test_world(&[ChunkPos { x: 0, y: 0 }, ChunkPos { x: 0, y: -1 }], &wall);
+        let field = FlowField::generate(&mut world, goal, &SearchConfig::default())
+            .expect("The goal should be passable");
+
+        assert_eq!(field.goal(), goal);
+        assert_eq!(field.cost_to_goal(goal), Some(0));
+        assert_eq!(field.next_step(goal), None);
+
+        for start in TilePos::rect(TilePos { x: 0, y: -32 }, TilePos { x: 31, y: 31 })
+            .filter(|coords| !wall.contains(coords))
+        {
+            let mut current = start;
+            let mut cost = field
+                .cost_to_goal(current)
+                .expect("Every passable tile should be in the field");
+            while let Some(next) = field.next_step(current) {
+                let next_cost = field
+                    .cost_to_goal(next)
+                    .expect("Every step should lead to a tile in the field");
+                assert!(
+                    next_cost < cost,
+                    "{current:?} didn't get closer to the goal"
+                );
+                current = next;
+                cost = next_cost;
+            }
+            assert_eq!(current, goal);
+        }
+
+        // Following the field should be as cheap as the best path.
+        for start in [
+            TilePos { x: 20, y: 0 },
+            TilePos { x: 2, y: -30 },
+            TilePos { x: 31, y: 31 },
+        ] {
+            let path = find_path(&mut world, start, goal, &SearchConfig::default())
+                .expect("There should be a path around the wall");
+            assert_eq!(field.cost_to_goal(start), Some(path.cost));
+        }
+    }
+}
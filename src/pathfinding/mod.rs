@@ -0,0 +1,238 @@
+//! Pathfinding over the global tile grid.
+//!
+//! All searches work on `TilePos` and allow movement to all eight neighbours of a tile.
+//! Diagonal moves can't cut the corner of an impassable tile.
+
+mod astar;
+mod flow_field;
+
+use crate::position::TilePos;
+use crate::world::World;
+pub use astar::find_path;
+// Nothing follows a flow field yet.
+#[expect(unused_imports)]
+pub use flow_field::FlowField;
+
+/// Settings shared by all of the search algorithms.
+#[derive(Copy, Clone)]
+pub struct SearchConfig {
+    /// What to do when the search reaches a chunk that hasn't been generated yet.
+    pub unknown_chunks: UnknownChunkPolicy,
+    /// The maximum number of tiles that a search is allowed to expand before giving up.
+    pub node_budget: usize,
+}
+
+impl Default for SearchConfig {
+    fn default() -> Self {
+        SearchConfig {
+            unknown_chunks: UnknownChunkPolicy::Blocked,
+            node_budget: 10_000,
+        }
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Eq)]
+pub enum UnknownChunkPolicy {
+    /// Generate chunks as the search reaches them.
+    Generate,
+    /// Treat tiles in ungenerated chunks as impassable.
+    Blocked,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum PathfindingError {
+    /// The start or goal tile can't be walked on.
+    Impassable,
+    /// Every reachable tile was searched without reaching the goal.
+    Unreachable,
+    /// The search expanded more tiles than the node budget allows.
+    BudgetExhausted,
+}
+
+/// The cost of moving one tile horizontally or vertically onto a tile with a movement cost of 1.
+/// Diagonal moves cost `DIAGONAL_STEP_COST` instead, which approximates `STRAIGHT_STEP_COST * sqrt(2)`.
+const STRAIGHT_STEP_COST: u32 = 10;
+const DIAGONAL_STEP_COST: u32 = 14;
+
+/// The lowest movement cost of any tile (including roads). Used to keep the A* heuristic admissible.
+const MIN_TILE_COST: u32 = 1;
+
+/// Returns the movement cost of the tile at the given coordinates, generating its chunk first if
+/// the config allows it.
+fn tile_cost(world: &mut World, coords: TilePos, config: &SearchConfig) -> Option<u32> {
+    if config.unknown_chunks == UnknownChunkPolicy::Generate {
+        world.generate_chunk(coords.chunk());
+    }
+
+    world.movement_cost(coords)
+}
+
+/// A passable tile next to the tile being searched from.
+struct Neighbour {
+    coords: TilePos,
+    /// The distance to the neighbour, either `STRAIGHT_STEP_COST` or `DIAGONAL_STEP_COST`.
+    distance: u32,
+    /// The movement cost of the neighbouring tile itself.
+    tile_cost: u32,
+}
+
+/// Returns each passable neighbour of a tile.
+fn neighbours(world: &mut World, coords: TilePos, config: &SearchConfig) -> Vec<Neighbour> {
+    let mut result = Vec::with_capacity(8);
+
+    for (dx, dy) in [
+        (0, 1),
+        (1, 0),
+        (0, -1),
+        (-1, 0),
+        (1, 1),
+        (1, -1),
+        (-1, -1),
+        (-1, 1),
+    ] {
+        let neighbour = coords + TilePos { x: dx, y: dy };
+        // Tiles at the edge of the world have no neighbours past it.
+        if (dx != 0 && neighbour.x == coords.x) || (dy != 0 && neighbour.y == coords.y) {
+            continue;
+        }
+        let Some(cost) = tile_cost(world, neighbour, config) else {
+            continue;
+        };
+
+        let distance = if dx != 0 && dy != 0 {
+            // Don't allow cutting corners - both of the tiles we'd be squeezing between need to be passable.
+            let horizontal = coords + TilePos { x: dx, y: 0 };
+            let vertical = coords + TilePos { x: 0, y: dy };
+            if tile_cost(world, horizontal, config).is_none()
+                || tile_cost(world, vertical, config).is_none()
+            {
+                continue;
+            }
+            DIAGONAL_STEP_COST
+        } else {
+            STRAIGHT_STEP_COST
+        };
+
+        result.push(Neighbour {
+            coords: neighbour,
+            distance,
+            tile_cost: cost,
+        });
+    }
+
+    result
+}
+
+/// The octile distance between two tiles, scaled to match the step costs. This is the cheapest
+/// possible cost of moving between them. Distances too far to cost a u32 are given as `u32::MAX`.
+fn octile_distance(a: TilePos, b: TilePos) -> u32 {
+    let dx = a.x.abs_diff(b.x);
+    let dy = a.y.abs_diff(b.y);
+    let diagonal_steps = dx.min(dy);
+    let straight_steps = dx.max(dy) - diagonal_steps;
+
+    let cost = diagonal_steps
+        .saturating_mul(u64::from(DIAGONAL_STEP_COST))
+        .saturating_add(straight_steps.saturating_mul(u64::from(STRAIGHT_STEP_COST)))
+        .saturating_mul(u64::from(MIN_TILE_COST));
+    u32::try_from(cost).unwrap_or(u32::MAX)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::position::{ChunkPos, TileIndex};
+    use crate::world::{Chunk, Tile, TileData};
+
+    /// Creates a world of grass over the given chunks, with water on the given tiles.
+    pub fn test_world(chunks: &[ChunkPos], water: &[TilePos]) -> World {
+        let mut world = World::new();
+        let side = i64::from(Chunk::side_size());
+        for &chunk in chunks {
+            world.generate_chunk(chunk);
+            let bottom_left = chunk.tile(TileIndex { x: 0, y: 0 });
+            let top_right = bottom_left
+                + TilePos {
+                    x: side - 1,
+                    y: side - 1,
+                };
+            for coords in TilePos::rect(bottom_left, top_right) {
+                let tile = if water.contains(&coords) {
+                    Tile::Water
+                } else {
+                    Tile::Grass
+                };
+                world.set_tile_data(
+                    coords,
+                    TileData {
+                        tile,
+                        elevation: 0.0,
+                    },
+                );
+            }
+        }
+        world
+    }
+
+    #[test]
+    fn diagonal_moves_cant_cut_corners() {
+        let mut world = test_world(&[ChunkPos::default()], &[TilePos { x: 6, y: 5 }]);
+        let coords: Vec<_> =
+            neighbours(&mut world, TilePos { x: 5, y: 5 }, &SearchConfig::default())
+                .into_iter()
+                .map(|neighbour| neighbour.coords)
+                .collect();
+
+        assert_eq!(coords.len(), 5);
+        assert!(!coords.contains(&TilePos { x: 6, y: 6 }));
+        assert!(!coords.contains(&TilePos { x: 6, y: 4 }));
+    }
+
+    #[test]
+    fn neighbours_stop_at_the_edge_of_the_world() {
+        let corner = ChunkPos {
+            x: i32::MAX,
+            y: i32::MAX,
+        };
+        let mut world = test_world(&[corner], &[]);
+        let edge = TilePos {
+            x: i64::MAX,
+            y: i64::MAX,
+        };
+        let coords: Vec<_> = neighbours(&mut world, edge, &SearchConfig::default())
+            .into_iter()
+            .map(|neighbour| neighbour.coords)
+            .collect();
+
+        assert_eq!(
+            coords,
+            [
+                TilePos {
+                    x: edge.x,
+                    y: edge.y - 1
+                },
+                TilePos {
+                    x: edge.x - 1,
+                    y: edge.y
+                },
+                TilePos {
+                    x: edge.x - 1,
+                    y: edge.y - 1
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn octile_distance_saturates() {
+        let far = TilePos {
+            x: i64::MAX,
+            y: i64::MIN,
+        };
+        assert_eq!(octile_distance(TilePos { x: 0, y: 0 }, far), u32::MAX);
+        assert_eq!(
+            octile_distance(TilePos { x: 0, y: 0 }, TilePos { x: 3, y: 1 }),
+            DIAGONAL_STEP_COST + 2 * STRAIGHT_STEP_COST
+        );
+    }
+}
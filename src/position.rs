@@ -1,113 +1,583 @@
 use crate::world::{Chunk, Tile};
+use std::ops::{Add, AddAssign, Sub};
 
-/// A position in the game world. Supports pixels, tiles, and chunks (a pixel being a screen pixel
-/// at 1x zoom).
+/// A position in the game world, measured in pixels (a pixel being a screen pixel at 1x zoom).
+///
+/// Positions are stored as the chunk they're in plus a fixed-point offset within the chunk, so
+/// they're just as precise far away from the origin as they are close to it.
 ///
 /// Notes:
 /// - Both tiles and chunks are identified by their lower left corners
-/// - 0, 0 is the same in all three coordinate systems
-#[derive(Default, Copy, Clone)]
-pub struct Position {
-    // Note that fixed-point decimal numbers would be more efficient (they would just take more effort).
-    // Also note that x and y are stored as pixels.
-    x: f64,
-    y: f64,
-}
-
-impl Position {
-    /// Mode here refers to the format of the input x and y arguments.
-    pub fn new(x: f64, y: f64, mode: PositionMode) -> Self {
-        match mode {
-            PositionMode::Pixels => Self { x, y },
-            PositionMode::Tiles => Self {
-                x: Self::tiles_to_pixels(x),
-                y: Self::tiles_to_pixels(y),
-            },
-            PositionMode::Chunks => Self {
-                x: Self::chunks_to_pixels(x),
-                y: Self::chunks_to_pixels(y),
+/// - 0, 0 is the bottom left corner of both tile 0, 0 and chunk 0, 0
+#[derive(Default, Copy, Clone, Debug, PartialEq, Eq)]
+pub struct PixelPos {
+    chunk: ChunkPos,
+    /// The distance from the bottom left corner of the chunk along each axis, in
+    /// 1/`SUBPIXELS_PER_PIXEL`ths of a pixel. Always less than the width of a chunk.
+    offset_in_chunk: [u32; 2],
+}
+
+impl PixelPos {
+    pub fn new(x: f64, y: f64) -> Self {
+        let (chunk_x, offset_x) = Self::axis_from_pixels(x);
+        let (chunk_y, offset_y) = Self::axis_from_pixels(y);
+        Self {
+            chunk: ChunkPos {
+                x: chunk_x,
+                y: chunk_y,
             },
+            offset_in_chunk: [offset_x, offset_y],
         }
     }
 
-    pub fn x(&self, mode: PositionMode) -> f64 {
-        match mode {
-            PositionMode::Pixels => self.x,
-            PositionMode::Tiles => Self::pixels_to_tiles(self.x),
-            PositionMode::Chunks => Self::pixels_to_chunks(self.x),
+    /// Creates a position from coordinates in tiles, which can be anywhere within a tile.
+    pub fn from_tiles(x: f64, y: f64) -> Self {
+        let tile_width_px = f64::from(Tile::width_px());
+        Self::new(x * tile_width_px, y * tile_width_px)
+    }
+
+    pub fn x(&self) -> f64 {
+        Self::axis_to_pixels(self.chunk.x, self.offset_in_chunk[0])
+    }
+
+    pub fn y(&self) -> f64 {
+        Self::axis_to_pixels(self.chunk.y, self.offset_in_chunk[1])
+    }
+
+    /// The x coordinate in tiles, including how far across its tile the position is.
+    pub fn tile_x(&self) -> f64 {
+        self.x() / f64::from(Tile::width_px())
+    }
+
+    /// The y coordinate in tiles, including how far up its tile the position is.
+    pub fn tile_y(&self) -> f64 {
+        self.y() / f64::from(Tile::width_px())
+    }
+
+    /// The straight line distance to another position, in pixels.
+    pub fn distance(&self, other: &Self) -> f64 {
+        let [dx, dy] = *other - *self;
+        dx.hypot(dy)
+    }
+
+    /// Reads a position written as two numbers followed by the units they're in, like
+    /// `12.5, -3 tiles`, `4 2 chunks` or `1000, 0 px`. Tiles are assumed if there are no units.
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let parts: Vec<&str> = text
+            .split(|character: char| character == ',' || character.is_whitespace())
+            .filter(|part| !part.is_empty())
+            .collect();
+
+        let tile_width_px = f64::from(Tile::width_px());
+        let (coordinates, px_per_unit) = match parts.as_slice() {
+            [x, y] => ([x, y], tile_width_px),
+            [x, y, units] => {
+                let px_per_unit = match units.to_lowercase().as_str() {
+                    "px" | "pixel" | "pixels" => 1.0,
+                    "t" | "tile" | "tiles" => tile_width_px,
+                    "c" | "chunk" | "chunks" => tile_width_px * f64::from(Chunk::side_size()),
+                    _ => return Err(format!("unknown units {units}")),
+                };
+                ([x, y], px_per_unit)
+            }
+            _ => return Err("expected an x and a y coordinate".to_string()),
+        };
+
+        let [x, y] = coordinates.map(|coordinate| {
+            coordinate
+                .parse::<f64>()
+                .ok()
+                .filter(|value| value.is_finite())
+                .ok_or_else(|| format!("{coordinate} isn't a number"))
+        });
+        Ok(Self::new(x? * px_per_unit, y? * px_per_unit))
+    }
+
+    /// The chunk that the position is in.
+    pub fn chunk(self) -> ChunkPos {
+        self.chunk
+    }
+
+    /// The tile that the position is in.
+    pub fn tile(self) -> TilePos {
+        let subpixels_per_tile = i64::from(Tile::width_px()) * SUBPIXELS_PER_PIXEL;
+        let index = |offset: u32| (i64::from(offset) / subpixels_per_tile) as usize;
+        self.chunk.tile(TileIndex {
+            x: index(self.offset_in_chunk[0]),
+            y: index(self.offset_in_chunk[1]),
+        })
+    }
+
+    /// Splits a coordinate in pixels into the chunk it's in and the fixed-point offset within the
+    /// chunk, rounding to the nearest subpixel.
+    fn axis_from_pixels(pixel_coordinate: f64) -> (i32, u32) {
+        let subpixels = (pixel_coordinate * SUBPIXELS_PER_PIXEL as f64).round() as i64;
+        let chunk_width = Self::chunk_width_subpixels();
+        let (chunk, offset) = floor_div_rem(subpixels, chunk_width);
+        // Positions beyond the last chunk that can be identified are kept at the edge of the world.
+        if chunk > i64::from(i32::MAX) {
+            (i32::MAX, (chunk_width - 1) as u32)
+        } else if chunk < i64::from(i32::MIN) {
+            (i32::MIN, 0)
+        } else {
+            (chunk as i32, offset as u32)
         }
     }
 
-    pub fn y(&self, mode: PositionMode) -> f64 {
-        match mode {
-            PositionMode::Pixels => self.y,
-            PositionMode::Tiles => Self::pixels_to_tiles(self.y),
-            PositionMode::Chunks => Self::pixels_to_chunks(self.y),
+    /// The inverse of `axis_from_pixels`. This is exact, since an f64 can hold every subpixel in
+    /// the world.
+    fn axis_to_pixels(chunk: i32, offset: u32) -> f64 {
+        Self::axis_to_subpixels(chunk, offset) as f64 / SUBPIXELS_PER_PIXEL as f64
+    }
+
+    fn axis_to_subpixels(chunk: i32, offset: u32) -> i64 {
+        i64::from(chunk) * Self::chunk_width_subpixels() + i64::from(offset)
+    }
+
+    fn chunk_width_subpixels() -> i64 {
+        i64::from(Tile::width_px()) * i64::from(Chunk::side_size()) * SUBPIXELS_PER_PIXEL
+    }
+}
+
+/// Moves a position by a distance in pixels along each axis.
+impl Add<[f64; 2]> for PixelPos {
+    type Output = Self;
+
+    fn add(self, [dx, dy]: [f64; 2]) -> Self {
+        Self::new(self.x() + dx, self.y() + dy)
+    }
+}
+
+impl AddAssign<[f64; 2]> for PixelPos {
+    fn add_assign(&mut self, distance: [f64; 2]) {
+        *self = *self + distance;
+    }
+}
+
+/// The distance in pixels from one position to another along each axis. This is worked out from
+/// the fixed-point coordinates, so it's exact for positions that are close together no matter how
+/// far from the origin they are.
+impl Sub for PixelPos {
+    type Output = [f64; 2];
+
+    fn sub(self, other: Self) -> [f64; 2] {
+        let axis = |index: usize, chunk: i32, other_chunk: i32| {
+            let subpixels = Self::axis_to_subpixels(chunk, self.offset_in_chunk[index])
+                - Self::axis_to_subpixels(other_chunk, other.offset_in_chunk[index]);
+            subpixels as f64 / SUBPIXELS_PER_PIXEL as f64
+        };
+        [
+            axis(0, self.chunk.x, other.chunk.x),
+            axis(1, self.chunk.y, other.chunk.y),
+        ]
+    }
+}
+
+/// The integer coordinates of a chunk, for when you care about which chunk but not where in the
+/// chunk.
+#[derive(Default, Copy, Clone, Debug, Hash, Eq, PartialEq)]
+pub struct ChunkPos {
+    pub x: i32,
+    pub y: i32,
+}
+
+impl ChunkPos {
+    /// The position of the chunk's bottom left corner.
+    pub fn bottom_left(self) -> PixelPos {
+        PixelPos {
+            chunk: self,
+            offset_in_chunk: [0, 0],
         }
     }
 
-    pub fn set_x(&mut self, value: f64, mode: PositionMode) {
-        self.x = match mode {
-            PositionMode::Pixels => value,
-            PositionMode::Tiles => Self::tiles_to_pixels(value),
-            PositionMode::Chunks => Self::chunks_to_pixels(value),
+    /// The chunk's bottom left tile.
+    pub fn bottom_left_tile(self) -> TilePos {
+        self.tile(TileIndex { x: 0, y: 0 })
+    }
+
+    /// The tile at an index within the chunk. This is the inverse of `TilePos::chunk` and
+    /// `TilePos::index_in_chunk`.
+    pub fn tile(self, index: TileIndex) -> TilePos {
+        let axis = |chunk: i32, index: usize| {
+            i64::from(chunk) * i64::from(Chunk::side_size()) + index as i64
+        };
+        TilePos {
+            x: axis(self.x, index.x),
+            y: axis(self.y, index.y),
         }
     }
 
-    pub fn set_y(&mut self, value: f64, mode: PositionMode) {
-        self.y = match mode {
-            PositionMode::Pixels => value,
-            PositionMode::Tiles => Self::tiles_to_pixels(value),
-            PositionMode::Chunks => Self::chunks_to_pixels(value),
+    /// The square of the straight line distance to another chunk, in chunks. This avoids a square
+    /// root, for comparing distances.
+    pub fn distance_squared(self, other: Self) -> i64 {
+        let dx = i64::from(other.x) - i64::from(self.x);
+        let dy = i64::from(other.y) - i64::from(self.y);
+        dx * dx + dy * dy
+    }
+
+    /// Every chunk from `bottom_left` to `top_right` (inclusive), a column at a time.
+    pub fn rect(bottom_left: Self, top_right: Self) -> impl Iterator<Item = Self> {
+        (bottom_left.x..=top_right.x)
+            .flat_map(move |x| (bottom_left.y..=top_right.y).map(move |y| Self { x, y }))
+    }
+}
+
+/// Adds the coordinates of two chunks. Like `PixelPos`, results beyond the last chunk that can be
+/// identified are kept at the edge of the world rather than overflowing.
+impl Add for ChunkPos {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        Self {
+            x: self.x.saturating_add(other.x),
+            y: self.y.saturating_add(other.y),
         }
     }
+}
 
-    pub fn into_int_chunk_coords(self) -> IntChunkCoordinates {
-        let int_x = Self::pixels_to_chunks(self.x) as i32;
-        let int_y = Self::pixels_to_chunks(self.y) as i32;
+/// The distance from one chunk to another along each axis, kept at the edge of the world like
+/// `Add`.
+impl Sub for ChunkPos {
+    type Output = Self;
 
-        // We have to do a little bit of logic here because `as i32` always rounds towards zero,
-        // whereas chunks are identified by their lower left corners.
-        IntChunkCoordinates {
-            x: if self.x >= 0.0 { int_x } else { int_x - 1 },
-            y: if self.y >= 0.0 { int_y } else { int_y - 1 },
+    fn sub(self, other: Self) -> Self {
+        Self {
+            x: self.x.saturating_sub(other.x),
+            y: self.y.saturating_sub(other.y),
         }
     }
+}
 
-    fn pixels_to_tiles(pixel_coordinate: f64) -> f64 {
-        pixel_coordinate / f64::from(Tile::width_px())
+/// The integer coordinates of a tile in the world (as opposed to within a chunk), for when you
+/// care about which tile but not where in the tile.
+///
+/// The coordinates are i64s so that every tile of every chunk can be identified. Tiles beyond the
+/// last chunk (which can only be made by adding to a tile) are in the chunk at the edge of the
+/// world.
+#[derive(Copy, Clone, Debug, Hash, Eq, PartialEq)]
+pub struct TilePos {
+    pub x: i64,
+    pub y: i64,
+}
+
+impl TilePos {
+    /// The chunk that the tile is in.
+    pub fn chunk(self) -> ChunkPos {
+        let side_size = i64::from(Chunk::side_size());
+        ChunkPos {
+            x: saturate_to_i32(floor_div_rem(self.x, side_size).0),
+            y: saturate_to_i32(floor_div_rem(self.y, side_size).0),
+        }
     }
 
-    fn tiles_to_pixels(tile_coordinate: f64) -> f64 {
-        tile_coordinate * f64::from(Tile::width_px())
+    /// Where the tile is within its chunk, as used to index `Chunk.tiles`.
+    pub fn index_in_chunk(self) -> TileIndex {
+        let side_size = i64::from(Chunk::side_size());
+        TileIndex {
+            x: floor_div_rem(self.x, side_size).1 as usize,
+            y: floor_div_rem(self.y, side_size).1 as usize,
+        }
     }
 
-    fn pixels_to_chunks(pixel_coordinate: f64) -> f64 {
-        Self::pixels_to_tiles(pixel_coordinate) / f64::from(Chunk::side_size())
+    /// The position of the tile's bottom left corner.
+    pub fn bottom_left(self) -> PixelPos {
+        self.position_within(0)
     }
 
-    fn chunks_to_pixels(chunk_coordinate: f64) -> f64 {
-        Self::tiles_to_pixels(chunk_coordinate * f64::from(Chunk::side_size()))
+    /// The position of the middle of the tile.
+    pub fn center(self) -> PixelPos {
+        self.position_within(Tile::width_px() as u32 * SUBPIXELS_PER_PIXEL as u32 / 2)
+    }
+
+    /// The square of the straight line distance to another tile, in tiles. This avoids a square
+    /// root, for comparing distances. Distances too big for an i64 are given as `i64::MAX`.
+    pub fn distance_squared(self, other: Self) -> i64 {
+        let dx = other.x.saturating_sub(self.x);
+        let dy = other.y.saturating_sub(self.y);
+        dx.saturating_mul(dx).saturating_add(dy.saturating_mul(dy))
+    }
+
+    /// Every tile from `bottom_left` to `top_right` (inclusive), a column at a time.
+    pub fn rect(bottom_left: Self, top_right: Self) -> impl Iterator<Item = Self> {
+        (bottom_left.x..=top_right.x)
+            .flat_map(move |x| (bottom_left.y..=top_right.y).map(move |y| Self { x, y }))
+    }
+
+    /// A position the same distance (in subpixels) up and right from the tile's bottom left corner.
+    fn position_within(self, offset_in_tile: u32) -> PixelPos {
+        let index = self.index_in_chunk();
+        let subpixels_per_tile = Tile::width_px() as u32 * SUBPIXELS_PER_PIXEL as u32;
+        let axis = |index: usize| index as u32 * subpixels_per_tile + offset_in_tile;
+        PixelPos {
+            chunk: self.chunk(),
+            offset_in_chunk: [axis(index.x), axis(index.y)],
+        }
     }
 }
 
-#[derive(Copy, Clone)]
-pub enum PositionMode {
-    Pixels,
-    Tiles,
-    Chunks,
+/// Adds the coordinates of two tiles, keeping the result at the edge of the world rather than
+/// overflowing.
+impl Add for TilePos {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        Self {
+            x: self.x.saturating_add(other.x),
+            y: self.y.saturating_add(other.y),
+        }
+    }
 }
 
-/// A struct with the integer coordinates of a chunk, for when you care about which chunk
-/// but not where in the chunk.
-#[derive(Copy, Clone, Hash, Eq, PartialEq)]
-pub struct IntChunkCoordinates {
-    pub x: i32,
-    pub y: i32,
+/// The distance from one tile to another along each axis, kept at the edge of the range like
+/// `Add`.
+impl Sub for TilePos {
+    type Output = Self;
+
+    fn sub(self, other: Self) -> Self {
+        Self {
+            x: self.x.saturating_sub(other.x),
+            y: self.y.saturating_sub(other.y),
+        }
+    }
 }
 
-impl IntChunkCoordinates {
-    pub fn into_bottom_left_tile_pos(self) -> Position {
-        Position::new(f64::from(self.x), f64::from(self.y), PositionMode::Chunks)
+/// The coordinates of a tile within its chunk, from 0 to `Chunk::side_size()` - 1 along each axis.
+#[derive(Copy, Clone, Debug, Hash, Eq, PartialEq)]
+pub struct TileIndex {
+    pub x: usize,
+    pub y: usize,
+}
+
+impl TileIndex {
+    /// Every tile index in a chunk, a column at a time.
+    pub fn all() -> impl Iterator<Item = Self> {
+        let side_size = Chunk::side_size() as usize;
+        (0..side_size).flat_map(move |x| (0..side_size).map(move |y| Self { x, y }))
+    }
+}
+
+/// Divides, rounding down rather than towards zero, along with the remainder that goes with it
+/// (which is never negative). Plain division rounds towards zero, which would put negative
+/// coordinates in the wrong tile or chunk.
+fn floor_div_rem(value: i64, divisor: i64) -> (i64, i64) {
+    (value.div_euclid(divisor), value.rem_euclid(divisor))
+}
+
+/// Converts a chunk coordinate worked out as an i64 back to an i32, keeping coordinates that are
+/// too big at the edge of the world.
+fn saturate_to_i32(value: i64) -> i32 {
+    i32::try_from(value).unwrap_or(if value < 0 { i32::MIN } else { i32::MAX })
+}
+
+/// The number of steps that each pixel is split into in the fixed-point offsets of `PixelPos`.
+const SUBPIXELS_PER_PIXEL: i64 = 256;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TILE_WIDTH_PX: f64 = 128.0;
+    const CHUNK_WIDTH_PX: f64 = 4096.0;
+
+    fn tile(x: i64, y: i64) -> TilePos {
+        TilePos { x, y }
+    }
+
+    fn chunk(x: i32, y: i32) -> ChunkPos {
+        ChunkPos { x, y }
+    }
+
+    #[test]
+    fn floor_div_rem_rounds_down() {
+        assert_eq!(floor_div_rem(0, 32), (0, 0));
+        assert_eq!(floor_div_rem(31, 32), (0, 31));
+        assert_eq!(floor_div_rem(32, 32), (1, 0));
+        assert_eq!(floor_div_rem(-1, 32), (-1, 31));
+        assert_eq!(floor_div_rem(-32, 32), (-1, 0));
+        assert_eq!(floor_div_rem(-33, 32), (-2, 31));
+        assert_eq!(floor_div_rem(i64::MIN, 32), (i64::MIN / 32, 0));
+        assert_eq!(floor_div_rem(i64::MAX, 32), (i64::MAX / 32, 31));
+    }
+
+    #[test]
+    fn tiles_are_in_the_right_chunk() {
+        let cases = [
+            (0, 0, 0),
+            (31, 0, 31),
+            (32, 1, 0),
+            (-1, -1, 31),
+            (-32, -1, 0),
+            (-33, -2, 31),
+            (i64::from(i32::MAX), i32::MAX / 32, 31),
+            (i64::from(i32::MIN), i32::MIN / 32, 0),
+            (i64::from(i32::MAX) * 32 + 31, i32::MAX, 31),
+            (i64::from(i32::MIN) * 32, i32::MIN, 0),
+        ];
+        for (coordinate, chunk_coordinate, index) in cases {
+            let tile_pos = tile(coordinate, -coordinate.saturating_add(1));
+            assert_eq!(tile_pos.chunk().x, chunk_coordinate, "{tile_pos:?}");
+            assert_eq!(tile_pos.index_in_chunk().x, index, "{tile_pos:?}");
+            assert_eq!(
+                tile_pos.chunk().tile(tile_pos.index_in_chunk()),
+                tile_pos,
+                "{tile_pos:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn every_chunk_has_its_own_tiles() {
+        let index = TileIndex { x: 5, y: 31 };
+        for chunk_pos in [
+            chunk(i32::MAX, i32::MIN),
+            chunk(i32::MIN, i32::MAX),
+            chunk(100_000_000, 0),
+            chunk(-1, 1),
+        ] {
+            let tile_pos = chunk_pos.tile(index);
+            assert_eq!(tile_pos.chunk(), chunk_pos);
+            assert_eq!(tile_pos.index_in_chunk(), index);
+            assert_eq!(tile_pos.bottom_left().chunk(), chunk_pos);
+            assert_eq!(tile_pos.bottom_left().tile(), tile_pos);
+            assert_eq!(tile_pos.center().tile(), tile_pos);
+        }
+    }
+
+    #[test]
+    fn tiles_beyond_the_last_chunk_are_in_the_edge_chunk() {
+        assert_eq!(tile(i64::MAX, i64::MIN).chunk(), chunk(i32::MAX, i32::MIN));
+    }
+
+    // These pixel coordinates are all whole subpixels, so they should come back exactly.
+    #[expect(clippy::float_cmp)]
+    #[test]
+    fn pixel_positions_are_in_the_right_tile_and_chunk() {
+        let cases = [
+            (0.0, 0, 0),
+            (-1.0, -1, -1),
+            (-TILE_WIDTH_PX, -1, -1),
+            (-TILE_WIDTH_PX - 1.0, -2, -1),
+            (-32.0 * TILE_WIDTH_PX, -32, -1),
+            (-33.0 * TILE_WIDTH_PX, -33, -2),
+            (CHUNK_WIDTH_PX - 0.5, 31, 0),
+            (CHUNK_WIDTH_PX, 32, 1),
+            (-CHUNK_WIDTH_PX, -32, -1),
+            (-CHUNK_WIDTH_PX - 0.5, -33, -2),
+            (
+                f64::from(i32::MAX),
+                i64::from(i32::MAX / 128),
+                i32::MAX / 4096,
+            ),
+            (
+                f64::from(i32::MIN),
+                i64::from(i32::MIN / 128),
+                i32::MIN / 4096,
+            ),
+        ];
+        for (px, tile_coordinate, chunk_coordinate) in cases {
+            let pos = PixelPos::new(px, -px);
+            assert_eq!(pos.x(), px);
+            assert_eq!(pos.tile().x, tile_coordinate, "{px}");
+            assert_eq!(pos.chunk().x, chunk_coordinate, "{px}");
+            assert_eq!(pos.tile().chunk(), pos.chunk(), "{px}");
+        }
+    }
+
+    #[test]
+    fn pixel_positions_far_from_the_origin_stay_at_the_edge_of_the_world() {
+        let far = CHUNK_WIDTH_PX * 2.0_f64.powi(27);
+        let pos = PixelPos::new(far, -far);
+        assert_eq!(pos.chunk(), chunk(1 << 27, -(1 << 27)));
+        assert_eq!(pos.tile(), tile(1 << 32, -(1 << 32)));
+
+        let beyond_chunks = CHUNK_WIDTH_PX * 2.0_f64.powi(40);
+        let pos = PixelPos::new(beyond_chunks, -beyond_chunks);
+        let last_tile = i64::from(i32::MAX) * 32 + 31;
+        assert_eq!(pos.chunk(), chunk(i32::MAX, i32::MIN));
+        assert_eq!(pos.tile(), tile(last_tile, i64::from(i32::MIN) * 32));
+    }
+
+    // The distances are whole subpixels, and subtraction is exact, so there's no rounding error.
+    #[expect(clippy::float_cmp)]
+    #[test]
+    fn adding_and_subtracting_pixel_positions_round_trips() {
+        for (x, y) in [(0.0, 0.0), (-1.0, 4095.5), (-33.0 * TILE_WIDTH_PX, 1e12)] {
+            let pos = PixelPos::new(x, y);
+            let distance = [-CHUNK_WIDTH_PX - 0.25, 12.5];
+            let moved = pos + distance;
+
+            assert_eq!(moved - pos, distance);
+            assert_eq!(pos - moved, [-distance[0], -distance[1]]);
+        }
+    }
+
+    #[test]
+    fn adding_and_subtracting_tiles_and_chunks_round_trips() {
+        let a = tile(-33, 7);
+        let b = tile(40, -1);
+        assert_eq!(a + b - b, a);
+        assert_eq!(b - a + a, b);
+
+        let a = chunk(-33, 7);
+        let b = chunk(40, -1);
+        assert_eq!(a + b - b, a);
+        assert_eq!(b - a + a, b);
+    }
+
+    #[test]
+    fn adding_and_subtracting_tiles_and_chunks_saturates() {
+        assert_eq!(
+            tile(i64::MAX, i64::MIN) + tile(1, -1),
+            tile(i64::MAX, i64::MIN)
+        );
+        assert_eq!(
+            tile(i64::MIN, i64::MAX) - tile(1, -1),
+            tile(i64::MIN, i64::MAX)
+        );
+        assert_eq!(
+            tile(i64::MIN, 0).distance_squared(tile(i64::MAX, 0)),
+            i64::MAX
+        );
+        assert_eq!(
+            chunk(i32::MAX, i32::MIN) + chunk(1, -1),
+            chunk(i32::MAX, i32::MIN)
+        );
+        assert_eq!(
+            chunk(i32::MIN, i32::MAX) - chunk(1, -1),
+            chunk(i32::MIN, i32::MAX)
+        );
+    }
+
+    #[test]
+    fn rect_includes_both_corners_a_column_at_a_time() {
+        let chunks: Vec<_> = ChunkPos::rect(chunk(-1, -1), chunk(0, 1)).collect();
+        assert_eq!(
+            chunks,
+            [
+                chunk(-1, -1),
+                chunk(-1, 0),
+                chunk(-1, 1),
+                chunk(0, -1),
+                chunk(0, 0),
+                chunk(0, 1),
+            ]
+        );
+
+        let tiles: Vec<_> = TilePos::rect(tile(3, 3), tile(3, 3)).collect();
+        assert_eq!(tiles, [tile(3, 3)]);
+        assert_eq!(TilePos::rect(tile(1, 0), tile(0, 0)).count(), 0);
+    }
+
+    #[test]
+    fn tile_index_all_covers_the_chunk_once() {
+        let indices: Vec<_> = TileIndex::all().collect();
+        assert_eq!(indices.len(), 32 * 32);
+        assert_eq!(indices[0], TileIndex { x: 0, y: 0 });
+        assert_eq!(indices[1], TileIndex { x: 0, y: 1 });
+        assert_eq!(indices[indices.len() - 1], TileIndex { x: 31, y: 31 });
+
+        let unique: std::collections::HashSet<_> = indices.iter().collect();
+        assert_eq!(unique.len(), indices.len());
+        assert!(indices.iter().all(|index| index.x < 32 && index.y < 32));
     }
 }
@@ -0,0 +1,387 @@
+//! Maps keys, mouse buttons and gamepad buttons to the actions they trigger.
+//!
+//! On desktop, the default bindings can be overridden from `bindings.toml` in the working
+//! directory. Each line binds an action (by its `Action::name`) to a list of inputs, for example
+//! `undo = ["Ctrl+Z", "Super+Z"]`. Inputs are written as any modifiers (`Ctrl`, `Shift`, `Alt` or
+//! `Super`) followed by a key or button, separated by `+`. Keys use the names of winit's
+//! `KeyCode`s (such as `KeyW`, `ArrowUp` or `F5`), or just the letter or digit for letter and digit
+//! keys. Mouse buttons are `MouseLeft`, `MouseMiddle`, `MouseRight`, `MouseBack` and
+//! `MouseForward`. Gamepad buttons are `Pad` followed by the name of a gilrs `Button`, such as
+//! `PadSouth`, `PadStart` or `PadDPadUp`. Actions that aren't in the file keep their default
+//! bindings, and an empty list unbinds an action.
+
+use crate::ui::read_text_file_if_exists;
+use gilrs::Button;
+use serde::de::value::StringDeserializer;
+use serde::de::IntoDeserializer;
+use serde::Deserialize;
+use std::collections::{HashMap, HashSet};
+use std::fmt::Write;
+use winit::event::MouseButton;
+use winit::keyboard::{KeyCode, ModifiersState};
+
+/// Something that the user can do with a key or button.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Action {
+    PanUp,
+    PanDown,
+    PanLeft,
+    PanRight,
+    ZoomIn,
+    ZoomOut,
+    /// Paints with the brush, or selects the tile to inspect. Also clicks on the debug UI.
+    Interact,
+    DragCamera,
+    TogglePanels,
+    Pause,
+    Save,
+    Load,
+    Undo,
+    Redo,
+    CycleOverlay,
+    CycleOverlayBackwards,
+    IncreaseOverlayOpacity,
+    DecreaseOverlayOpacity,
+    ToggleChunkGrid,
+    ToggleTileGrid,
+    ToggleMinimap,
+    /// Opens a prompt for coordinates to fly the camera to.
+    GoTo,
+    /// Opens a prompt for the name of a new bookmark at the camera's position.
+    AddBookmark,
+    /// Locks the camera onto the selected settlement (or the nearest one), or unlocks it.
+    ToggleFollow,
+    /// Splits off another viewport, looking at the same place as the active one.
+    AddViewport,
+    /// Closes the active viewport, unless it's the only one.
+    RemoveViewport,
+}
+
+impl Action {
+    const ALL: [Action; 26] = [
+        Action::PanUp,
+        Action::PanDown,
+        Action::PanLeft,
+        Action::PanRight,
+        Action::ZoomIn,
+        Action::ZoomOut,
+        Action::Interact,
+        Action::DragCamera,
+        Action::TogglePanels,
+        Action::Pause,
+        Action::Save,
+        Action::Load,
+        Action::Undo,
+        Action::Redo,
+        Action::CycleOverlay,
+        Action::CycleOverlayBackwards,
+        Action::IncreaseOverlayOpacity,
+        Action::DecreaseOverlayOpacity,
+        Action::ToggleChunkGrid,
+        Action::ToggleTileGrid,
+        Action::ToggleMinimap,
+        Action::GoTo,
+        Action::AddBookmark,
+        Action::ToggleFollow,
+        Action::AddViewport,
+        Action::RemoveViewport,
+    ];
+
+    /// The name of the action in the bindings file.
+    pub fn name(self) -> &'static str {
+        match self {
+            Action::PanUp => "pan_up",
+            Action::PanDown => "pan_down",
+            Action::PanLeft => "pan_left",
+            Action::PanRight => "pan_right",
+            Action::ZoomIn => "zoom_in",
+            Action::ZoomOut => "zoom_out",
+            Action::Interact => "interact",
+            Action::DragCamera => "drag_camera",
+            Action::TogglePanels => "toggle_panels",
+            Action::Pause => "pause",
+            Action::Save => "save",
+            Action::Load => "load",
+            Action::Undo => "undo",
+            Action::Redo => "redo",
+            Action::CycleOverlay => "cycle_overlay",
+            Action::CycleOverlayBackwards => "cycle_overlay_backwards",
+            Action::IncreaseOverlayOpacity => "increase_overlay_opacity",
+            Action::DecreaseOverlayOpacity => "decrease_overlay_opacity",
+            Action::ToggleChunkGrid => "toggle_chunk_grid",
+            Action::ToggleTileGrid => "toggle_tile_grid",
+            Action::ToggleMinimap => "toggle_minimap",
+            Action::GoTo => "go_to",
+            Action::AddBookmark => "add_bookmark",
+            Action::ToggleFollow => "toggle_follow",
+            Action::AddViewport => "add_viewport",
+            Action::RemoveViewport => "remove_viewport",
+        }
+    }
+
+    /// The inputs that trigger the action when there's no bindings file. The analog sticks and
+    /// triggers on gamepads aren't bound to actions, since they pan and zoom by varying amounts.
+    fn default_bindings(self) -> &'static [&'static str] {
+        match self {
+            Action::PanUp => &["W", "ArrowUp", "PadDPadUp"],
+            Action::PanDown => &["S", "ArrowDown", "PadDPadDown"],
+            Action::PanLeft => &["A", "ArrowLeft", "PadDPadLeft"],
+            Action::PanRight => &["D", "ArrowRight", "PadDPadRight"],
+            Action::ZoomIn => &["Equal"],
+            Action::ZoomOut => &["Minus"],
+            Action::Interact => &["MouseLeft"],
+            Action::DragCamera => &["MouseMiddle", "MouseRight"],
+            Action::TogglePanels => &["F1", "PadSelect"],
+            Action::Pause => &["Space", "PadStart"],
+            Action::Save => &["F5"],
+            Action::Load => &["F9"],
+            Action::Undo => &["Ctrl+Z", "Super+Z", "PadWest"],
+            Action::Redo => &["Ctrl+Shift+Z", "Super+Shift+Z", "PadEast"],
+            Action::CycleOverlay => &["O", "PadRightTrigger"],
+            Action::CycleOverlayBackwards => &["Shift+O", "PadLeftTrigger"],
+            Action::IncreaseOverlayOpacity => &["BracketRight"],
+            Action::DecreaseOverlayOpacity => &["BracketLeft"],
+            Action::ToggleChunkGrid => &["G", "PadNorth"],
+            Action::ToggleTileGrid => &["Shift+G"],
+            Action::ToggleMinimap => &["M"],
+            Action::GoTo => &["Ctrl+G", "Super+G"],
+            Action::AddBookmark => &["Ctrl+B", "Super+B"],
+            Action::ToggleFollow => &["F", "PadSouth"],
+            Action::AddViewport => &["V"],
+            Action::RemoveViewport => &["Shift+V"],
+        }
+    }
+}
+
+/// A key, mouse button or gamepad button.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum InputButton {
+    Key(KeyCode),
+    Mouse(MouseButton),
+    Gamepad(Button),
+}
+
+/// A key or button, along with the modifier keys that have to be held down with it.
+#[derive(Copy, Clone, PartialEq, Eq, Hash)]
+struct Binding {
+    button: InputButton,
+    modifiers: ModifiersState,
+}
+
+impl Binding {
+    /// Reads a binding written like `Ctrl+Shift+Z`.
+    fn parse(text: &str) -> Result<Self, String> {
+        let mut parts: Vec<&str> = text.split('+').map(str::trim).collect();
+        let button_name = parts.pop().unwrap_or_default();
+
+        let mut modifiers = ModifiersState::empty();
+        for part in parts {
+            modifiers |= match part {
+                "Ctrl" => ModifiersState::CONTROL,
+                "Shift" => ModifiersState::SHIFT,
+                "Alt" => ModifiersState::ALT,
+                "Super" => ModifiersState::SUPER,
+                _ => return Err(format!("unknown modifier {part} in {text}")),
+            };
+        }
+
+        let button = match button_name {
+            "MouseLeft" => InputButton::Mouse(MouseButton::Left),
+            "MouseMiddle" => InputButton::Mouse(MouseButton::Middle),
+            "MouseRight" => InputButton::Mouse(MouseButton::Right),
+            "MouseBack" => InputButton::Mouse(MouseButton::Back),
+            "MouseForward" => InputButton::Mouse(MouseButton::Forward),
+            _ if button_name.starts_with("Pad") => InputButton::Gamepad(
+                parse_gamepad_button(&button_name["Pad".len()..])
+                    .ok_or_else(|| format!("unknown gamepad button {button_name} in {text}"))?,
+            ),
+            _ => InputButton::Key(
+                parse_key_code(button_name)
+                    .ok_or_else(|| format!("unknown key {button_name} in {text}"))?,
+            ),
+        };
+
+        Ok(Binding { button, modifiers })
+    }
+
+    /// Writes the binding the same way that `parse` reads it, for showing to the user.
+    fn describe(self) -> String {
+        let mut text = String::new();
+        for (modifier, name) in [
+            (ModifiersState::CONTROL, "Ctrl"),
+            (ModifiersState::SHIFT, "Shift"),
+            (ModifiersState::ALT, "Alt"),
+            (ModifiersState::SUPER, "Super"),
+        ] {
+            if self.modifiers.contains(modifier) {
+                text.push_str(name);
+                text.push('+');
+            }
+        }
+
+        match self.button {
+            InputButton::Key(key_code) => {
+                let name = format!("{key_code:?}");
+                // Letter and digit keys are shown as just the letter or digit.
+                let short_name = name
+                    .strip_prefix("Key")
+                    .or_else(|| name.strip_prefix("Digit"))
+                    .unwrap_or(&name);
+                text.push_str(short_name);
+            }
+            InputButton::Mouse(button) => {
+                let _ = write!(text, "Mouse{button:?}");
+            }
+            InputButton::Gamepad(button) => {
+                let _ = write!(text, "Pad{button:?}");
+            }
+        }
+        text
+    }
+}
+
+/// Reads the name of a winit `KeyCode`, such as `KeyW` or `ArrowUp`. Letter and digit keys can be
+/// written as just the letter or digit.
+fn parse_key_code(name: &str) -> Option<KeyCode> {
+    let full_name = match name.chars().next() {
+        Some(character) if name.len() == 1 && character.is_ascii_alphabetic() => {
+            format!("Key{}", character.to_ascii_uppercase())
+        }
+        Some(character) if name.len() == 1 && character.is_ascii_digit() => {
+            format!("Digit{character}")
+        }
+        _ => name.to_string(),
+    };
+    let deserializer: StringDeserializer<serde::de::value::Error> = full_name.into_deserializer();
+    KeyCode::deserialize(deserializer).ok()
+}
+
+/// Reads the name of a gilrs `Button`, such as `South` or `DPadUp`.
+fn parse_gamepad_button(name: &str) -> Option<Button> {
+    let deserializer: StringDeserializer<serde::de::value::Error> =
+        name.to_string().into_deserializer();
+    Button::deserialize(deserializer)
+        .ok()
+        .filter(|button| *button != Button::Unknown)
+}
+
+/// Which inputs trigger which actions.
+pub struct Bindings {
+    bindings: Vec<(Binding, Action)>,
+}
+
+impl Bindings {
+    /// Loads the bindings file if there is one, using the defaults for anything it doesn't cover.
+    /// Problems with the file and inputs that are bound to more than one action are reported, but
+    /// don't stop the bindings from loading.
+    pub fn load() -> Self {
+        let config = read_text_file_if_exists(BINDINGS_FILE_PATH).and_then(|text| {
+            toml::from_str::<HashMap<String, Vec<String>>>(&text)
+                .map_err(|error| eprintln!("Couldn't read {BINDINGS_FILE_PATH}: {error}"))
+                .ok()
+        });
+
+        let mut bindings = vec![];
+        for action in Action::ALL {
+            let texts: Vec<&str> =
+                match config.as_ref().and_then(|config| config.get(action.name())) {
+                    Some(texts) => texts.iter().map(String::as_str).collect(),
+                    None => action.default_bindings().to_vec(),
+                };
+            for text in texts {
+                match Binding::parse(text) {
+                    Ok(binding) => bindings.push((binding, action)),
+                    Err(error) => eprintln!("Ignoring binding for {}: {error}", action.name()),
+                }
+            }
+        }
+
+        if let Some(config) = &config {
+            for name in config.keys() {
+                if !Action::ALL.iter().any(|action| action.name() == name) {
+                    eprintln!(
+                        "Ignoring bindings for unknown action {name} in {BINDINGS_FILE_PATH}"
+                    );
+                }
+            }
+        }
+
+        let bindings = Bindings { bindings };
+        bindings.report_conflicts();
+        bindings
+    }
+
+    /// The actions to start when the button is pressed while the given modifiers are held. If
+    /// bindings with different modifiers match, only the most specific ones are used, so that (for
+    /// example) Ctrl+Shift+Z doesn't also trigger Ctrl+Z.
+    pub fn pressed_actions(&self, button: InputButton, modifiers: ModifiersState) -> Vec<Action> {
+        let matching: Vec<&(Binding, Action)> = self
+            .bindings
+            .iter()
+            .filter(|(binding, _)| {
+                binding.button == button && modifiers.contains(binding.modifiers)
+            })
+            .collect();
+        let most_modifiers = matching
+            .iter()
+            .map(|(binding, _)| binding.modifiers.bits().count_ones())
+            .max()
+            .unwrap_or_default();
+
+        matching
+            .into_iter()
+            .filter(|(binding, _)| binding.modifiers.bits().count_ones() == most_modifiers)
+            .map(|&(_, action)| action)
+            .collect()
+    }
+
+    /// The actions to stop when the button is released. Modifiers are ignored, since they may have
+    /// been let go of first.
+    pub fn released_actions(&self, button: InputButton) -> Vec<Action> {
+        self.bindings
+            .iter()
+            .filter(|(binding, _)| binding.button == button)
+            .map(|&(_, action)| action)
+            .collect()
+    }
+
+    /// Whether any of the buttons bound to the action are held down.
+    pub fn is_held(&self, action: Action, held_buttons: &HashSet<InputButton>) -> bool {
+        self.bindings.iter().any(|(binding, bound_action)| {
+            *bound_action == action && held_buttons.contains(&binding.button)
+        })
+    }
+
+    /// A label for the action's first binding, for showing next to the things it does in the debug
+    /// UI.
+    pub fn describe(&self, action: Action) -> String {
+        self.bindings
+            .iter()
+            .find(|(_, bound_action)| *bound_action == action)
+            .map_or_else(|| "unbound".to_string(), |(binding, _)| binding.describe())
+    }
+
+    fn report_conflicts(&self) {
+        let mut actions_by_binding: HashMap<Binding, Vec<Action>> = HashMap::new();
+        for &(binding, action) in &self.bindings {
+            let actions = actions_by_binding.entry(binding).or_default();
+            if !actions.contains(&action) {
+                actions.push(action);
+            }
+        }
+
+        for (binding, actions) in actions_by_binding {
+            if actions.len() > 1 {
+                let names: Vec<_> = actions.iter().map(|action| action.name()).collect();
+                eprintln!(
+                    "{} is bound to more than one action: {}",
+                    binding.describe(),
+                    names.join(", ")
+                );
+            }
+        }
+    }
+}
+
+/// Where the bindings are loaded from, relative to the working directory.
+const BINDINGS_FILE_PATH: &str = "bindings.toml";
@@ -0,0 +1,88 @@
+//! Named camera positions that the user can fly back to. On desktop, they're kept in
+//! `bookmarks.toml` in the working directory so that they last between sessions.
+
+use crate::position::PixelPos;
+use crate::ui::{read_text_file_if_exists, write_file};
+use serde::{Deserialize, Serialize};
+
+#[derive(Default, Serialize, Deserialize)]
+pub struct Bookmarks {
+    bookmarks: Vec<Bookmark>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct Bookmark {
+    pub name: String,
+    /// The position of the camera, in tiles.
+    x: f64,
+    y: f64,
+    zoom_level: f32,
+}
+
+impl Bookmark {
+    pub fn pos(&self) -> PixelPos {
+        PixelPos::from_tiles(self.x, self.y)
+    }
+
+    pub fn zoom_level(&self) -> f32 {
+        self.zoom_level
+    }
+}
+
+impl Bookmarks {
+    /// Loads the bookmarks file, or starts with no bookmarks if there isn't one.
+    pub fn load() -> Self {
+        let Some(text) = read_text_file_if_exists(BOOKMARKS_FILE_PATH) else {
+            return Bookmarks::default();
+        };
+        toml::from_str(&text).unwrap_or_else(|error| {
+            eprintln!("Couldn't read {BOOKMARKS_FILE_PATH}: {error}");
+            Bookmarks::default()
+        })
+    }
+
+    /// Adds a bookmark and saves the bookmarks. If no name is given, the bookmark is numbered
+    /// instead.
+    pub fn add(&mut self, name: &str, pos: &PixelPos, zoom_level: f32) {
+        let name = name.trim();
+        let name = if name.is_empty() {
+            format!("Bookmark {}", self.bookmarks.len() + 1)
+        } else {
+            name.to_string()
+        };
+
+        self.bookmarks.push(Bookmark {
+            name,
+            x: pos.tile_x(),
+            y: pos.tile_y(),
+            zoom_level,
+        });
+        self.save();
+    }
+
+    /// Removes a bookmark and saves the bookmarks.
+    pub fn remove(&mut self, index: usize) {
+        if index < self.bookmarks.len() {
+            self.bookmarks.remove(index);
+            self.save();
+        }
+    }
+
+    pub fn get(&self, index: usize) -> Option<&Bookmark> {
+        self.bookmarks.get(index)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Bookmark> {
+        self.bookmarks.iter()
+    }
+
+    fn save(&self) {
+        match toml::to_string(self) {
+            Ok(text) => write_file(BOOKMARKS_FILE_PATH, text.as_bytes()),
+            Err(error) => eprintln!("Couldn't save the bookmarks: {error}"),
+        }
+    }
+}
+
+/// Where the bookmarks are kept, relative to the working directory.
+const BOOKMARKS_FILE_PATH: &str = "bookmarks.toml";
@@ -0,0 +1,169 @@
+use crate::position::TilePos;
+use crate::world::{Tile, TileData, TileEdit, World, WorldCommand};
+use std::collections::HashMap;
+
+/// A tool for editing the world by hand. While the mouse is held down, the brush is applied to
+/// every tile within its radius of the cursor once per tick. Each stroke becomes a single command
+/// once it's finished, so that it can be undone in one go.
+pub struct Brush {
+    pub enabled: bool,
+    pub mode: BrushMode,
+    /// The radius of the brush in tiles.
+    pub radius: f32,
+    /// The fraction of the radius (measured inwards from the edge) over which the brush fades
+    /// out. 0 is a hard edge, 1 fades out all the way from the center.
+    pub falloff: f32,
+    /// The amount that elevation is changed by per tick at the center of the brush.
+    pub strength: f32,
+    /// The edits made since the mouse was pressed, if it's currently held down.
+    current_stroke: Option<Stroke>,
+}
+
+impl Default for Brush {
+    fn default() -> Self {
+        Brush {
+            enabled: false,
+            mode: BrushMode::Paint(Tile::Grass),
+            radius: 3.0,
+            falloff: 0.5,
+            strength: 0.01,
+            current_stroke: None,
+        }
+    }
+}
+
+impl Brush {
+    /// Applies the brush around the given tile as part of the current stroke (starting a new
+    /// stroke if there isn't one), returning the edits that it made.
+    pub fn apply(&mut self, world: &mut World, center: TilePos) -> Vec<TileEdit> {
+        let reach = self.radius.ceil() as i64;
+        let reach = TilePos { x: reach, y: reach };
+        let mut edits = vec![];
+
+        for coords in TilePos::rect(center - reach, center + reach) {
+            let distance = (center.distance_squared(coords) as f32).sqrt();
+            let weight = self.weight(distance);
+            if weight <= 0.0 {
+                continue;
+            }
+            let Some(before) = world.tile_data(coords) else {
+                continue;
+            };
+
+            let after = match self.mode {
+                // Painting has no in-between, so only paint the tiles that are mostly covered.
+                BrushMode::Paint(tile) if weight >= 0.5 => TileData { tile, ..before },
+                BrushMode::Paint(_) => continue,
+                BrushMode::Raise => Self::with_elevation(before, self.strength * weight),
+                BrushMode::Lower => Self::with_elevation(before, -self.strength * weight),
+            };
+            if after == before {
+                continue;
+            }
+
+            world.set_tile_data(coords, after);
+            self.current_stroke
+                .get_or_insert_with(Stroke::default)
+                .record(coords, before, after);
+            edits.push(TileEdit {
+                coords,
+                before,
+                after,
+            });
+        }
+
+        edits
+    }
+
+    /// Ends the current stroke, returning a command with all of its edits. The edits have already
+    /// been applied to the world.
+    pub fn end_stroke(&mut self) -> Option<WorldCommand> {
+        let stroke = self.current_stroke.take()?;
+
+        Some(WorldCommand::EditTiles(
+            stroke
+                .edits
+                .into_iter()
+                .map(|(coords, (before, after))| TileEdit {
+                    coords,
+                    before,
+                    after,
+                })
+                .collect(),
+        ))
+    }
+
+    /// How strongly the brush affects a tile at the given distance (in tiles) from its center,
+    /// from 0 to 1.
+    fn weight(&self, distance: f32) -> f32 {
+        if distance > self.radius {
+            return 0.0;
+        }
+
+        let falloff_start = self.radius * (1.0 - self.falloff);
+        if distance <= falloff_start {
+            1.0
+        } else {
+            1.0 - (distance - falloff_start) / (self.radius - falloff_start)
+        }
+    }
+
+    /// Changes a tile's elevation, updating its type to match.
+    fn with_elevation(data: TileData, change: f32) -> TileData {
+        let elevation = data.elevation + change;
+        TileData {
+            tile: Tile::from_elevation(elevation),
+            elevation,
+        }
+    }
+}
+
+#[derive(Copy, Clone, PartialEq)]
+pub enum BrushMode {
+    /// Sets tiles to the given type.
+    Paint(Tile),
+    Raise,
+    Lower,
+}
+
+impl BrushMode {
+    /// Every mode, in the order they're cycled through in the UI.
+    pub const ALL: [BrushMode; 6] = [
+        BrushMode::Paint(Tile::Grass),
+        BrushMode::Paint(Tile::Beach),
+        BrushMode::Paint(Tile::Water),
+        BrushMode::Paint(Tile::Mountain),
+        BrushMode::Raise,
+        BrushMode::Lower,
+    ];
+
+    pub fn next(self) -> Self {
+        let index = Self::ALL.iter().position(|&mode| mode == self).unwrap_or(0);
+        Self::ALL[(index + 1) % Self::ALL.len()]
+    }
+
+    pub fn name(self) -> String {
+        match self {
+            BrushMode::Paint(tile) => format!("Paint {tile:?}"),
+            BrushMode::Raise => "Raise".to_string(),
+            BrushMode::Lower => "Lower".to_string(),
+        }
+    }
+}
+
+/// All the tiles changed by a single press of the mouse.
+#[derive(Default)]
+struct Stroke {
+    /// The state of each changed tile before and after the stroke.
+    edits: HashMap<TilePos, (TileData, TileData)>,
+}
+
+impl Stroke {
+    fn record(&mut self, coords: TilePos, before: TileData, after: TileData) {
+        // Keep the state from before the first time the stroke touched the tile.
+        self.edits
+            .entry(coords)
+            .and_modify(|(_, existing_after)| *existing_after = after)
+            .or_insert((before, after));
+    }
+}
@@ -1,12 +1,37 @@
-use crate::position::{Position, PositionMode};
+use crate::position::{ChunkPos, PixelPos};
 use std::collections::HashSet;
+use winit::dpi::{PhysicalPosition, PhysicalSize};
 
 #[derive(Default)]
 pub struct Camera {
-    pub pos: Position,
-    /// Note that the zoom level can be a decimal value, but only the value before the decimal place
-    /// is used when determining zoom multiplier.
+    pub pos: PixelPos,
+    /// The zoom level currently shown. This eases towards `target_zoom_level` over a few ticks
+    /// rather than jumping straight to it.
     zoom_level: f32,
+    /// The zoom level that the user asked for.
+    target_zoom_level: f32,
+    /// The point on the screen (in pixels from the top left corner of the viewport) that stays over
+    /// the same part of the world while zooming, or None to zoom around the center of the screen.
+    zoom_anchor: Option<PhysicalPosition<f64>>,
+    /// How far the camera keeps moving each tick after being dragged, in pixels in the world.
+    velocity: [f64; 2],
+    /// How far the camera has been dragged since the last tick, in pixels in the world.
+    drag_movement: [f64; 2],
+    /// The smooth move to another part of the world that is in progress, if any.
+    flight: Option<Flight>,
+}
+
+/// A smooth move to another part of the world, started by `Camera::fly_to`.
+struct Flight {
+    start_pos: PixelPos,
+    target_pos: PixelPos,
+    start_zoom_level: f32,
+    target_zoom_level: f32,
+    /// How many zoom levels the camera zooms out by halfway through the flight, so that long
+    /// flights don't turn into a blur.
+    zoom_out_levels: f32,
+    /// How far through the flight the camera is, from 0 (just started) to 1 (arrived).
+    progress: f32,
 }
 
 impl Camera {
@@ -17,23 +42,72 @@ impl Camera {
         }
     }
 
+    /// A camera that isn't moving, looking at the same place as this one at the zoom it's heading
+    /// towards.
+    #[must_use]
+    pub fn still_copy(&self) -> Self {
+        Camera {
+            pos: self.pos,
+            zoom_level: self.target_zoom_level,
+            target_zoom_level: self.target_zoom_level,
+            ..Default::default()
+        }
+    }
+
     pub fn pan(&mut self, direction: &MoveDirection) {
+        self.pan_by(direction.to_vector());
+    }
+
+    /// Moves the camera by a fraction of its full speed along each axis, with y pointing up. This
+    /// allows for speeds in between stopped and full speed, for inputs like analog sticks.
+    pub fn pan_by(&mut self, direction: [f64; 2]) {
         let speed = CAMERA_SPEED_PX / f64::from(self.zoom_multiplier());
+        self.pos += direction.map(|fraction| fraction * speed);
+    }
 
-        let primary_directions = direction.to_primary_directions();
-        let pos = &mut self.pos;
+    /// Converts a distance on the screen (in pixels, with y pointing down) into the distance in the
+    /// world (in pixels, with y pointing up) that it covers at the current zoom.
+    pub fn screen_delta_to_world_delta(&self, screen_delta: [f64; 2]) -> [f64; 2] {
+        let zoom = f64::from(self.zoom_multiplier());
+        [screen_delta[0] / zoom, -screen_delta[1] / zoom]
+    }
 
-        if primary_directions.contains(&&PrimaryDirection::North) {
-            pos.set_y(pos.y(PositionMode::Pixels) + speed, PositionMode::Pixels);
-        }
-        if primary_directions.contains(&&PrimaryDirection::East) {
-            pos.set_x(pos.x(PositionMode::Pixels) + speed, PositionMode::Pixels);
+    /// Moves the camera so that the world follows the cursor, which moved by `screen_delta` (in
+    /// pixels on the screen).
+    pub fn drag(&mut self, screen_delta: [f64; 2]) {
+        let [x, y] = self.screen_delta_to_world_delta(screen_delta);
+        self.drag_movement[0] -= x;
+        self.drag_movement[1] -= y;
+        self.pos += [-x, -y];
+    }
+
+    /// Keeps the camera gliding after it's let go of, slowing down a bit every tick. While it's being
+    /// dragged, this instead measures how fast it's moving so that it can carry on at that speed once
+    /// it's let go of. Should be called once per tick.
+    pub fn update_inertia(&mut self, is_dragging: bool, inertia_enabled: bool) {
+        if is_dragging {
+            // Smooth the speed out over a few ticks, since the cursor doesn't move evenly.
+            for axis in 0..2 {
+                self.velocity[axis] = self.velocity[axis] * (1.0 - DRAG_VELOCITY_SMOOTHING)
+                    + self.drag_movement[axis] * DRAG_VELOCITY_SMOOTHING;
+            }
+            self.drag_movement = [0.0; 2];
+            return;
         }
-        if primary_directions.contains(&&PrimaryDirection::South) {
-            pos.set_y(pos.y(PositionMode::Pixels) - speed, PositionMode::Pixels);
+
+        if !inertia_enabled {
+            self.velocity = [0.0; 2];
+            return;
         }
-        if primary_directions.contains(&&PrimaryDirection::West) {
-            pos.set_x(pos.x(PositionMode::Pixels) - speed, PositionMode::Pixels);
+
+        self.pos += self.velocity;
+        self.velocity = self.velocity.map(|speed| speed * INERTIA_FRICTION);
+
+        // Stop completely once the movement is too small to see.
+        let speed_on_screen =
+            self.velocity[0].hypot(self.velocity[1]) * f64::from(self.zoom_multiplier());
+        if speed_on_screen < MIN_INERTIA_SPEED_PX {
+            self.velocity = [0.0; 2];
         }
     }
 
@@ -41,16 +115,231 @@ impl Camera {
     ///
     /// For example, a value of 2 would indicate that everything looks twice as big as normal.
     pub fn zoom_multiplier(&self) -> f32 {
-        // Round the zoom level so that zooming happens in increments instead of continuously.
-        let clipped_zoom_level = self.zoom_level as i32;
-        CAMERA_ZOOM_LEVEL_MULTIPLIER.powf(clipped_zoom_level as f32)
+        CAMERA_ZOOM_LEVEL_MULTIPLIER.powf(self.zoom_level)
     }
 
-    pub fn zoom(&mut self, lines: f32) {
-        self.zoom_level += lines;
-        self.zoom_level = self
-            .zoom_level
+    /// How the camera maps the world onto a window (or viewport) of the given size. Everything that
+    /// converts between the world and the screen goes through this, including the shaders.
+    pub fn view_transform(&self, window_size: PhysicalSize<u32>) -> ViewTransform {
+        let zoom = f64::from(self.zoom_multiplier());
+        let origin_chunk = self.pos.chunk();
+        let [camera_x, camera_y] = self.pos - origin_chunk.bottom_left();
+
+        // The camera is at the center of the window, and the y axis points up in the world but
+        // down on the screen.
+        ViewTransform {
+            origin_chunk,
+            scale: [zoom, -zoom],
+            offset: [
+                f64::from(window_size.width) / 2.0 - camera_x * zoom,
+                f64::from(window_size.height) / 2.0 + camera_y * zoom,
+            ],
+            window_size,
+        }
+    }
+
+    /// Converts a position on the screen (in pixels from the top left corner of the window) into
+    /// the position in the world that is displayed there.
+    pub fn screen_to_world(
+        &self,
+        screen_pos: PhysicalPosition<f64>,
+        window_size: PhysicalSize<u32>,
+    ) -> PixelPos {
+        self.view_transform(window_size)
+            .screen_to_world([screen_pos.x, screen_pos.y])
+    }
+
+    /// The inverse of `screen_to_world`. Converts a position in the world into the position on the
+    /// screen (in pixels from the top left corner of the window) where it is displayed, or None if
+    /// it isn't in the window.
+    pub fn world_to_screen(
+        &self,
+        world_pos: &PixelPos,
+        window_size: PhysicalSize<u32>,
+    ) -> Option<[f32; 2]> {
+        let [x, y] = self.view_transform(window_size).world_to_screen(world_pos);
+        // Positions in the world are rounded to the nearest subpixel, so a position worked out from
+        // the edge of the screen can end up a tiny bit outside of it.
+        let is_on_screen = |coordinate: f64, size: u32| {
+            (-SCREEN_EDGE_TOLERANCE_PX..=f64::from(size) + SCREEN_EDGE_TOLERANCE_PX)
+                .contains(&coordinate)
+        };
+        (is_on_screen(x, window_size.width) && is_on_screen(y, window_size.height))
+            .then_some([x as f32, y as f32])
+    }
+
+    /// The bottom left and top right corners of the part of the world that can be seen in a window
+    /// (or viewport) of the given size.
+    pub fn visible_bounds(&self, window_size: PhysicalSize<u32>) -> (PixelPos, PixelPos) {
+        let bottom_left = self.screen_to_world(
+            PhysicalPosition::new(0.0, f64::from(window_size.height)),
+            window_size,
+        );
+        let top_right = self.screen_to_world(
+            PhysicalPosition::new(f64::from(window_size.width), 0.0),
+            window_size,
+        );
+        (bottom_left, top_right)
+    }
+
+    /// The zoom level that the camera is at or is easing towards, for getting back to the same zoom
+    /// later.
+    pub fn target_zoom_level(&self) -> f32 {
+        self.target_zoom_level
+    }
+
+    /// Starts flying smoothly to a position in the world, and to a zoom level if one is given. The
+    /// flight is animated by `update_flight`, and replaces any flight that was already in progress.
+    pub fn fly_to(&mut self, target_pos: PixelPos, target_zoom_level: Option<f32>) {
+        let target_zoom_level = target_zoom_level
+            .unwrap_or(self.target_zoom_level)
             .clamp(CAMERA_MIN_ZOOM_LEVEL, CAMERA_MAX_ZOOM_LEVEL);
+
+        // Zoom out far enough that the distance being flown looks like a comfortable distance on
+        // the screen.
+        let distance_px = self.pos.distance(&target_pos);
+        let distance_on_screen_px = distance_px * f64::from(self.zoom_multiplier());
+        let zoom_out_levels = (distance_on_screen_px / FLIGHT_COMFORTABLE_DISTANCE_PX)
+            .log(f64::from(CAMERA_ZOOM_LEVEL_MULTIPLIER))
+            .max(0.0) as f32;
+
+        self.velocity = [0.0; 2];
+        self.zoom_anchor = None;
+        self.flight = Some(Flight {
+            start_pos: self.pos,
+            target_pos,
+            start_zoom_level: self.zoom_level,
+            target_zoom_level,
+            zoom_out_levels,
+            progress: 0.0,
+        });
+    }
+
+    pub fn is_flying(&self) -> bool {
+        self.flight.is_some()
+    }
+
+    /// Cancels the flight in progress, leaving the camera wherever it got to.
+    pub fn stop_flying(&mut self) {
+        self.flight = None;
+    }
+
+    /// Moves the camera along the flight in progress, if there is one. Should be called once per
+    /// tick.
+    pub fn update_flight(&mut self) {
+        let Some(flight) = &mut self.flight else {
+            return;
+        };
+
+        flight.progress = (flight.progress + 1.0 / FLIGHT_DURATION_TICKS).min(1.0);
+        // Ease in and out, so that the camera doesn't jerk into or out of motion.
+        let eased = flight.progress * flight.progress * (3.0 - 2.0 * flight.progress);
+
+        let journey = flight.target_pos - flight.start_pos;
+        self.pos = flight.start_pos + journey.map(|distance| distance * f64::from(eased));
+
+        let zoom_out = flight.zoom_out_levels * (eased * std::f32::consts::PI).sin();
+        self.zoom_level = (flight.start_zoom_level
+            + (flight.target_zoom_level - flight.start_zoom_level) * eased
+            - zoom_out)
+            .max(CAMERA_MIN_ZOOM_LEVEL);
+        // Keep `update_zoom` from pulling the zoom somewhere else.
+        self.target_zoom_level = self.zoom_level;
+
+        if flight.progress >= 1.0 {
+            self.flight = None;
+        }
+    }
+
+    /// Starts zooming in (or out, for negative amounts) by the given number of zoom levels. The
+    /// zoom is animated by `update_zoom`, keeping the part of the world under `anchor` (a position
+    /// on the screen) in place, or the part at the center of the screen if there's no anchor.
+    pub fn zoom(&mut self, levels: f32, anchor: Option<PhysicalPosition<f64>>) {
+        // Zooming during a flight changes where the flight ends up instead.
+        if let Some(flight) = &mut self.flight {
+            flight.target_zoom_level = (flight.target_zoom_level + levels)
+                .clamp(CAMERA_MIN_ZOOM_LEVEL, CAMERA_MAX_ZOOM_LEVEL);
+            return;
+        }
+
+        self.target_zoom_level =
+            (self.target_zoom_level + levels).clamp(CAMERA_MIN_ZOOM_LEVEL, CAMERA_MAX_ZOOM_LEVEL);
+        self.zoom_anchor = anchor;
+    }
+
+    /// Moves the zoom level part of the way towards the level that was asked for. Should be called
+    /// once per tick.
+    pub fn update_zoom(&mut self, window_size: PhysicalSize<u32>) {
+        let remaining = self.target_zoom_level - self.zoom_level;
+        if remaining.abs() < f32::EPSILON {
+            return;
+        }
+
+        let anchor = self.zoom_anchor.unwrap_or(PhysicalPosition::new(
+            f64::from(window_size.width) / 2.0,
+            f64::from(window_size.height) / 2.0,
+        ));
+        let anchored_world_pos = self.screen_to_world(anchor, window_size);
+
+        // Ease out, so that the zoom starts quickly and slows down as it gets close.
+        if remaining.abs() < ZOOM_SNAP_DISTANCE {
+            self.zoom_level = self.target_zoom_level;
+        } else {
+            self.zoom_level += remaining * ZOOM_EASING;
+        }
+
+        // Move the camera so that the anchored part of the world is back under the anchor.
+        let drifted_world_pos = self.screen_to_world(anchor, window_size);
+        self.pos += anchored_world_pos - drifted_world_pos;
+    }
+}
+
+/// The mapping between positions in the world and positions on the screen (in pixels from the top
+/// left corner of the window) for a camera, found by `Camera::view_transform`.
+///
+/// The mapping is measured from the bottom left corner of `origin_chunk` (the chunk the camera is
+/// in) rather than from the origin of the world, so that the numbers involved stay small enough
+/// for f32s in the shaders.
+#[derive(Copy, Clone, Debug)]
+pub struct ViewTransform {
+    pub origin_chunk: ChunkPos,
+    /// A position on the screen is the position in the world (relative to `origin_chunk`) times
+    /// `scale`, plus `offset`, along each axis.
+    pub scale: [f64; 2],
+    pub offset: [f64; 2],
+    pub window_size: PhysicalSize<u32>,
+}
+
+impl ViewTransform {
+    pub fn world_to_screen(&self, world_pos: &PixelPos) -> [f64; 2] {
+        let relative_pos = *world_pos - self.origin_chunk.bottom_left();
+        [0, 1].map(|axis| relative_pos[axis] * self.scale[axis] + self.offset[axis])
+    }
+
+    pub fn screen_to_world(&self, screen_pos: [f64; 2]) -> PixelPos {
+        let relative_pos =
+            [0, 1].map(|axis| (screen_pos[axis] - self.offset[axis]) / self.scale[axis]);
+        self.origin_chunk.bottom_left() + relative_pos
+    }
+
+    /// The scale and offset that take a position in the world (relative to `origin_chunk`) to
+    /// clip space, which goes from -1 to 1 across the window with the y axis pointing up. This is
+    /// `world_to_screen` for the shaders.
+    pub fn world_to_clip(&self) -> ([f32; 2], [f32; 2]) {
+        let size = [self.window_size.width, self.window_size.height].map(f64::from);
+        let flip = [1.0, -1.0];
+        let scale = [0, 1].map(|axis| (self.scale[axis] * 2.0 / size[axis] * flip[axis]) as f32);
+        let offset =
+            [0, 1].map(|axis| ((self.offset[axis] * 2.0 / size[axis] - 1.0) * flip[axis]) as f32);
+        (scale, offset)
+    }
+
+    /// The scale and offset that take a position on the screen to the position in the world
+    /// (relative to `origin_chunk`). This is `screen_to_world` for the shaders.
+    pub fn screen_to_relative_world(&self) -> ([f32; 2], [f32; 2]) {
+        let scale = self.scale.map(|scale| (1.0 / scale) as f32);
+        let offset = [0, 1].map(|axis| (-self.offset[axis] / self.scale[axis]) as f32);
+        (scale, offset)
     }
 }
 
@@ -93,6 +382,21 @@ impl MoveDirection {
         }
     }
 
+    /// The direction as a fraction of full speed along each axis, with y pointing up. Diagonal
+    /// movement is at full speed along both axes.
+    fn to_vector(&self) -> [f64; 2] {
+        let mut vector = [0.0; 2];
+        for direction in self.to_primary_directions() {
+            match direction {
+                PrimaryDirection::North => vector[1] += 1.0,
+                PrimaryDirection::East => vector[0] += 1.0,
+                PrimaryDirection::South => vector[1] -= 1.0,
+                PrimaryDirection::West => vector[0] -= 1.0,
+            }
+        }
+        vector
+    }
+
     fn to_primary_directions(&self) -> Vec<&PrimaryDirection> {
         match self {
             MoveDirection::Stationary => {
@@ -121,4 +425,287 @@ const CAMERA_SPEED_PX: f64 = 10.0;
 /// The amount each zoom increment zooms in/out.
 const CAMERA_ZOOM_LEVEL_MULTIPLIER: f32 = 1.15;
 const CAMERA_MAX_ZOOM_LEVEL: f32 = 5.0;
-const CAMERA_MIN_ZOOM_LEVEL: f32 = -25.0;
+const CAMERA_MIN_ZOOM_LEVEL: f32 = -40.0;
+/// The fraction of its speed that the camera keeps each tick when gliding after a drag.
+const INERTIA_FRICTION: f64 = 0.9;
+/// How much each tick's movement counts towards the measured speed while dragging.
+const DRAG_VELOCITY_SMOOTHING: f64 = 0.5;
+/// The speed (in pixels of the window per tick) below which a gliding camera stops.
+const MIN_INERTIA_SPEED_PX: f64 = 0.1;
+/// The fraction of the remaining distance to the target zoom level that is covered each tick.
+const ZOOM_EASING: f32 = 0.25;
+/// How close the zoom level has to get to the target before it stops easing and jumps the rest of
+/// the way.
+const ZOOM_SNAP_DISTANCE: f32 = 0.001;
+/// How far outside of the window (in pixels) a position can be and still count as being on the
+/// screen.
+const SCREEN_EDGE_TOLERANCE_PX: f64 = 0.01;
+/// How long it takes the camera to fly anywhere.
+const FLIGHT_DURATION_TICKS: f32 = 60.0;
+/// The farthest that the camera flies (in pixels on the screen) without zooming out on the way.
+const FLIGHT_COMFORTABLE_DISTANCE_PX: f64 = 1000.0;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WINDOW_SIZE: PhysicalSize<u32> = PhysicalSize::new(800, 600);
+
+    fn camera_at(x: f64, y: f64, zoom_level: f32) -> Camera {
+        camera_at_pos(PixelPos::new(x, y), zoom_level)
+    }
+
+    fn camera_at_pos(pos: PixelPos, zoom_level: f32) -> Camera {
+        Camera {
+            pos,
+            zoom_level,
+            target_zoom_level: zoom_level,
+            ..Default::default()
+        }
+    }
+
+    fn assert_near(actual: &PixelPos, expected: &PixelPos) {
+        let [dx, dy] = *actual - *expected;
+        assert!(
+            dx.abs() < 1e-6 && dy.abs() < 1e-6,
+            "expected {expected:?}, got {actual:?}"
+        );
+    }
+
+    #[test]
+    fn screen_to_world_maps_the_center_of_the_window_to_the_camera() {
+        let camera = camera_at(1234.5, -678.25, 0.0);
+        let center = PhysicalPosition::new(400.0, 300.0);
+
+        assert_near(&camera.screen_to_world(center, WINDOW_SIZE), &camera.pos);
+    }
+
+    #[test]
+    fn screen_to_world_maps_the_corners_of_the_window() {
+        let camera = camera_at(100.0, 200.0, 0.0);
+        let corner = |x, y| camera.screen_to_world(PhysicalPosition::new(x, y), WINDOW_SIZE);
+
+        assert_near(&corner(0.0, 0.0), &PixelPos::new(-300.0, 500.0));
+        assert_near(&corner(800.0, 0.0), &PixelPos::new(500.0, 500.0));
+        assert_near(&corner(0.0, 600.0), &PixelPos::new(-300.0, -100.0));
+        assert_near(&corner(800.0, 600.0), &PixelPos::new(500.0, -100.0));
+    }
+
+    #[test]
+    fn screen_to_world_flips_the_y_axis() {
+        let camera = camera_at(0.0, 0.0, 0.0);
+        let above_center = camera.screen_to_world(PhysicalPosition::new(400.0, 290.0), WINDOW_SIZE);
+        let below_center = camera.screen_to_world(PhysicalPosition::new(400.0, 310.0), WINDOW_SIZE);
+
+        assert_near(&above_center, &PixelPos::new(0.0, 10.0));
+        assert_near(&below_center, &PixelPos::new(0.0, -10.0));
+    }
+
+    #[test]
+    fn screen_to_world_scales_with_zoom() {
+        for zoom_level in [-10.0, -1.0, 1.0, CAMERA_MAX_ZOOM_LEVEL] {
+            let camera = camera_at(-50.0, 75.0, zoom_level);
+            let zoom = f64::from(camera.zoom_multiplier());
+            let world_pos =
+                camera.screen_to_world(PhysicalPosition::new(500.0, 250.0), WINDOW_SIZE);
+
+            assert_near(
+                &world_pos,
+                &PixelPos::new(-50.0 + 100.0 / zoom, 75.0 + 50.0 / zoom),
+            );
+        }
+    }
+
+    const ZOOM_LEVELS: [f32; 6] = [
+        CAMERA_MIN_ZOOM_LEVEL,
+        -20.0,
+        -3.5,
+        0.0,
+        2.0,
+        CAMERA_MAX_ZOOM_LEVEL,
+    ];
+    const WINDOW_SIZES: [PhysicalSize<u32>; 4] = [
+        PhysicalSize::new(800, 600),
+        PhysicalSize::new(1920, 1080),
+        PhysicalSize::new(333, 777),
+        PhysicalSize::new(1, 1),
+    ];
+    const CAMERA_POSITIONS: [[f64; 2]; 6] = [
+        [0.0, 0.0],
+        [-1.0, -1.0],
+        [-4096.5, 4095.75],
+        [123_456.789, -987_654.321],
+        [1e12, -1e12],
+        [-3e11, 7e11],
+    ];
+    /// Chunks close to the edges of the world, where pixel coordinates are far too big for an f32
+    /// (and only just fit in an f64). They're far enough from the edges that the whole window is
+    /// still inside the world at the minimum zoom.
+    const FAR_CAMERA_CHUNKS: [[i32; 2]; 3] = [
+        [i32::MAX - 200, i32::MAX - 200],
+        [i32::MIN + 200, i32::MAX - 300],
+        [i32::MAX - 250, i32::MIN + 150],
+    ];
+
+    /// The camera positions above, plus some part way across the far chunks.
+    fn camera_positions() -> impl Iterator<Item = PixelPos> {
+        let far_positions = FAR_CAMERA_CHUNKS
+            .into_iter()
+            .map(|[x, y]| ChunkPos { x, y }.bottom_left() + [1234.5, 3071.25]);
+        CAMERA_POSITIONS
+            .into_iter()
+            .map(|[x, y]| PixelPos::new(x, y))
+            .chain(far_positions)
+    }
+
+    /// Every combination of the zoom levels, window sizes and camera positions above.
+    fn cameras() -> impl Iterator<Item = (Camera, PhysicalSize<u32>)> {
+        ZOOM_LEVELS.into_iter().flat_map(|zoom_level| {
+            WINDOW_SIZES.into_iter().flat_map(move |window_size| {
+                camera_positions().map(move |pos| (camera_at_pos(pos, zoom_level), window_size))
+            })
+        })
+    }
+
+    /// A grid of points across a window, including its edges.
+    fn screen_points(window_size: PhysicalSize<u32>) -> impl Iterator<Item = [f64; 2]> {
+        let fractions = [0.0, 0.1, 0.25, 0.5, 0.8, 1.0];
+        fractions.into_iter().flat_map(move |fraction_x| {
+            fractions.into_iter().map(move |fraction_y| {
+                [
+                    f64::from(window_size.width) * fraction_x,
+                    f64::from(window_size.height) * fraction_y,
+                ]
+            })
+        })
+    }
+
+    fn assert_near_screen(actual: Option<[f32; 2]>, expected: [f64; 2]) {
+        let Some(actual) = actual else {
+            panic!("expected {expected:?} to be on the screen");
+        };
+        for axis in 0..2 {
+            assert!(
+                (f64::from(actual[axis]) - expected[axis]).abs() < 0.01,
+                "expected {expected:?}, got {actual:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn screen_positions_survive_a_round_trip_through_the_world() {
+        for (camera, window_size) in cameras() {
+            for [x, y] in screen_points(window_size) {
+                let world_pos = camera.screen_to_world(PhysicalPosition::new(x, y), window_size);
+
+                assert_near_screen(camera.world_to_screen(&world_pos, window_size), [x, y]);
+            }
+        }
+    }
+
+    #[test]
+    fn world_positions_survive_a_round_trip_through_the_screen() {
+        for (camera, window_size) in cameras() {
+            let zoom = f64::from(camera.zoom_multiplier());
+            // The round trip can't be more precise than a pixel on the screen (stored as an f32)
+            // or a subpixel in the world.
+            let tolerance = 0.01 / zoom + 1.0 / 256.0;
+            for [x, y] in screen_points(window_size) {
+                // Somewhere on the screen, but not exactly where a screen position would map to.
+                let offset = [
+                    (x - f64::from(window_size.width) / 2.0) * 0.9 / zoom,
+                    (y - f64::from(window_size.height) / 2.0) * 0.9 / zoom,
+                ];
+                let world_pos = camera.pos + offset;
+
+                let Some([screen_x, screen_y]) = camera.world_to_screen(&world_pos, window_size)
+                else {
+                    panic!("expected {world_pos:?} to be on the screen");
+                };
+                let round_trip = camera.screen_to_world(
+                    PhysicalPosition::new(f64::from(screen_x), f64::from(screen_y)),
+                    window_size,
+                );
+                let [dx, dy] = round_trip - world_pos;
+
+                assert!(
+                    dx.abs() < tolerance && dy.abs() < tolerance,
+                    "expected {world_pos:?}, got {round_trip:?}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn world_to_screen_is_none_off_the_screen() {
+        for (camera, window_size) in cameras() {
+            let zoom = f64::from(camera.zoom_multiplier());
+            let width = f64::from(window_size.width) / zoom;
+            let height = f64::from(window_size.height) / zoom;
+
+            for offset in [
+                [-width, 0.0],
+                [width, 0.0],
+                [0.0, -height],
+                [0.0, height],
+                [width, height],
+            ] {
+                let world_pos = camera.pos + offset;
+                assert!(camera.world_to_screen(&world_pos, window_size).is_none());
+            }
+        }
+    }
+
+    #[test]
+    fn visible_bounds_are_at_the_corners_of_the_screen() {
+        for (camera, window_size) in cameras() {
+            let (bottom_left, top_right) = camera.visible_bounds(window_size);
+            let width = f64::from(window_size.width);
+            let height = f64::from(window_size.height);
+
+            assert_near_screen(
+                camera.world_to_screen(&bottom_left, window_size),
+                [0.0, height],
+            );
+            assert_near_screen(
+                camera.world_to_screen(&top_right, window_size),
+                [width, 0.0],
+            );
+        }
+    }
+
+    #[test]
+    fn the_shader_transforms_match_the_camera() {
+        for (camera, window_size) in cameras() {
+            let transform = camera.view_transform(window_size);
+            let (clip_scale, clip_offset) = transform.world_to_clip();
+            let (world_scale, world_offset) = transform.screen_to_relative_world();
+            let size = [window_size.width, window_size.height].map(f64::from);
+
+            for screen_pos in screen_points(window_size) {
+                let world_pos = transform.screen_to_world(screen_pos);
+                let relative_pos = world_pos - transform.origin_chunk.bottom_left();
+
+                // What the shaders work out, in f32s like the shaders do.
+                let clip_pos = [0, 1].map(|axis| {
+                    f64::from(relative_pos[axis] as f32 * clip_scale[axis] + clip_offset[axis])
+                });
+                let shader_relative_pos = [0, 1]
+                    .map(|axis| screen_pos[axis] as f32 * world_scale[axis] + world_offset[axis]);
+
+                let clip_to_screen = [
+                    f64::midpoint(clip_pos[0], 1.0) * size[0],
+                    (1.0 - clip_pos[1]) / 2.0 * size[1],
+                ];
+                let zoom = f64::from(camera.zoom_multiplier());
+                for axis in 0..2 {
+                    assert!((clip_to_screen[axis] - screen_pos[axis]).abs() < 0.01);
+                    assert!(
+                        (f64::from(shader_relative_pos[axis]) - relative_pos[axis]).abs()
+                            < 0.01 / zoom + 1.0 / 256.0
+                    );
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,269 @@
+//! A small immediate-mode UI for debugging and tweaking things at runtime.
+//!
+//! Every tick, the UI is rebuilt from scratch by calling the widget functions in order. Each widget
+//! draws itself and reports whether the user interacted with it since the last tick.
+
+use crate::display::Hud;
+use std::collections::HashSet;
+
+/// The state of the mouse pointer for a single frame of the UI.
+#[derive(Copy, Clone, Default)]
+pub struct PointerInput {
+    /// The position of the cursor in pixels from the top left corner of the window, or None if
+    /// it's outside the window.
+    pub pos: Option<[f32; 2]>,
+    /// Whether the left mouse button is held down.
+    pub down: bool,
+    /// Whether the left mouse button was pressed since the last frame.
+    pub clicked: bool,
+}
+
+#[derive(Default)]
+pub struct DebugUi {
+    /// What the UI looks like, rebuilt every frame.
+    draw_list: Hud,
+    input: PointerInput,
+    /// The areas of the screen covered by the UI as of the last frame. Input that lands in them
+    /// belongs to the UI rather than to whatever is behind it.
+    covered_rects: Vec<Rect>,
+    /// The titles of the panels that are collapsed.
+    collapsed_panels: HashSet<String>,
+    /// The label of the slider being dragged, if any. Sliders keep following the cursor while the
+    /// mouse button is held, even if it leaves them.
+    active_slider: Option<String>,
+    /// The position of the next panel's top left corner.
+    next_panel_pos: [f32; 2],
+    /// The position of the next widget's top left corner within the current panel.
+    cursor: [f32; 2],
+}
+
+impl DebugUi {
+    /// Starts a new frame of the UI. Panels are stacked downwards from `origin`.
+    pub fn begin_frame(&mut self, input: PointerInput, origin: [f32; 2]) {
+        self.draw_list.clear();
+        self.covered_rects.clear();
+        self.input = input;
+        self.next_panel_pos = origin;
+
+        if !input.down {
+            self.active_slider = None;
+        }
+    }
+
+    /// The width of every panel in pixels.
+    pub fn panel_width() -> f32 {
+        PANEL_WIDTH
+    }
+
+    pub fn draw_list(&self) -> &Hud {
+        &self.draw_list
+    }
+
+    /// Whether the given point (in pixels from the top left corner of the window) is covered by
+    /// the UI, meaning that input there should go to the UI.
+    pub fn is_over_ui(&self, pos: [f32; 2]) -> bool {
+        self.covered_rects.iter().any(|rect| rect.contains(pos))
+    }
+
+    /// Whether the UI is in the middle of an interaction (such as dragging a slider) that should
+    /// capture all mouse input.
+    pub fn is_capturing_pointer(&self) -> bool {
+        self.active_slider.is_some()
+    }
+
+    /// Draws a collapsible panel, with the widgets added by `contents` inside it. Clicking the
+    /// title bar collapses or expands the panel.
+    pub fn panel(&mut self, title: &str, contents: impl FnOnce(&mut Self)) {
+        let panel_pos = self.next_panel_pos;
+        let header = Rect {
+            pos: panel_pos,
+            size: [PANEL_WIDTH, ROW_HEIGHT],
+        };
+
+        if self.was_clicked(header) && !self.collapsed_panels.remove(title) {
+            self.collapsed_panels.insert(title.to_string());
+        }
+        let collapsed = self.collapsed_panels.contains(title);
+
+        // Draw the contents into a separate list so that the background can go underneath them
+        // once we know how tall the panel is.
+        let panel_draw_list = std::mem::take(&mut self.draw_list);
+        self.cursor = [panel_pos[0] + PADDING, panel_pos[1] + ROW_HEIGHT + PADDING];
+        if !collapsed {
+            contents(self);
+        }
+        let contents_draw_list = std::mem::replace(&mut self.draw_list, panel_draw_list);
+
+        let panel = Rect {
+            pos: panel_pos,
+            size: [
+                PANEL_WIDTH,
+                if collapsed {
+                    ROW_HEIGHT
+                } else {
+                    self.cursor[1] - panel_pos[1]
+                },
+            ],
+        };
+        self.draw_list
+            .rect(panel.pos, panel.size, PANEL_COLOR, PANEL_ALPHA);
+        self.draw_list
+            .rect(header.pos, header.size, HEADER_COLOR, PANEL_ALPHA);
+        self.draw_text(
+            [header.pos[0] + PADDING, header.pos[1]],
+            &format!("{} {title}", if collapsed { "+" } else { "-" }),
+        );
+        self.draw_list.append(&contents_draw_list);
+
+        self.covered_rects.push(panel);
+        self.next_panel_pos = [panel_pos[0], panel.pos[1] + panel.size[1] + PADDING];
+    }
+
+    /// Draws a line of text.
+    pub fn label(&mut self, text: &str) {
+        let pos = self.next_row();
+        self.draw_text(pos, text);
+    }
+
+    /// Draws a button and returns whether it was clicked.
+    pub fn button(&mut self, label: &str) -> bool {
+        let pos = self.next_row();
+        let rect = Rect {
+            pos,
+            size: [Hud::text_width(label) + PADDING * 2.0, ROW_HEIGHT - PADDING],
+        };
+
+        let color = if self.is_hovered(rect) {
+            WIDGET_HOVER_COLOR
+        } else {
+            WIDGET_COLOR
+        };
+        self.draw_list.rect(rect.pos, rect.size, color, 1.0);
+        self.draw_text([pos[0] + PADDING, pos[1]], label);
+
+        self.was_clicked(rect)
+    }
+
+    /// Draws a checkbox for a boolean value, toggling it when clicked. Returns whether it changed.
+    pub fn checkbox(&mut self, label: &str, value: &mut bool) -> bool {
+        let pos = self.next_row();
+        let box_size = Hud::text_height();
+        let rect = Rect {
+            pos,
+            size: [box_size + PADDING + Hud::text_width(label), box_size],
+        };
+
+        let clicked = self.was_clicked(rect);
+        if clicked {
+            *value = !*value;
+        }
+
+        let color = if self.is_hovered(rect) {
+            WIDGET_HOVER_COLOR
+        } else {
+            WIDGET_COLOR
+        };
+        self.draw_list.rect(pos, [box_size, box_size], color, 1.0);
+        if *value {
+            let inset = box_size / 4.0;
+            self.draw_list.rect(
+                [pos[0] + inset, pos[1] + inset],
+                [box_size - inset * 2.0, box_size - inset * 2.0],
+                ACCENT_COLOR,
+                1.0,
+            );
+        }
+        self.draw_text([pos[0] + box_size + PADDING, pos[1]], label);
+
+        clicked
+    }
+
+    /// Draws a slider for a value between `min` and `max`, which can be changed by clicking or
+    /// dragging along it. Returns whether the value changed.
+    pub fn slider(&mut self, label: &str, value: &mut f32, min: f32, max: f32) -> bool {
+        self.label(&format!("{label}: {value:.2}"));
+
+        let pos = self.next_row();
+        let track = Rect {
+            pos,
+            size: [PANEL_WIDTH - PADDING * 2.0, ROW_HEIGHT - PADDING],
+        };
+
+        if self.was_clicked(track) {
+            self.active_slider = Some(label.to_string());
+        }
+
+        let mut changed = false;
+        if self.active_slider.as_deref() == Some(label) {
+            if let Some(cursor_pos) = self.input.pos {
+                let fraction = ((cursor_pos[0] - track.pos[0]) / track.size[0]).clamp(0.0, 1.0);
+                let new_value = min + fraction * (max - min);
+                changed = (new_value - *value).abs() > f32::EPSILON;
+                *value = new_value;
+            }
+        }
+
+        let fraction = ((*value - min) / (max - min)).clamp(0.0, 1.0);
+        self.draw_list
+            .rect(track.pos, track.size, WIDGET_COLOR, 1.0);
+        self.draw_list.rect(
+            track.pos,
+            [track.size[0] * fraction, track.size[1]],
+            ACCENT_COLOR,
+            1.0,
+        );
+
+        changed
+    }
+
+    /// Reserves space for a row of widgets in the current panel and returns its top left corner.
+    fn next_row(&mut self) -> [f32; 2] {
+        let pos = self.cursor;
+        self.cursor[1] += ROW_HEIGHT;
+        pos
+    }
+
+    fn draw_text(&mut self, pos: [f32; 2], text: &str) {
+        // Center the text vertically within its row.
+        let offset = (ROW_HEIGHT - PADDING - Hud::text_height()) / 2.0;
+        self.draw_list
+            .text([pos[0], pos[1] + offset], text, TEXT_COLOR);
+    }
+
+    fn is_hovered(&self, rect: Rect) -> bool {
+        self.input.pos.is_some_and(|pos| rect.contains(pos))
+    }
+
+    fn was_clicked(&self, rect: Rect) -> bool {
+        self.input.clicked && self.is_hovered(rect)
+    }
+}
+
+#[derive(Copy, Clone)]
+struct Rect {
+    pos: [f32; 2],
+    size: [f32; 2],
+}
+
+impl Rect {
+    fn contains(&self, point: [f32; 2]) -> bool {
+        point[0] >= self.pos[0]
+            && point[0] < self.pos[0] + self.size[0]
+            && point[1] >= self.pos[1]
+            && point[1] < self.pos[1] + self.size[1]
+    }
+}
+
+const PANEL_WIDTH: f32 = 320.0;
+/// The space between the edges of a panel and its widgets.
+const PADDING: f32 = 4.0;
+/// The height of a single row of widgets, including the space below it.
+const ROW_HEIGHT: f32 = 28.0;
+
+const PANEL_COLOR: [u8; 3] = [30, 30, 40];
+const HEADER_COLOR: [u8; 3] = [60, 60, 90];
+const PANEL_ALPHA: f32 = 0.85;
+const WIDGET_COLOR: [u8; 3] = [70, 70, 80];
+const WIDGET_HOVER_COLOR: [u8; 3] = [100, 100, 120];
+const ACCENT_COLOR: [u8; 3] = [90, 160, 230];
+const TEXT_COLOR: [u8; 3] = [255, 255, 255];
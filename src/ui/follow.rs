@@ -0,0 +1,67 @@
+use crate::position::PixelPos;
+use crate::world::{Settlement, World};
+
+/// Something in the world that the camera can lock onto.
+#[derive(Copy, Clone)]
+pub enum FollowTarget {
+    /// The settlement with an ID.
+    Settlement(u64),
+}
+
+impl FollowTarget {
+    /// The settlement nearest to a position, if there are any settlements.
+    pub fn nearest_settlement(world: &World, pos: &PixelPos) -> Option<Self> {
+        let distance_squared = |settlement: &Settlement| {
+            let [dx, dy] = settlement.position.bottom_left() - *pos;
+            dx * dx + dy * dy
+        };
+
+        world
+            .settlements
+            .iter()
+            .min_by(|a, b| distance_squared(a).total_cmp(&distance_squared(b)))
+            .map(|settlement| FollowTarget::Settlement(settlement.id))
+    }
+
+    /// Where the target is now, or None if it no longer exists (for example, because the world was
+    /// regenerated or the settlement's founding was undone).
+    pub fn position(self, world: &World) -> Option<PixelPos> {
+        match self {
+            FollowTarget::Settlement(id) => {
+                // Follow the center of the tile rather than its corner.
+                Some(world.settlement(id)?.position.center())
+            }
+        }
+    }
+
+    pub fn describe(self) -> String {
+        match self {
+            FollowTarget::Settlement(id) => format!("settlement {id}"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::position::{ChunkPos, TilePos};
+
+    #[test]
+    fn following_a_settlement_survives_removing_an_earlier_one() {
+        let mut world = World::new();
+        world.generate_chunk(ChunkPos::default());
+        world.spawn_settlement(TilePos { x: 2, y: 2 });
+        world.spawn_settlement(TilePos { x: 20, y: 20 });
+        let target = FollowTarget::nearest_settlement(&world, &TilePos { x: 19, y: 19 }.center())
+            .expect("There are settlements to follow");
+
+        world.remove_settlement(0);
+        assert_eq!(
+            target.position(&world),
+            Some(TilePos { x: 20, y: 20 }.center())
+        );
+
+        world.remove_settlement(0);
+        assert_eq!(target.position(&world), None);
+    }
+}
@@ -0,0 +1,106 @@
+use gilrs::ev::state::ButtonData;
+use gilrs::{Axis, Button, EventType, Gamepad, Gilrs};
+use winit::event::ElementState;
+
+/// Reads the state of any connected game controllers. Their buttons are bound to actions like the
+/// keyboard's are, while the left stick pans the camera and the triggers zoom it by however far
+/// they're pushed.
+pub struct Gamepads {
+    /// None if gamepads aren't supported on this platform.
+    gilrs: Option<Gilrs>,
+}
+
+/// Something that happened on a gamepad since the last poll.
+pub enum GamepadEvent {
+    Button(Button, ElementState),
+    /// A gamepad was unplugged, so none of its buttons will be released.
+    Disconnected,
+}
+
+impl Gamepads {
+    pub fn new() -> Self {
+        let gilrs = Gilrs::new()
+            .map_err(|error| log::warn!("Gamepads aren't available: {error}"))
+            .ok();
+        Gamepads { gilrs }
+    }
+
+    /// Takes the button presses and releases that have happened since the last call. Should be
+    /// called once per tick, before reading the sticks and triggers.
+    pub fn poll(&mut self) -> Vec<GamepadEvent> {
+        let Some(gilrs) = &mut self.gilrs else {
+            return vec![];
+        };
+
+        let mut events = vec![];
+        while let Some(event) = gilrs.next_event() {
+            match event.event {
+                EventType::ButtonPressed(button, _) if button != Button::Unknown => {
+                    events.push(GamepadEvent::Button(button, ElementState::Pressed));
+                }
+                EventType::ButtonReleased(button, _) if button != Button::Unknown => {
+                    events.push(GamepadEvent::Button(button, ElementState::Released));
+                }
+                EventType::Disconnected => events.push(GamepadEvent::Disconnected),
+                _ => {}
+            }
+        }
+        events
+    }
+
+    /// The direction that the left sticks are pushed in, with y pointing up. Each component is
+    /// between -1 and 1, and the length grows with how far the sticks are pushed, so it can be
+    /// used as a fraction of the full panning speed.
+    pub fn pan_direction(&self) -> [f64; 2] {
+        let Some(gilrs) = &self.gilrs else {
+            return [0.0; 2];
+        };
+
+        let mut direction = [0.0; 2];
+        for (_, gamepad) in gilrs.gamepads() {
+            let x = f64::from(gamepad.value(Axis::LeftStickX));
+            let y = f64::from(gamepad.value(Axis::LeftStickY));
+
+            // Sticks rarely rest exactly in the middle, so ignore small movements. The dead zone is
+            // round so that diagonal movement isn't harder to start than straight movement, and the
+            // rest of the range is stretched so that the speed starts from 0 at its edge.
+            let deflection = x.hypot(y).min(1.0);
+            if deflection < STICK_DEAD_ZONE {
+                continue;
+            }
+            let scale = (deflection - STICK_DEAD_ZONE) / (1.0 - STICK_DEAD_ZONE) / x.hypot(y);
+            direction[0] += x * scale;
+            direction[1] += y * scale;
+        }
+
+        // Several gamepads pushing the same way shouldn't go faster than one.
+        let length = direction[0].hypot(direction[1]);
+        if length > 1.0 {
+            direction = direction.map(|component| component / length);
+        }
+        direction
+    }
+
+    /// How fast the triggers are asking to zoom, from -1 (zooming out at full speed with the left
+    /// trigger) to 1 (zooming in at full speed with the right trigger).
+    pub fn zoom_direction(&self) -> f32 {
+        let Some(gilrs) = &self.gilrs else {
+            return 0.0;
+        };
+
+        let trigger_value =
+            |gamepad: &Gamepad, button| gamepad.button_data(button).map_or(0.0, ButtonData::value);
+        let direction: f32 = gilrs
+            .gamepads()
+            .map(|(_, gamepad)| {
+                trigger_value(&gamepad, Button::RightTrigger2)
+                    - trigger_value(&gamepad, Button::LeftTrigger2)
+            })
+            .sum();
+        direction.clamp(-1.0, 1.0)
+    }
+}
+
+/// How far the sticks have to be pushed (as a fraction of the way to the edge) before they move
+/// the camera.
+const STICK_DEAD_ZONE: f64 = 0.15;
@@ -0,0 +1,68 @@
+use crate::position::TilePos;
+use crate::world::World;
+
+/// Keeps track of the tile the user clicked on so that its data can be shown.
+#[derive(Default)]
+pub struct Inspector {
+    selected_tile: Option<TilePos>,
+}
+
+impl Inspector {
+    pub fn select(&mut self, coords: TilePos) {
+        self.selected_tile = Some(coords);
+    }
+
+    pub fn selected_tile(&self) -> Option<TilePos> {
+        self.selected_tile
+    }
+
+    /// Describes the selected tile, one line per property. Returns an empty list if nothing is selected.
+    pub fn describe(&self, world: &World) -> Vec<String> {
+        let Some(coords) = self.selected_tile else {
+            return vec![];
+        };
+
+        let chunk_coords = coords.chunk();
+        let index_in_chunk = coords.index_in_chunk();
+        let mut lines = vec![
+            format!("Tile: ({}, {})", coords.x, coords.y),
+            format!("Chunk: ({}, {})", chunk_coords.x, chunk_coords.y),
+            format!(
+                "Index in chunk: ({}, {})",
+                index_in_chunk.x, index_in_chunk.y
+            ),
+        ];
+
+        let (Some(tile), Some(elevation)) = (world.tile(coords), world.elevation(coords)) else {
+            lines.push("Not generated".to_string());
+            return lines;
+        };
+
+        if let Some(chunk) = world.chunks.get(&chunk_coords) {
+            lines.push(format!("Chunk revision: {}", chunk.revision()));
+        }
+        lines.push(format!("Type: {tile:?}"));
+        lines.push(format!("Elevation: {elevation:.3}"));
+        lines.push(match world.movement_cost(coords) {
+            Some(cost) => format!("Movement cost: {cost}"),
+            None => "Movement cost: impassable".to_string(),
+        });
+        if world.has_road(coords) {
+            lines.push("Road".to_string());
+        }
+        if let Some(settlement) = world
+            .settlements
+            .iter()
+            .find(|settlement| settlement.position == coords)
+        {
+            lines.push(format!(
+                "Settlement: population {}/{}, {} roads",
+                settlement.population,
+                settlement.capacity,
+                settlement.connections.len()
+            ));
+        }
+
+        lines
+    }
+}
@@ -1,130 +1,1113 @@
+mod bindings;
+mod bookmarks;
+mod brush;
 mod camera;
+mod debug_ui;
+mod follow;
+mod gamepad;
+mod inspector;
+mod prompt;
+mod viewport;
 
-use crate::position::IntChunkCoordinates;
-use crate::ui::camera::Camera;
-use crate::world::World;
+use crate::display::{Hud, MinimapLayout, RenderSettings, HUD_TEXT_COLOR};
+use crate::position::{ChunkPos, PixelPos};
+use crate::ui::bindings::{Action, Bindings, InputButton};
+use crate::world::{CommandHistory, GeneratorParams, World, WorldCommand};
+use bookmarks::Bookmarks;
+use brush::{Brush, BrushMode};
+pub use camera::Camera;
 use camera::{MoveDirection, PrimaryDirection};
+use debug_ui::{DebugUi, PointerInput};
+use follow::FollowTarget;
+use gamepad::{GamepadEvent, Gamepads};
+use inspector::Inspector;
+use prompt::{PromptPurpose, PromptUpdate, TextPrompt};
 use std::collections::HashSet;
-use winit::event::{ElementState, KeyEvent, MouseScrollDelta};
-use winit::keyboard::{KeyCode, PhysicalKey};
+pub use viewport::{Viewport, MAX_VIEWPORTS};
+use winit::dpi::{PhysicalPosition, PhysicalSize};
+use winit::event::{ElementState, KeyEvent, MouseButton, MouseScrollDelta};
+use winit::keyboard::{ModifiersState, PhysicalKey};
 
 /// Represents the UI - anything that affects what the user sees, but which is not actually part of the simulation.
+// The flags track unrelated bits of state, so they wouldn't be any clearer as an enum.
+#[expect(clippy::struct_excessive_bools)]
 pub struct Ui {
-    pub camera: Camera,
-    held_inputs: Inputs,
+    /// The parts of the window that each show the world through their own camera, from left to
+    /// right. There's always at least one.
+    viewports: Vec<Viewport>,
+    /// The index of the viewport that input goes to, which is the one that the cursor was last over.
+    active_viewport: usize,
+    bindings: Bindings,
+    /// The keys and buttons that are currently held down.
+    held_buttons: HashSet<InputButton>,
+    gamepads: Gamepads,
+    modifiers: ModifiersState,
+    window_size: PhysicalSize<u32>,
+    /// The position of the mouse cursor in the window, or None if it isn't over the window.
+    cursor_pos: Option<PhysicalPosition<f64>>,
+    /// Whether the left mouse button was pressed over the debug UI panels since the last tick.
+    panels_clicked: bool,
+    inspector: Inspector,
+    brush: Brush,
+    /// Whether a brush stroke is in progress (the mouse was pressed over the world with the brush enabled).
+    painting: bool,
+    /// Whether the camera is being dragged (the middle or right mouse button was pressed over the world).
+    dragging: bool,
+    camera_controls: CameraControls,
+    bookmarks: Bookmarks,
+    /// Whether the user asked to start or stop following something since the last tick.
+    follow_toggled: bool,
+    /// The text that the user is typing in, if any. Key presses go to the prompt while it's open.
+    prompt: Option<TextPrompt>,
+    panels: DebugUi,
+    show_panels: bool,
+    pub render_settings: RenderSettings,
+    /// The number of times the world is ticked per frame. 0 means the simulation is paused.
+    simulation_speed: u32,
+    /// The simulation speed to go back to when the simulation is unpaused.
+    speed_before_pause: u32,
+    /// The generator settings shown in the debug UI, which are used when the world is regenerated.
+    generator_params: GeneratorParams,
+    /// How many chunks beyond the edges of each viewport are generated ahead of time.
+    chunk_generation_margin: i32,
+    /// The bottom left and top right corners of the area that chunks have been generated in, as of
+    /// the last tick, which the minimap shows.
+    generated_bounds: Option<[ChunkPos; 2]>,
+    /// Actions on the world that the user asked for, which will be carried out on the next tick.
+    pending_actions: Vec<PendingAction>,
 }
 
 impl Ui {
     #[must_use]
-    pub fn new() -> Self {
+    pub fn new(window_size: PhysicalSize<u32>) -> Self {
+        let mut viewports = vec![Viewport::new(Camera::new())];
+        viewport::arrange(&mut viewports, window_size);
+
         Self {
-            camera: Camera::new(),
-            held_inputs: Inputs {
-                ..Default::default()
-            },
+            viewports,
+            active_viewport: 0,
+            bindings: Bindings::load(),
+            held_buttons: HashSet::new(),
+            gamepads: Gamepads::new(),
+            modifiers: ModifiersState::empty(),
+            window_size,
+            cursor_pos: None,
+            panels_clicked: false,
+            inspector: Inspector::default(),
+            brush: Brush::default(),
+            painting: false,
+            dragging: false,
+            camera_controls: CameraControls::default(),
+            bookmarks: Bookmarks::load(),
+            follow_toggled: false,
+            prompt: None,
+            panels: DebugUi::default(),
+            show_panels: true,
+            render_settings: RenderSettings::default(),
+            simulation_speed: 1,
+            speed_before_pause: 1,
+            generator_params: GeneratorParams::default(),
+            chunk_generation_margin: DEFAULT_CHUNK_GENERATION_MARGIN,
+            generated_bounds: None,
+            pending_actions: vec![],
         }
     }
 
     pub fn handle_key_event(&mut self, event: &KeyEvent) {
+        // Typing goes to the prompt rather than triggering actions. Releases still go through so
+        // that keys held down before the prompt was opened are let go of.
+        if event.state == ElementState::Pressed {
+            if let Some(prompt) = &mut self.prompt {
+                match prompt.handle_key_event(event) {
+                    PromptUpdate::Editing => {}
+                    PromptUpdate::Submitted(text) => self.submit_prompt(&text),
+                    PromptUpdate::Cancelled => self.prompt = None,
+                }
+                return;
+            }
+        }
+
         // Ignore key repeats.
         if event.repeat {
             return;
         }
 
         if let PhysicalKey::Code(key_code) = event.physical_key {
-            let key_state = if event.state == ElementState::Pressed {
-                KeyState::Pressed
-            } else {
-                KeyState::Released
-            };
-            match key_code {
-                KeyCode::KeyW | KeyCode::ArrowUp => {
-                    self.held_inputs.up = key_state;
+            self.handle_button_event(InputButton::Key(key_code), event.state);
+        }
+    }
+
+    pub fn handle_modifiers_changed(&mut self, modifiers: ModifiersState) {
+        self.modifiers = modifiers;
+    }
+
+    pub fn handle_scroll_event(&mut self, delta: &MouseScrollDelta) {
+        if self.is_cursor_over_panels() {
+            return;
+        }
+
+        let levels = match delta {
+            MouseScrollDelta::LineDelta(_, lines) => *lines,
+            // Touchpads scroll by pixels, in much smaller steps than a mouse wheel.
+            MouseScrollDelta::PixelDelta(pixels) => pixels.y as f32 / SCROLL_PIXELS_PER_ZOOM_LEVEL,
+        };
+        let viewport = &mut self.viewports[self.active_viewport];
+        let anchor = self.cursor_pos.map(|pos| viewport.to_local(pos));
+        viewport.camera.zoom(levels, anchor);
+    }
+
+    pub fn handle_cursor_moved(&mut self, position: PhysicalPosition<f64>) {
+        if self.dragging {
+            if let Some(old_position) = self.cursor_pos {
+                self.camera_mut()
+                    .drag([position.x - old_position.x, position.y - old_position.y]);
+            }
+        } else if !self.painting {
+            // Input goes to the viewport under the cursor, but a drag or brush stroke stays in the
+            // viewport that it started in.
+            if let Some(index) = self
+                .viewports
+                .iter()
+                .position(|viewport| viewport.contains(position))
+            {
+                self.active_viewport = index;
+            }
+        }
+        self.cursor_pos = Some(position);
+    }
+
+    pub fn handle_cursor_left(&mut self) {
+        self.cursor_pos = None;
+    }
+
+    pub fn handle_resize(&mut self, window_size: PhysicalSize<u32>) {
+        self.window_size = window_size;
+        viewport::arrange(&mut self.viewports, window_size);
+    }
+
+    /// The viewports that the window is split into, from left to right.
+    pub fn viewports(&self) -> &[Viewport] {
+        &self.viewports
+    }
+
+    /// The index of the viewport that input goes to.
+    pub fn active_viewport(&self) -> usize {
+        self.active_viewport
+    }
+
+    fn viewport(&self) -> &Viewport {
+        &self.viewports[self.active_viewport]
+    }
+
+    fn camera(&self) -> &Camera {
+        &self.viewport().camera
+    }
+
+    fn camera_mut(&mut self) -> &mut Camera {
+        &mut self.viewports[self.active_viewport].camera
+    }
+
+    /// Splits off another viewport on the right, looking at the same place as the active one.
+    fn add_viewport(&mut self) {
+        if self.viewports.len() >= MAX_VIEWPORTS {
+            return;
+        }
+        self.viewports
+            .push(Viewport::new(self.camera().still_copy()));
+        viewport::arrange(&mut self.viewports, self.window_size);
+    }
+
+    /// Closes the active viewport, unless it's the only one left.
+    fn remove_viewport(&mut self) {
+        if self.viewports.len() <= 1 {
+            return;
+        }
+        self.viewports.remove(self.active_viewport);
+        self.active_viewport = self.active_viewport.min(self.viewports.len() - 1);
+        // A drag can't carry on in a viewport that's gone.
+        self.dragging = false;
+        viewport::arrange(&mut self.viewports, self.window_size);
+    }
+
+    pub fn handle_mouse_button_event(&mut self, state: ElementState, button: MouseButton) {
+        self.handle_button_event(InputButton::Mouse(button), state);
+    }
+
+    fn handle_gamepad_events(&mut self) {
+        for event in self.gamepads.poll() {
+            match event {
+                GamepadEvent::Button(button, state) => {
+                    self.handle_button_event(InputButton::Gamepad(button), state);
+                }
+                GamepadEvent::Disconnected => {
+                    // Let go of everything that was held on the gamepad, so that it doesn't carry
+                    // on panning forever.
+                    let gamepad_buttons: Vec<InputButton> = self
+                        .held_buttons
+                        .iter()
+                        .copied()
+                        .filter(|button| matches!(button, InputButton::Gamepad(_)))
+                        .collect();
+                    for button in gamepad_buttons {
+                        self.handle_button_event(button, ElementState::Released);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Starts or stops the actions bound to a key or button.
+    fn handle_button_event(&mut self, button: InputButton, state: ElementState) {
+        if state == ElementState::Pressed {
+            self.held_buttons.insert(button);
+            for action in self.bindings.pressed_actions(button, self.modifiers) {
+                self.start_action(action);
+            }
+        } else {
+            self.held_buttons.remove(&button);
+            for action in self.bindings.released_actions(button) {
+                self.stop_action(action);
+            }
+        }
+    }
+
+    /// Does whatever an action does when one of its bindings is pressed. Actions that last for as
+    /// long as they're held down (like panning) are checked every tick instead.
+    fn start_action(&mut self, action: Action) {
+        let overlays = &mut self.render_settings.overlays;
+        match action {
+            Action::PanUp | Action::PanDown | Action::PanLeft | Action::PanRight => {}
+            Action::ZoomIn => self.camera_mut().zoom(1.0, None),
+            Action::ZoomOut => self.camera_mut().zoom(-1.0, None),
+            Action::Interact => self.start_interacting(),
+            Action::DragCamera => {
+                // Dragging on the debug UI shouldn't also move the world behind it.
+                if self.cursor_pos.is_some() && !self.is_cursor_over_panels() {
+                    self.dragging = true;
+                    self.take_camera_control();
                 }
-                KeyCode::KeyS | KeyCode::ArrowDown => {
-                    self.held_inputs.down = key_state;
+            }
+            Action::TogglePanels => self.show_panels = !self.show_panels,
+            Action::Pause => {
+                if self.simulation_speed == 0 {
+                    self.simulation_speed = self.speed_before_pause.max(1);
+                } else {
+                    self.speed_before_pause = self.simulation_speed;
+                    self.simulation_speed = 0;
                 }
-                KeyCode::KeyA | KeyCode::ArrowLeft => {
-                    self.held_inputs.left = key_state;
+            }
+            Action::Save => self.pending_actions.push(PendingAction::Save),
+            Action::Load => self.pending_actions.push(PendingAction::Load),
+            Action::Undo => self.pending_actions.push(PendingAction::Undo),
+            Action::Redo => self.pending_actions.push(PendingAction::Redo),
+            Action::CycleOverlay => overlays.cycle(true),
+            Action::CycleOverlayBackwards => overlays.cycle(false),
+            Action::IncreaseOverlayOpacity => overlays.change_opacity(OVERLAY_OPACITY_STEP),
+            Action::DecreaseOverlayOpacity => overlays.change_opacity(-OVERLAY_OPACITY_STEP),
+            Action::ToggleChunkGrid => {
+                self.render_settings.show_chunk_grid = !self.render_settings.show_chunk_grid;
+            }
+            Action::ToggleTileGrid => {
+                self.render_settings.show_tile_grid = !self.render_settings.show_tile_grid;
+            }
+            Action::ToggleMinimap => {
+                self.render_settings.show_minimap = !self.render_settings.show_minimap;
+            }
+            Action::GoTo => self.prompt = Some(TextPrompt::new(PromptPurpose::GoTo)),
+            Action::AddBookmark => {
+                self.prompt = Some(TextPrompt::new(PromptPurpose::BookmarkName));
+            }
+            Action::ToggleFollow => self.follow_toggled = true,
+            Action::AddViewport => self.add_viewport(),
+            Action::RemoveViewport => self.remove_viewport(),
+        }
+    }
+
+    /// Uses the text typed into the prompt, closing it unless the text wasn't valid.
+    fn submit_prompt(&mut self, text: &str) {
+        let Some(prompt) = &mut self.prompt else {
+            return;
+        };
+
+        match prompt.purpose {
+            PromptPurpose::GoTo => match PixelPos::parse(text) {
+                Ok(pos) => {
+                    self.take_camera_control();
+                    self.camera_mut().fly_to(pos, None);
                 }
-                KeyCode::KeyD | KeyCode::ArrowRight => {
-                    self.held_inputs.right = key_state;
+                Err(error) => {
+                    prompt.reject(error);
+                    return;
                 }
-                _ => {}
+            },
+            PromptPurpose::BookmarkName => {
+                let camera = &self.viewports[self.active_viewport].camera;
+                self.bookmarks
+                    .add(text, &camera.pos, camera.target_zoom_level());
             }
         }
+        self.prompt = None;
     }
 
-    pub fn handle_scroll_event(&mut self, delta: &MouseScrollDelta) {
-        if let MouseScrollDelta::LineDelta(_, lines) = delta {
-            self.camera.zoom(*lines);
+    /// Where the minimap is, or None if it's hidden.
+    pub fn minimap_layout(&self) -> Option<MinimapLayout> {
+        self.render_settings.show_minimap.then(|| {
+            MinimapLayout::new(self.window_size, self.generated_bounds, &self.camera().pos)
+        })
+    }
+
+    /// Stops the active viewport's camera following anything or flying anywhere, because the user
+    /// is moving it.
+    fn take_camera_control(&mut self) {
+        let viewport = &mut self.viewports[self.active_viewport];
+        viewport.following = None;
+        viewport.camera.stop_flying();
+    }
+
+    fn fly_to_bookmark(&mut self, index: usize) {
+        let Some(bookmark) = self.bookmarks.get(index) else {
+            return;
+        };
+        let (pos, zoom_level) = (bookmark.pos(), bookmark.zoom_level());
+        self.take_camera_control();
+        self.camera_mut().fly_to(pos, Some(zoom_level));
+    }
+
+    /// Starts or stops following something in the active viewport if the user asked to, and keeps
+    /// each viewport's camera locked onto whatever it's following.
+    fn update_following(&mut self, world: &World) {
+        if std::mem::take(&mut self.follow_toggled) {
+            let selected_tile = self.inspector.selected_tile();
+            let viewport = &mut self.viewports[self.active_viewport];
+            if viewport.following.is_some() {
+                viewport.following = None;
+            } else {
+                // Follow the selected settlement if there is one, or the nearest one otherwise.
+                let selected_settlement = selected_tile.and_then(|coords| {
+                    world
+                        .settlements
+                        .iter()
+                        .find(|settlement| settlement.position == coords)
+                });
+                viewport.following = selected_settlement
+                    .map(|settlement| FollowTarget::Settlement(settlement.id))
+                    .or_else(|| FollowTarget::nearest_settlement(world, &viewport.camera.pos));
+                if let Some(pos) = viewport.following.and_then(|target| target.position(world)) {
+                    viewport.camera.fly_to(pos, None);
+                }
+            }
+        }
+
+        for viewport in &mut self.viewports {
+            let Some(target) = viewport.following else {
+                continue;
+            };
+            match target.position(world) {
+                // Once the camera has flown to the target, it sticks to it.
+                Some(pos) if !viewport.camera.is_flying() => viewport.camera.pos = pos,
+                Some(_) => {}
+                None => viewport.following = None,
+            }
+        }
+    }
+
+    /// Stops the actions that last for as long as they're held down, once none of their bindings
+    /// are held any more.
+    fn stop_action(&mut self, action: Action) {
+        if self.bindings.is_held(action, &self.held_buttons) {
+            return;
+        }
+        match action {
+            Action::Interact => self.painting = false,
+            Action::DragCamera => self.dragging = false,
+            _ => {}
+        }
+    }
+
+    /// Paints with the brush or selects a tile to inspect, depending on whether the brush is enabled.
+    fn start_interacting(&mut self) {
+        let Some(cursor_pos) = self.cursor_pos else {
+            return;
+        };
+
+        // Clicks on the debug UI shouldn't also affect the world behind it.
+        if self.is_cursor_over_panels() {
+            self.panels_clicked = true;
+            return;
+        }
+
+        // Clicking on the minimap moves the camera straight to the place that was clicked.
+        if let Some(layout) = self.minimap_layout() {
+            let cursor_pos = [cursor_pos.x as f32, cursor_pos.y as f32];
+            if layout.contains(cursor_pos) {
+                self.take_camera_control();
+                self.camera_mut().pos = layout.screen_to_world(cursor_pos);
+                return;
+            }
+        }
+
+        // The brush is applied every tick while the mouse is held down.
+        if self.brush.enabled {
+            self.painting = true;
+            return;
         }
+
+        let clicked_tile = self.viewport().screen_to_world(cursor_pos).tile();
+        self.inspector.select(clicked_tile);
+    }
+
+    /// The number of times the world should be ticked this frame.
+    pub fn simulation_speed(&self) -> u32 {
+        self.simulation_speed
     }
 
-    pub fn tick(&mut self, world: &mut World) {
+    /// Adds information about the active camera and the world to the HUD, one line at a time
+    /// starting at `pos`, followed by the debug UI.
+    pub fn draw_hud(&self, hud: &mut Hud, pos: [f32; 2], world: &World) {
+        let camera = self.camera();
+        let mut lines = vec![
+            format!("Tick: {}", world.tick_count()),
+            format!(
+                "Camera: ({:.1}, {:.1})",
+                camera.pos.tile_x(),
+                camera.pos.tile_y()
+            ),
+            format!("Zoom: {:.2}x", camera.zoom_multiplier()),
+        ];
+        if self.viewports.len() > 1 {
+            lines.push(format!(
+                "Viewport {}/{}",
+                self.active_viewport + 1,
+                self.viewports.len()
+            ));
+        }
+        if let Some(target) = self.viewport().following {
+            lines.push(format!("Following {}", target.describe()));
+        }
+
+        for (index, line) in lines.iter().enumerate() {
+            hud.text(
+                [pos[0], pos[1] + index as f32 * Hud::line_height()],
+                line,
+                HUD_TEXT_COLOR,
+            );
+        }
+        if let Some(prompt) = &self.prompt {
+            prompt.draw(
+                hud,
+                [pos[0], pos[1] + lines.len() as f32 * Hud::line_height()],
+            );
+        }
+
+        hud.append(self.panels.draw_list());
+    }
+
+    pub fn tick(&mut self, world: &mut World, history: &mut CommandHistory) {
+        // Finish any brush stroke first so that it can be undone along with everything else.
+        if !self.painting {
+            if let Some(command) = self.brush.end_stroke() {
+                history.record(command);
+            }
+        }
+        for action in std::mem::take(&mut self.pending_actions) {
+            action.perform(world, history);
+            // A loaded or regenerated world may have been generated with different settings, and
+            // won't have the same chunks.
+            self.generator_params = *world.generator_params();
+            self.forget_generated_chunks();
+        }
+        self.handle_gamepad_events();
+        self.update_panels(world, history);
+        self.apply_brush(world, history);
         self.move_camera();
-        self.gen_chunks_around_camera(world);
+        self.update_following(world);
+        for (index, viewport) in self.viewports.iter_mut().enumerate() {
+            viewport.camera.update_flight();
+            // Only the active viewport can be being dragged.
+            viewport.camera.update_inertia(
+                self.dragging && index == self.active_viewport,
+                self.camera_controls.drag_inertia,
+            );
+            viewport.camera.update_zoom(viewport.size);
+        }
+        self.generate_visible_chunks(world, history);
+        self.generated_bounds = world.generated_bounds();
+    }
+
+    fn apply_brush(&mut self, world: &mut World, history: &mut CommandHistory) {
+        if !self.painting {
+            return;
+        }
+        let Some(cursor_pos) = self.cursor_pos else {
+            return;
+        };
+
+        let center = self.viewport().screen_to_world(cursor_pos).tile();
+        let edits = self.brush.apply(world, center);
+        if !edits.is_empty() {
+            history.log_unrecorded(&WorldCommand::EditTiles(edits));
+        }
+    }
+
+    fn is_cursor_over_panels(&self) -> bool {
+        self.show_panels
+            && (self.panels.is_capturing_pointer()
+                || self
+                    .cursor_pos
+                    .is_some_and(|pos| self.panels.is_over_ui([pos.x as f32, pos.y as f32])))
+    }
+
+    /// Passes the mouse state to the debug UI, ready for the panels to be rebuilt.
+    fn begin_panels_frame(&mut self) {
+        let input = PointerInput {
+            pos: self.cursor_pos.map(|pos| [pos.x as f32, pos.y as f32]),
+            down: self.bindings.is_held(Action::Interact, &self.held_buttons),
+            clicked: self.panels_clicked,
+        };
+        self.panels_clicked = false;
+        self.panels.begin_frame(
+            input,
+            [
+                self.window_size.width as f32 - DebugUi::panel_width() - DEBUG_UI_MARGIN_PX,
+                DEBUG_UI_MARGIN_PX,
+            ],
+        );
+    }
+
+    /// Rebuilds the debug UI panels, applying any changes the user made through them.
+    fn update_panels(&mut self, world: &mut World, history: &mut CommandHistory) {
+        self.begin_panels_frame();
+        if !self.show_panels {
+            return;
+        }
+
+        let Self {
+            panels,
+            simulation_speed,
+            generator_params,
+            chunk_generation_margin,
+            render_settings,
+            inspector,
+            brush,
+            pending_actions,
+            camera_controls,
+            bindings,
+            bookmarks,
+            viewports,
+            active_viewport,
+            ..
+        } = self;
+
+        panels.panel("Simulation", |ui| {
+            let mut speed = *simulation_speed as f32;
+            if ui.slider(
+                "Ticks per frame",
+                &mut speed,
+                0.0,
+                MAX_SIMULATION_SPEED as f32,
+            ) {
+                *simulation_speed = speed.round() as u32;
+            }
+            ui.label(&format!("Settlements: {}", world.settlements.len()));
+        });
+
+        let mut regenerated = false;
+        panels.panel("Generator", |ui| {
+            regenerated = generator_panel(
+                ui,
+                generator_params,
+                chunk_generation_margin,
+                world,
+                history,
+            );
+        });
+
+        let mut navigation_request = None;
+        panels.panel("Camera", |ui| {
+            navigation_request = camera_panel(ui, camera_controls, viewports.len(), bindings)
+                .map(NavigationRequest::Action);
+        });
+
+        panels.panel("Navigation", |ui| {
+            let following = viewports[*active_viewport].following;
+            if let Some(request) = navigation_panel(ui, bookmarks, following, bindings) {
+                navigation_request = Some(request);
+            }
+        });
+
+        panels.panel("Brush", |ui| {
+            ui.checkbox("Enabled", &mut brush.enabled);
+            if ui.button(&format!("Mode: {}", brush.mode.name())) {
+                brush.mode = brush.mode.next();
+            }
+            ui.slider("Radius", &mut brush.radius, 0.5, 15.0);
+            ui.slider("Falloff", &mut brush.falloff, 0.0, 1.0);
+            if matches!(brush.mode, BrushMode::Raise | BrushMode::Lower) {
+                ui.slider("Strength", &mut brush.strength, 0.001, 0.05);
+            }
+        });
+
+        panels.panel("History", |ui| history_panel(ui, pending_actions, bindings));
+
+        panels.panel("Overlays", |ui| {
+            render_settings_panel(ui, render_settings, bindings);
+        });
+
+        panels.panel("Inspector", |ui| {
+            let lines = inspector.describe(world);
+            if lines.is_empty() {
+                ui.label("Click a tile to inspect it.");
+            }
+            for line in lines {
+                ui.label(&line);
+            }
+            if let Some(position) = inspector.selected_tile() {
+                if ui.button("Found settlement here") {
+                    history.execute(
+                        WorldCommand::SpawnSettlement {
+                            position,
+                            index: None,
+                        },
+                        world,
+                    );
+                }
+            }
+        });
+
+        if regenerated {
+            self.forget_generated_chunks();
+        }
+        match navigation_request {
+            Some(NavigationRequest::Action(action)) => self.start_action(action),
+            Some(NavigationRequest::FlyToBookmark(index)) => self.fly_to_bookmark(index),
+            Some(NavigationRequest::DeleteBookmark(index)) => self.bookmarks.remove(index),
+            None => {}
+        }
     }
 
     fn move_camera(&mut self) {
         let mut move_directions = HashSet::new();
 
-        if self.held_inputs.up == KeyState::Pressed {
+        if self.is_held(Action::PanUp) {
             move_directions.insert(PrimaryDirection::North);
         }
-        if self.held_inputs.down == KeyState::Pressed {
+        if self.is_held(Action::PanDown) {
             move_directions.insert(PrimaryDirection::South);
         }
-        if self.held_inputs.left == KeyState::Pressed {
+        if self.is_held(Action::PanLeft) {
             move_directions.insert(PrimaryDirection::West);
         }
-        if self.held_inputs.right == KeyState::Pressed {
+        if self.is_held(Action::PanRight) {
             move_directions.insert(PrimaryDirection::East);
         }
+        move_directions.extend(self.edge_scroll_directions());
+        let gamepad_direction = self.gamepads.pan_direction();
 
-        self.camera
-            .pan(&MoveDirection::from_primary_directions(move_directions));
+        if !move_directions.is_empty()
+            || gamepad_direction.iter().any(|&component| component != 0.0)
+        {
+            self.take_camera_control();
+        }
+        let zoom_direction = self.gamepads.zoom_direction();
+        let camera = self.camera_mut();
+        camera.pan(&MoveDirection::from_primary_directions(move_directions));
+        camera.pan_by(gamepad_direction);
+        if zoom_direction != 0.0 {
+            camera.zoom(zoom_direction * GAMEPAD_ZOOM_LEVELS_PER_TICK, None);
+        }
     }
 
-    /// Generate chunks in a radius around the camera.
-    fn gen_chunks_around_camera(&self, world: &mut World) {
-        const CHUNK_GENERATION_RADIUS: i32 = 10;
+    fn is_held(&self, action: Action) -> bool {
+        self.bindings.is_held(action, &self.held_buttons)
+    }
 
-        let center_chunk_pos = self.camera.pos.into_int_chunk_coords();
+    /// The directions to move the camera in because the cursor is near the edges of the active
+    /// viewport.
+    fn edge_scroll_directions(&self) -> Vec<PrimaryDirection> {
+        let Some(cursor_pos) = self.cursor_pos else {
+            return vec![];
+        };
+        if !self.camera_controls.edge_scrolling || self.dragging || self.is_cursor_over_panels() {
+            return vec![];
+        }
 
-        for x_chunk_pos in center_chunk_pos.x - CHUNK_GENERATION_RADIUS
-            ..=center_chunk_pos.x + CHUNK_GENERATION_RADIUS
-        {
-            for y_chunk_pos in center_chunk_pos.y - CHUNK_GENERATION_RADIUS
-                ..=center_chunk_pos.y + CHUNK_GENERATION_RADIUS
-            {
-                // TODO: Optimize - Only generate chunks when the camera moves, and skip chunks that
-                //  would have been in the radius of the old camera position.
-                world.generate_chunk(IntChunkCoordinates {
-                    x: x_chunk_pos,
-                    y: y_chunk_pos,
-                });
+        self.viewport()
+            .edges_near(cursor_pos, EDGE_SCROLL_MARGIN_PX)
+    }
+
+    /// Generates the chunks that the viewports can see, plus a margin around them. The chunks
+    /// nearest to the cameras are generated first, and only so many are generated per tick so that
+    /// zooming far out doesn't freeze everything.
+    fn generate_visible_chunks(&mut self, world: &mut World, history: &mut CommandHistory) {
+        let margin = self.chunk_generation_margin;
+        let mut missing_chunks = vec![];
+
+        for viewport in &mut self.viewports {
+            let area = viewport.chunk_generation_area(margin);
+            // Nothing new can have come into view if the area hasn't changed since it was last
+            // finished.
+            if viewport.generated_area == Some(area) {
+                continue;
+            }
+            viewport.generated_area = None;
+
+            let center = viewport.camera.pos.chunk();
+            for chunk_pos in ChunkPos::rect(area[0], area[1]) {
+                if !world.chunks.contains_key(&chunk_pos) {
+                    let distance_squared = chunk_pos.distance_squared(center);
+                    missing_chunks.push((distance_squared, chunk_pos));
+                }
             }
         }
+
+        missing_chunks.sort_unstable_by_key(|&(distance_squared, _)| distance_squared);
+        for &(_, chunk_pos) in missing_chunks.iter().take(MAX_CHUNKS_GENERATED_PER_TICK) {
+            history.generate_chunk(world, chunk_pos);
+        }
+
+        // Otherwise, the rest of the chunks are looked for again next tick.
+        if missing_chunks.len() <= MAX_CHUNKS_GENERATED_PER_TICK {
+            for viewport in &mut self.viewports {
+                viewport.generated_area = Some(viewport.chunk_generation_area(margin));
+            }
+        }
+    }
+
+    /// Makes the chunks be looked for again, because the world has been replaced by one that might
+    /// not have them.
+    fn forget_generated_chunks(&mut self) {
+        for viewport in &mut self.viewports {
+            viewport.generated_area = None;
+        }
     }
 }
 
-#[derive(Default)]
-struct Inputs {
-    up: KeyState,
-    down: KeyState,
-    left: KeyState,
-    right: KeyState,
+/// The contents of the panel for changing how the world is generated. Returns whether the world
+/// was regenerated.
+fn generator_panel(
+    ui: &mut DebugUi,
+    generator_params: &mut GeneratorParams,
+    chunk_generation_margin: &mut i32,
+    world: &mut World,
+    history: &mut CommandHistory,
+) -> bool {
+    let mut noise_scale = generator_params.noise_scale as f32;
+    if ui.slider("Noise scale", &mut noise_scale, 5.0, 150.0) {
+        generator_params.noise_scale = f64::from(noise_scale);
+    }
+    let mut octaves = generator_params.octaves as f32;
+    if ui.slider("Octaves", &mut octaves, 1.0, 8.0) {
+        generator_params.octaves = octaves.round() as i32;
+    }
+    ui.slider(
+        "Elevation offset",
+        &mut generator_params.elevation_offset,
+        -0.3,
+        0.3,
+    );
+    let mut margin = *chunk_generation_margin as f32;
+    if ui.slider(
+        "Generation margin (chunks)",
+        &mut margin,
+        0.0,
+        MAX_CHUNK_GENERATION_MARGIN as f32,
+    ) {
+        *chunk_generation_margin = margin.round() as i32;
+    }
+    if ui.button("Regenerate world") {
+        history.execute(
+            WorldCommand::Regenerate {
+                params: *generator_params,
+                previous_world: None,
+            },
+            world,
+        );
+        return true;
+    }
+    false
+}
+
+/// The contents of the panel for changing how the camera is controlled and splitting the window
+/// into viewports. Returns the viewport action whose button was clicked, if any.
+fn camera_panel(
+    ui: &mut DebugUi,
+    camera_controls: &mut CameraControls,
+    viewport_count: usize,
+    bindings: &Bindings,
+) -> Option<Action> {
+    ui.checkbox("Drag inertia", &mut camera_controls.drag_inertia);
+    ui.checkbox("Edge scrolling", &mut camera_controls.edge_scrolling);
+    ui.label(&format!("Viewports: {viewport_count}/{MAX_VIEWPORTS}"));
+
+    let mut clicked_action = None;
+    for (label, action) in [
+        ("Add viewport", Action::AddViewport),
+        ("Close active viewport", Action::RemoveViewport),
+    ] {
+        if ui.button(&format!("{label} ({})", bindings.describe(action))) {
+            clicked_action = Some(action);
+        }
+    }
+    clicked_action
+}
+
+/// Something the user asked for in the camera or navigation panels.
+enum NavigationRequest {
+    Action(Action),
+    FlyToBookmark(usize),
+    DeleteBookmark(usize),
+}
+
+/// The contents of the panel for getting around the world.
+fn navigation_panel(
+    ui: &mut DebugUi,
+    bookmarks: &Bookmarks,
+    following: Option<FollowTarget>,
+    bindings: &Bindings,
+) -> Option<NavigationRequest> {
+    let mut request = None;
+
+    if ui.button(&format!(
+        "Go to coordinates ({})",
+        bindings.describe(Action::GoTo)
+    )) {
+        request = Some(NavigationRequest::Action(Action::GoTo));
+    }
+    let follow_label = match following {
+        Some(target) => format!("Stop following {}", target.describe()),
+        None => "Follow settlement".to_string(),
+    };
+    if ui.button(&format!(
+        "{follow_label} ({})",
+        bindings.describe(Action::ToggleFollow)
+    )) {
+        request = Some(NavigationRequest::Action(Action::ToggleFollow));
+    }
+    if ui.button(&format!(
+        "Add bookmark ({})",
+        bindings.describe(Action::AddBookmark)
+    )) {
+        request = Some(NavigationRequest::Action(Action::AddBookmark));
+    }
+
+    for (index, bookmark) in bookmarks.iter().enumerate() {
+        if ui.button(&format!("Go to {}", bookmark.name)) {
+            request = Some(NavigationRequest::FlyToBookmark(index));
+        }
+        if ui.button(&format!("Delete {}", bookmark.name)) {
+            request = Some(NavigationRequest::DeleteBookmark(index));
+        }
+    }
+
+    request
+}
+
+/// The contents of the panel for undoing, redoing and recording changes to the world.
+fn history_panel(ui: &mut DebugUi, pending_actions: &mut Vec<PendingAction>, bindings: &Bindings) {
+    for (label, action) in [
+        (
+            format!("Undo ({})", bindings.describe(Action::Undo)),
+            PendingAction::Undo,
+        ),
+        (
+            format!("Redo ({})", bindings.describe(Action::Redo)),
+            PendingAction::Redo,
+        ),
+        ("Save session".to_string(), PendingAction::SaveSession),
+        ("Replay session".to_string(), PendingAction::ReplaySession),
+    ] {
+        if ui.button(&label) {
+            pending_actions.push(action);
+        }
+    }
+}
+
+/// The contents of the panel for changing what gets drawn and how.
+fn render_settings_panel(
+    ui: &mut DebugUi,
+    render_settings: &mut RenderSettings,
+    bindings: &Bindings,
+) {
+    ui.checkbox("Roads", &mut render_settings.show_roads);
+    ui.checkbox("Settlements", &mut render_settings.show_settlements);
+    ui.checkbox("Sprites", &mut render_settings.show_sprites);
+    ui.checkbox(
+        &format!(
+            "Chunk grid ({})",
+            bindings.describe(Action::ToggleChunkGrid)
+        ),
+        &mut render_settings.show_chunk_grid,
+    );
+    ui.checkbox(
+        &format!("Tile grid ({})", bindings.describe(Action::ToggleTileGrid)),
+        &mut render_settings.show_tile_grid,
+    );
+    ui.checkbox(
+        &format!("Minimap ({})", bindings.describe(Action::ToggleMinimap)),
+        &mut render_settings.show_minimap,
+    );
+    let mode = render_settings.tile_render_mode;
+    if ui.button(&format!("Tiles drawn as: {}", mode.name())) {
+        render_settings.tile_render_mode = mode.next();
+    }
+
+    let overlays = &mut render_settings.overlays;
+    let overlay_name = overlays.active().map_or("None", |overlay| overlay.name);
+    if ui.button(&format!(
+        "Overlay ({}): {overlay_name}",
+        bindings.describe(Action::CycleOverlay)
+    )) {
+        overlays.cycle(true);
+    }
+    let mut opacity = overlays.opacity();
+    let opacity_label = format!(
+        "Overlay opacity ({} {})",
+        bindings.describe(Action::DecreaseOverlayOpacity),
+        bindings.describe(Action::IncreaseOverlayOpacity)
+    );
+    if ui.slider(&opacity_label, &mut opacity, 0.0, 1.0) {
+        overlays.change_opacity(opacity - overlays.opacity());
+    }
 }
 
-#[derive(Debug, PartialEq, Eq)]
-enum KeyState {
-    Pressed,
-    Released,
+/// Options for how the mouse moves the camera.
+struct CameraControls {
+    /// Whether the camera keeps gliding for a bit after being dragged.
+    drag_inertia: bool,
+    /// Whether the camera moves when the cursor is near the edges of the window.
+    edge_scrolling: bool,
 }
 
-impl Default for KeyState {
+impl Default for CameraControls {
     fn default() -> Self {
-        Self::Released
+        CameraControls {
+            drag_inertia: true,
+            // This gets in the way when the window doesn't fill the screen, so it's opt-in.
+            edge_scrolling: false,
+        }
+    }
+}
+
+/// Something the user asked to do to the world from outside of `tick`.
+enum PendingAction {
+    Save,
+    Load,
+    Undo,
+    Redo,
+    /// Saves the command log so that the editing session can be replayed later.
+    SaveSession,
+    /// Replaces the world with a fresh one that has the saved editing session replayed on it.
+    ReplaySession,
+}
+
+impl PendingAction {
+    fn perform(&self, world: &mut World, history: &mut CommandHistory) {
+        match self {
+            PendingAction::Save => write_file(SAVE_FILE_PATH, &world.serialize()),
+            PendingAction::Load => {
+                let Some(data) = read_file(SAVE_FILE_PATH) else {
+                    return;
+                };
+                match World::deserialize(&data) {
+                    Ok(loaded_world) => {
+                        *world = loaded_world;
+                        // The old commands don't make sense for the loaded world.
+                        *history = CommandHistory::for_loaded_world();
+                        println!("Loaded the world from {SAVE_FILE_PATH}.");
+                    }
+                    Err(error) => eprintln!("Couldn't load the world: {error}"),
+                }
+            }
+            PendingAction::Undo => history.undo(world),
+            PendingAction::Redo => history.redo(world),
+            PendingAction::SaveSession => match history.log() {
+                Ok(log) => write_file(SESSION_FILE_PATH, log),
+                Err(problem) => eprintln!("Couldn't save the session: {problem}"),
+            },
+            PendingAction::ReplaySession => {
+                let Some(data) = read_file(SESSION_FILE_PATH) else {
+                    return;
+                };
+                match CommandHistory::replay(&data) {
+                    Ok((replayed_world, replayed_history)) => {
+                        *world = replayed_world;
+                        *history = replayed_history;
+                        println!("Replayed the session from {SESSION_FILE_PATH}.");
+                    }
+                    Err(error) => eprintln!("Couldn't replay the session: {error}"),
+                }
+            }
+        }
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn write_file(path: &str, data: &[u8]) {
+    match std::fs::write(path, data) {
+        Ok(()) => println!("Saved {path}."),
+        Err(error) => eprintln!("Couldn't write {path}: {error}"),
     }
 }
+
+#[cfg(not(target_arch = "wasm32"))]
+fn read_file(path: &str) -> Option<Vec<u8>> {
+    std::fs::read(path)
+        .inspect_err(|error| eprintln!("Couldn't read {path}: {error}"))
+        .ok()
+}
+
+/// Reads a file that may not exist. Not existing isn't reported as a problem, since this is used
+/// for optional files like settings.
+#[cfg(not(target_arch = "wasm32"))]
+fn read_text_file_if_exists(path: &str) -> Option<String> {
+    match std::fs::read_to_string(path) {
+        Ok(text) => Some(text),
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => None,
+        Err(error) => {
+            eprintln!("Couldn't read {path}: {error}");
+            None
+        }
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+fn write_file(_path: &str, _data: &[u8]) {
+    log::warn!("Saving isn't supported on the web yet.");
+}
+
+#[cfg(target_arch = "wasm32")]
+fn read_file(_path: &str) -> Option<Vec<u8>> {
+    log::warn!("Loading isn't supported on the web yet.");
+    None
+}
+
+/// There's no file system on the web, so the optional files never exist there.
+#[cfg(target_arch = "wasm32")]
+fn read_text_file_if_exists(_path: &str) -> Option<String> {
+    None
+}
+
+/// The distance between the debug UI and the edges of the window.
+const DEBUG_UI_MARGIN_PX: f32 = 8.0;
+/// The highest number of ticks per frame that can be chosen in the debug UI.
+const MAX_SIMULATION_SPEED: u32 = 20;
+/// How close the cursor has to be to the edge of a viewport for edge scrolling to kick in.
+const EDGE_SCROLL_MARGIN_PX: f64 = 16.0;
+/// The number of pixels a touchpad has to scroll by to zoom by one level.
+const SCROLL_PIXELS_PER_ZOOM_LEVEL: f32 = 50.0;
+/// How many zoom levels the camera moves by per tick while a gamepad trigger is fully pressed.
+const GAMEPAD_ZOOM_LEVELS_PER_TICK: f32 = 0.1;
+/// How many chunks beyond the edges of each viewport are generated ahead of time, until it's changed
+/// in the debug UI.
+const DEFAULT_CHUNK_GENERATION_MARGIN: i32 = 2;
+/// The largest generation margin that can be chosen in the debug UI.
+const MAX_CHUNK_GENERATION_MARGIN: i32 = 10;
+/// The most chunks that are generated in a single tick. The rest wait for later ticks, nearest first.
+const MAX_CHUNKS_GENERATED_PER_TICK: usize = 64;
+/// How much the overlay opacity changes each time one of its keys is pressed.
+const OVERLAY_OPACITY_STEP: f32 = 0.1;
+
+/// Where the world is saved to and loaded from, relative to the working directory.
+const SAVE_FILE_PATH: &str = "world.sav";
+/// Where the command log is saved to and replayed from, relative to the working directory.
+const SESSION_FILE_PATH: &str = "session.log";
@@ -0,0 +1,84 @@
+use crate::display::{Hud, HUD_TEXT_COLOR};
+use winit::event::{ElementState, KeyEvent};
+use winit::keyboard::{Key, NamedKey};
+
+/// A line of text that the user is typing in, which takes over the keyboard until it's submitted
+/// with Enter or cancelled with Escape.
+pub struct TextPrompt {
+    pub purpose: PromptPurpose,
+    text: String,
+    /// Why the last submitted text wasn't accepted, if it wasn't.
+    error: Option<String>,
+}
+
+/// What the text in a prompt will be used for.
+#[derive(Copy, Clone)]
+pub enum PromptPurpose {
+    /// Coordinates for the camera to fly to.
+    GoTo,
+    /// The name of a new bookmark at the camera's position.
+    BookmarkName,
+}
+
+/// What happened to a prompt because of a key press.
+pub enum PromptUpdate {
+    Editing,
+    Submitted(String),
+    Cancelled,
+}
+
+impl TextPrompt {
+    pub fn new(purpose: PromptPurpose) -> Self {
+        TextPrompt {
+            purpose,
+            text: String::new(),
+            error: None,
+        }
+    }
+
+    pub fn handle_key_event(&mut self, event: &KeyEvent) -> PromptUpdate {
+        if event.state != ElementState::Pressed {
+            return PromptUpdate::Editing;
+        }
+
+        match &event.logical_key {
+            Key::Named(NamedKey::Enter) => return PromptUpdate::Submitted(self.text.clone()),
+            Key::Named(NamedKey::Escape) => return PromptUpdate::Cancelled,
+            Key::Named(NamedKey::Backspace) => {
+                self.text.pop();
+            }
+            _ => {
+                if let Some(text) = &event.text {
+                    // The HUD font only has printable ASCII characters.
+                    self.text.extend(
+                        text.chars()
+                            .filter(|character| character.is_ascii() && !character.is_control()),
+                    );
+                }
+            }
+        }
+        self.error = None;
+        PromptUpdate::Editing
+    }
+
+    /// Shows why the text wasn't accepted, keeping the prompt open so that it can be fixed.
+    pub fn reject(&mut self, error: String) {
+        self.error = Some(error);
+    }
+
+    /// Draws the prompt as one or two lines of text, with `pos` being its top left corner.
+    pub fn draw(&self, hud: &mut Hud, pos: [f32; 2]) {
+        let label = match self.purpose {
+            PromptPurpose::GoTo => "Go to (x, y tiles/chunks/px)",
+            PromptPurpose::BookmarkName => "Bookmark name",
+        };
+        hud.text(pos, &format!("{label}: {}_", self.text), HUD_TEXT_COLOR);
+        if let Some(error) = &self.error {
+            hud.text(
+                [pos[0], pos[1] + Hud::line_height()],
+                &format!("Error: {error}"),
+                HUD_TEXT_COLOR,
+            );
+        }
+    }
+}
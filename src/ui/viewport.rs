@@ -0,0 +1,174 @@
+use crate::position::{ChunkPos, PixelPos};
+use crate::ui::camera::{Camera, PrimaryDirection};
+use crate::ui::follow::FollowTarget;
+use winit::dpi::{PhysicalPosition, PhysicalSize};
+
+/// A part of the window that shows the world through its own camera. The window is split into
+/// side-by-side viewports so that different places (or the same place at different zooms) can be
+/// watched at once.
+pub struct Viewport {
+    pub camera: Camera,
+    /// The thing that the camera is locked onto, if any.
+    pub following: Option<FollowTarget>,
+    /// The top left corner of the viewport, in pixels from the top left corner of the window.
+    pub origin: PhysicalPosition<u32>,
+    pub size: PhysicalSize<u32>,
+    /// The chunks (bottom left and top right) that were all generated the last time chunks were
+    /// generated for the viewport, or None if some of them were still missing.
+    pub(super) generated_area: Option<[ChunkPos; 2]>,
+}
+
+impl Viewport {
+    pub fn new(camera: Camera) -> Self {
+        Viewport {
+            camera,
+            following: None,
+            origin: PhysicalPosition::new(0, 0),
+            size: PhysicalSize::new(1, 1),
+            generated_area: None,
+        }
+    }
+
+    /// Whether a position in the window (in pixels from its top left corner) is in the viewport.
+    pub fn contains(&self, window_pos: PhysicalPosition<f64>) -> bool {
+        let local_pos = self.to_local(window_pos);
+        local_pos.x >= 0.0
+            && local_pos.y >= 0.0
+            && local_pos.x < f64::from(self.size.width)
+            && local_pos.y < f64::from(self.size.height)
+    }
+
+    /// Converts a position in the window into a position relative to the top left corner of the
+    /// viewport.
+    pub fn to_local(&self, window_pos: PhysicalPosition<f64>) -> PhysicalPosition<f64> {
+        PhysicalPosition::new(
+            window_pos.x - f64::from(self.origin.x),
+            window_pos.y - f64::from(self.origin.y),
+        )
+    }
+
+    /// Converts a position in the window into the position in the world that the viewport shows
+    /// there.
+    pub fn screen_to_world(&self, window_pos: PhysicalPosition<f64>) -> PixelPos {
+        self.camera
+            .screen_to_world(self.to_local(window_pos), self.size)
+    }
+
+    /// The bottom left and top right chunks that should be generated for the viewport: the ones
+    /// that it can see, plus `margin` chunks on every side so that panning doesn't reveal gaps.
+    pub fn chunk_generation_area(&self, margin: i32) -> [ChunkPos; 2] {
+        let (bottom_left, top_right) = self.camera.visible_bounds(self.size);
+        let [bottom_left, top_right] = [bottom_left, top_right].map(PixelPos::chunk);
+        let margin = ChunkPos {
+            x: margin,
+            y: margin,
+        };
+        [bottom_left - margin, top_right + margin]
+    }
+
+    /// The edges of the viewport that a position in the window is within `margin` pixels of. A
+    /// position outside the viewport isn't near any of its edges.
+    pub fn edges_near(
+        &self,
+        window_pos: PhysicalPosition<f64>,
+        margin: f64,
+    ) -> Vec<PrimaryDirection> {
+        if !self.contains(window_pos) {
+            return vec![];
+        }
+
+        let local_pos = self.to_local(window_pos);
+        let mut edges = vec![];
+        if local_pos.y < margin {
+            edges.push(PrimaryDirection::North);
+        }
+        if local_pos.y > f64::from(self.size.height) - margin {
+            edges.push(PrimaryDirection::South);
+        }
+        if local_pos.x < margin {
+            edges.push(PrimaryDirection::West);
+        }
+        if local_pos.x > f64::from(self.size.width) - margin {
+            edges.push(PrimaryDirection::East);
+        }
+        edges
+    }
+
+    /// The inverse of `screen_to_world`, or None if the position isn't in the viewport.
+    pub fn world_to_screen(&self, world_pos: &PixelPos) -> Option<[f32; 2]> {
+        let [x, y] = self.camera.world_to_screen(world_pos, self.size)?;
+        Some([x + self.origin.x as f32, y + self.origin.y as f32])
+    }
+}
+
+/// Splits the window into equal columns, one for each viewport.
+pub fn arrange(viewports: &mut [Viewport], window_size: PhysicalSize<u32>) {
+    let count = viewports.len() as u32;
+    for (index, viewport) in viewports.iter_mut().enumerate() {
+        let index = index as u32;
+        let left = window_size.width * index / count;
+        let right = window_size.width * (index + 1) / count;
+        viewport.origin = PhysicalPosition::new(left, 0);
+        // Zero-sized viewports can't be drawn to, so they're kept at least one pixel wide.
+        viewport.size = PhysicalSize::new((right - left).max(1), window_size.height.max(1));
+    }
+}
+
+/// The most viewports that the window can be split into.
+pub const MAX_VIEWPORTS: usize = 4;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn viewport_at(chunk_pos: ChunkPos) -> Viewport {
+        let mut camera = Camera::new();
+        camera.pos = chunk_pos.bottom_left();
+        let mut viewport = Viewport::new(camera);
+        viewport.size = PhysicalSize::new(800, 600);
+        viewport
+    }
+
+    #[test]
+    fn chunk_generation_area_adds_the_margin() {
+        let viewport = viewport_at(ChunkPos { x: 10, y: -10 });
+        let [bottom_left, top_right] = viewport.chunk_generation_area(0);
+        let [bottom_left_with_margin, top_right_with_margin] = viewport.chunk_generation_area(2);
+
+        assert_eq!(
+            bottom_left_with_margin,
+            bottom_left - ChunkPos { x: 2, y: 2 }
+        );
+        assert_eq!(top_right_with_margin, top_right + ChunkPos { x: 2, y: 2 });
+    }
+
+    #[test]
+    fn chunk_generation_area_stops_at_the_edge_of_the_world() {
+        let viewport = viewport_at(ChunkPos {
+            x: i32::MAX,
+            y: i32::MIN,
+        });
+        let [bottom_left, top_right] = viewport.chunk_generation_area(5);
+
+        assert_eq!(bottom_left.y, i32::MIN);
+        assert_eq!(top_right.x, i32::MAX);
+        assert!(bottom_left.x < i32::MAX && top_right.y > i32::MIN);
+    }
+
+    #[test]
+    fn edges_near_uses_the_bounds_of_the_viewport() {
+        let mut viewports: Vec<_> = (0..2).map(|_| viewport_at(ChunkPos::default())).collect();
+        arrange(&mut viewports, PhysicalSize::new(800, 600));
+        let edges_near =
+            |index: usize, x, y| viewports[index].edges_near(PhysicalPosition::new(x, y), 16.0);
+
+        // Either side of the border between the two viewports.
+        assert!(edges_near(0, 395.0, 300.0) == [PrimaryDirection::East]);
+        assert!(edges_near(1, 405.0, 300.0) == [PrimaryDirection::West]);
+        assert!(edges_near(0, 200.0, 300.0).is_empty());
+        assert!(edges_near(1, 795.0, 5.0) == [PrimaryDirection::North, PrimaryDirection::East]);
+        // Positions in the other viewport aren't near this one's edges.
+        assert!(edges_near(0, 405.0, 300.0).is_empty());
+        assert!(edges_near(1, 5.0, 595.0).is_empty());
+    }
+}
@@ -6,3 +6,33 @@ pub fn resolve_optional_val<T>(val: Option<T>, default: T) -> T {
         default
     }
 }
+
+/// A small, fast pseudo-random number generator (xorshift64*). It's not suitable for anything
+/// security-related, but it's deterministic, which is what the simulation needs.
+#[derive(Copy, Clone)]
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        // The state must never be zero, or every output would be zero.
+        Rng { state: seed.max(1) }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state ^= self.state >> 12;
+        self.state ^= self.state << 25;
+        self.state ^= self.state >> 27;
+        self.state.wrapping_mul(0x2545_F491_4F6C_DD1D)
+    }
+
+    /// Returns a number in the range [0, max). `max` must be greater than 0.
+    pub fn below(&mut self, max: u32) -> u32 {
+        (self.next_u64() % u64::from(max)) as u32
+    }
+
+    pub fn state(self) -> u64 {
+        self.state
+    }
+}
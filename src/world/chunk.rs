@@ -1,12 +1,36 @@
-use crate::position::{IntChunkCoordinates, PositionMode};
+use crate::position::{ChunkPos, TileIndex};
 use crate::world::perlin_noise;
 use crate::world::tile::Tile;
+use crate::world::GeneratorParams;
+use std::cell::OnceCell;
+use std::sync::atomic::{AtomicU64, Ordering};
 
 /// A 32x32 chunk of the map.
-#[derive(Default)]
 pub struct Chunk {
     /// The tiles in the chunk, indexed as x/y from the bottom left corner.
     pub tiles: [[Tile; Chunk::side_size() as usize]; Chunk::side_size() as usize],
+    /// The elevation of each tile in the chunk, indexed the same way as `tiles`. Sea level is 0.
+    pub elevations: [[f32; Chunk::side_size() as usize]; Chunk::side_size() as usize],
+    /// Which tiles in the chunk have a road on them, indexed the same way as `tiles`.
+    pub roads: [[bool; Chunk::side_size() as usize]; Chunk::side_size() as usize],
+    /// Changes every time the chunk changes, so that anything derived from the chunk can tell when
+    /// it's out of date. Call `mark_dirty` after changing the chunk.
+    revision: u64,
+    /// Downsampled versions of `tiles` for drawing the chunk when it's far away, indexed by LOD
+    /// level minus one. They're only worked out when they're first needed.
+    lod_summaries: OnceCell<Vec<LodSummary>>,
+}
+
+impl Default for Chunk {
+    fn default() -> Self {
+        Chunk {
+            tiles: Default::default(),
+            elevations: Default::default(),
+            roads: Default::default(),
+            revision: next_revision(),
+            lod_summaries: OnceCell::new(),
+        }
+    }
 }
 
 impl Chunk {
@@ -15,38 +39,114 @@ impl Chunk {
         32
     }
 
-    pub fn generate(position: IntChunkCoordinates) -> Self {
+    /// The highest level of detail that can be passed to `lod_summary`, at which the whole chunk
+    /// is summarized as a single tile.
+    pub const fn max_lod_level() -> u32 {
+        Chunk::side_size().ilog2()
+    }
+
+    pub fn generate(position: ChunkPos, params: &GeneratorParams) -> Self {
         let mut chunk = Self {
             ..Default::default()
         };
 
-        let bottom_left_pos = position.into_bottom_left_tile_pos();
-
-        for x_in_chunk in 0..Chunk::side_size() {
-            let world_x = x_in_chunk + bottom_left_pos.x(PositionMode::Tiles) as i32;
-            for y_in_chunk in 0..Chunk::side_size() {
-                let world_y = y_in_chunk + bottom_left_pos.y(PositionMode::Tiles) as i32;
-
-                let noise = perlin_noise::octaved_noise(
-                    f64::from(world_x),
-                    f64::from(world_y),
-                    0.0,
-                    Some(30.0),
-                    None,
-                );
-
-                // Adjust the noise up a bit to reduce the amount of water
-                let tile_height = noise + 0.05;
-
-                chunk.tiles[x_in_chunk as usize][y_in_chunk as usize] = match tile_height {
-                    h if h < 0.0 => Tile::Water,
-                    h if h < 0.04 => Tile::Beach,
-                    h if h < 0.3 => Tile::Grass,
-                    _ => Tile::Mountain,
+        for index in TileIndex::all() {
+            let tile_pos = position.tile(index);
+
+            let noise = perlin_noise::octaved_noise(
+                tile_pos.x as f64,
+                tile_pos.y as f64,
+                0.0,
+                Some(params.noise_scale),
+                Some(params.octaves),
+            );
+
+            let tile_height = noise as f32 + params.elevation_offset;
+
+            chunk.elevations[index.x][index.y] = tile_height;
+            chunk.tiles[index.x][index.y] = Tile::from_elevation(tile_height);
+        }
+
+        chunk
+    }
+
+    /// Records that the chunk has changed.
+    pub fn mark_dirty(&mut self) {
+        self.revision = next_revision();
+        self.lod_summaries.take();
+    }
+
+    /// A number that identifies the current contents of the chunk. Revisions are never reused, even
+    /// by other chunks, so a chunk that replaces another one (for example, because a different
+    /// world was loaded) never looks like the chunk it replaced.
+    pub fn revision(&self) -> u64 {
+        self.revision
+    }
+
+    /// A summary of the chunk where each tile stands for a square of 2^`level` tiles on a side.
+    /// `level` must be between 1 and `Chunk::max_lod_level()`.
+    pub fn lod_summary(&self, level: u32) -> &LodSummary {
+        assert!(
+            (1..=Chunk::max_lod_level()).contains(&level),
+            "LOD level {level} is out of range"
+        );
+
+        let summaries = self.lod_summaries.get_or_init(|| {
+            (1..=Chunk::max_lod_level())
+                .map(|level| LodSummary::new(&self.tiles, level))
+                .collect()
+        });
+        &summaries[level as usize - 1]
+    }
+}
+
+/// A downsampled version of a chunk's tiles, where each tile is the most common tile in the
+/// square of tiles that it stands for.
+pub struct LodSummary {
+    /// The number of tiles on each side of the summary.
+    side_size: usize,
+    /// The tiles of the summary, indexed as x * `side_size` + y from the bottom left corner.
+    tiles: Vec<Tile>,
+}
+
+impl LodSummary {
+    fn new(
+        chunk_tiles: &[[Tile; Chunk::side_size() as usize]; Chunk::side_size() as usize],
+        level: u32,
+    ) -> Self {
+        let block_size = 1 << level;
+        let side_size = Chunk::side_size() as usize / block_size;
+        let mut tiles = Vec::with_capacity(side_size * side_size);
+
+        for block_x in 0..side_size {
+            for block_y in 0..side_size {
+                let mut counts = [0; u8::MAX as usize + 1];
+                for column in &chunk_tiles[block_x * block_size..(block_x + 1) * block_size] {
+                    for tile in &column[block_y * block_size..(block_y + 1) * block_size] {
+                        counts[tile.id() as usize] += 1;
+                    }
                 }
+
+                let most_common_id = (0..=u8::MAX)
+                    .max_by_key(|&id| counts[id as usize])
+                    .unwrap_or_default();
+                tiles.push(Tile::from_id(most_common_id).unwrap_or_default());
             }
         }
 
-        chunk
+        LodSummary { side_size, tiles }
+    }
+
+    pub fn side_size(&self) -> usize {
+        self.side_size
     }
+
+    pub fn tile(&self, x: usize, y: usize) -> Tile {
+        self.tiles[x * self.side_size + y]
+    }
+}
+
+fn next_revision() -> u64 {
+    static NEXT_REVISION: AtomicU64 = AtomicU64::new(0);
+    NEXT_REVISION.fetch_add(1, Ordering::Relaxed)
 }
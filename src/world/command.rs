@@ -0,0 +1,565 @@
+//! Commands for changing the world in ways that can be undone and redone.
+//!
+//! Every change to the world that doesn't come from the simulation itself (such as edits made by
+//! the user) should go through a `WorldCommand`, executed by a `CommandHistory`. The history keeps
+//! a log of everything that was done, which can be saved and then replayed against a fresh world.
+//! So that the replayed world ends up the same as the original, the simulation ticks and chunk
+//! generation in between the commands also go through the history and are logged with them.
+
+use crate::position::{ChunkPos, TilePos};
+use crate::world::save::{LoadError, Reader, Writer};
+use crate::world::{GeneratorParams, Tile, TileData, World};
+use std::fmt::{self, Display, Formatter};
+
+pub enum WorldCommand {
+    /// Changes the hand-editable data of a set of tiles.
+    EditTiles(Vec<TileEdit>),
+    /// Founds a settlement at the given tile.
+    SpawnSettlement {
+        position: TilePos,
+        /// The index of the settlement, once it has been spawned.
+        index: Option<usize>,
+    },
+    /// Throws away the world and starts again with new generator settings.
+    Regenerate {
+        params: GeneratorParams,
+        /// The world from before the command was applied, so that it can be restored.
+        previous_world: Option<Box<World>>,
+    },
+}
+
+/// A change to a single tile.
+#[derive(Copy, Clone)]
+pub struct TileEdit {
+    pub coords: TilePos,
+    pub before: TileData,
+    pub after: TileData,
+}
+
+impl WorldCommand {
+    pub fn apply(&mut self, world: &mut World) {
+        match self {
+            WorldCommand::EditTiles(edits) => {
+                for edit in edits {
+                    // The chunk might not exist yet if the command is being replayed.
+                    world.generate_chunk(edit.coords.chunk());
+                    world.set_tile_data(edit.coords, edit.after);
+                }
+            }
+            WorldCommand::SpawnSettlement { position, index } => {
+                world.generate_chunk(position.chunk());
+                *index = Some(world.spawn_settlement(*position));
+            }
+            WorldCommand::Regenerate {
+                params,
+                previous_world,
+            } => {
+                let new_world = World::with_generator_params(*params);
+                *previous_world = Some(Box::new(std::mem::replace(world, new_world)));
+            }
+        }
+    }
+
+    pub fn revert(&mut self, world: &mut World) {
+        match self {
+            WorldCommand::EditTiles(edits) => {
+                // Go backwards in case the same tile was edited more than once.
+                for edit in edits.iter().rev() {
+                    world.set_tile_data(edit.coords, edit.before);
+                }
+            }
+            WorldCommand::SpawnSettlement { index, .. } => {
+                if let Some(index) = index.take() {
+                    world.remove_settlement(index);
+                }
+            }
+            WorldCommand::Regenerate { previous_world, .. } => {
+                if let Some(previous_world) = previous_world.take() {
+                    *world = *previous_world;
+                }
+            }
+        }
+    }
+
+    /// Writes the command in the save file format. Only the information needed to apply the
+    /// command is written, not the information for reverting it.
+    fn serialize(&self, writer: &mut Writer) {
+        match self {
+            WorldCommand::EditTiles(edits) => {
+                writer.u8(EDIT_TILES_ID);
+                writer.u32(edits.len() as u32);
+                for edit in edits {
+                    writer.i64(edit.coords.x);
+                    writer.i64(edit.coords.y);
+                    for data in [edit.before, edit.after] {
+                        writer.u8(data.tile.id());
+                        writer.f32(data.elevation);
+                    }
+                }
+            }
+            WorldCommand::SpawnSettlement { position, .. } => {
+                writer.u8(SPAWN_SETTLEMENT_ID);
+                writer.i64(position.x);
+                writer.i64(position.y);
+            }
+            WorldCommand::Regenerate { params, .. } => {
+                writer.u8(REGENERATE_ID);
+                writer.f64(params.noise_scale);
+                writer.i32(params.octaves);
+                writer.f32(params.elevation_offset);
+            }
+        }
+    }
+
+    fn deserialize(reader: &mut Reader) -> Result<Self, LoadError> {
+        let read_tile_data = |reader: &mut Reader| -> Result<TileData, LoadError> {
+            let id = reader.u8()?;
+            Ok(TileData {
+                tile: Tile::from_id(id).ok_or(LoadError::InvalidTile(id))?,
+                elevation: reader.f32()?,
+            })
+        };
+
+        match reader.u8()? {
+            EDIT_TILES_ID => {
+                let count = reader.u32()?;
+                let mut edits = Vec::new();
+                for _ in 0..count {
+                    edits.push(TileEdit {
+                        coords: TilePos {
+                            x: reader.i64()?,
+                            y: reader.i64()?,
+                        },
+                        before: read_tile_data(reader)?,
+                        after: read_tile_data(reader)?,
+                    });
+                }
+                Ok(WorldCommand::EditTiles(edits))
+            }
+            SPAWN_SETTLEMENT_ID => Ok(WorldCommand::SpawnSettlement {
+                position: TilePos {
+                    x: reader.i64()?,
+                    y: reader.i64()?,
+                },
+                index: None,
+            }),
+            REGENERATE_ID => Ok(WorldCommand::Regenerate {
+                params: GeneratorParams {
+                    noise_scale: reader.f64()?,
+                    octaves: reader.i32()?,
+                    elevation_offset: reader.f32()?,
+                },
+                previous_world: None,
+            }),
+            id => Err(LoadError::InvalidCommand(id)),
+        }
+    }
+}
+
+/// A bounded list of commands that can be undone and redone, along with a log of everything that
+/// was done through it.
+#[derive(Default)]
+pub struct CommandHistory {
+    undo_stack: Vec<WorldCommand>,
+    redo_stack: Vec<WorldCommand>,
+    /// Every execute, undo and redo so far, along with the ticks and chunk generation in between,
+    /// in the save file format.
+    log: Writer,
+    /// The number of ticks since the last entry in the log. Runs of ticks are logged as a single
+    /// entry once something else happens.
+    unlogged_ticks: u32,
+    /// Why the log can no longer recreate the world, if it can't.
+    log_problem: Option<LogProblem>,
+}
+
+impl CommandHistory {
+    /// An empty history for a world that was loaded from a save file. The log can't be replayed,
+    /// since it would be replayed against a fresh world rather than the loaded one.
+    pub fn for_loaded_world() -> Self {
+        CommandHistory {
+            log_problem: Some(LogProblem::LoadedWorld),
+            ..Default::default()
+        }
+    }
+
+    /// Applies a command and adds it to the history.
+    pub fn execute(&mut self, mut command: WorldCommand, world: &mut World) {
+        command.apply(world);
+        if let Some(log) = self.start_log_entry(EXECUTE_ENTRY) {
+            command.serialize(log);
+        }
+        self.push(command);
+    }
+
+    /// Adds a command to the history that has already been applied bit by bit, with each bit
+    /// logged through `log_unrecorded`. The command itself isn't logged again - a replay puts
+    /// together the bits logged since the last recorded command instead.
+    pub fn record(&mut self, command: WorldCommand) {
+        self.start_log_entry(RECORD_ENTRY);
+        self.push(command);
+    }
+
+    /// Adds a command that has just been done to the undo stack.
+    fn push(&mut self, command: WorldCommand) {
+        self.undo_stack.push(command);
+        if self.undo_stack.len() > MAX_HISTORY_LENGTH {
+            self.undo_stack.remove(0);
+        }
+
+        // Each regeneration holds on to a whole world so that it can be undone, so only the most
+        // recent few can be. The commands from before the oldest one were applied to its world, so
+        // they can't be undone without it and go as well.
+        let is_regeneration =
+            |command: &WorldCommand| matches!(command, WorldCommand::Regenerate { .. });
+        if self
+            .undo_stack
+            .iter()
+            .filter(|command| is_regeneration(command))
+            .count()
+            > MAX_UNDOABLE_REGENERATIONS
+        {
+            if let Some(oldest) = self.undo_stack.iter().position(is_regeneration) {
+                self.undo_stack.drain(..=oldest);
+            }
+        }
+
+        // Doing something new means that the undone commands can't be redone any more.
+        self.redo_stack.clear();
+    }
+
+    pub fn undo(&mut self, world: &mut World) {
+        if let Some(mut command) = self.undo_stack.pop() {
+            command.revert(world);
+            self.redo_stack.push(command);
+            self.start_log_entry(UNDO_ENTRY);
+        }
+    }
+
+    pub fn redo(&mut self, world: &mut World) {
+        if let Some(mut command) = self.redo_stack.pop() {
+            command.apply(world);
+            self.undo_stack.push(command);
+            self.start_log_entry(REDO_ENTRY);
+        }
+    }
+
+    /// Logs a command that has already been applied, without adding it to the history. This is for
+    /// changes that are made bit by bit and only become a command once they're finished, like a
+    /// brush stroke, so that a replay makes each bit at the same point in the simulation.
+    pub fn log_unrecorded(&mut self, command: &WorldCommand) {
+        if let Some(log) = self.start_log_entry(UNRECORDED_ENTRY) {
+            command.serialize(log);
+        }
+    }
+
+    /// Runs the simulation for a tick.
+    pub fn tick(&mut self, world: &mut World) {
+        world.tick();
+        self.unlogged_ticks += 1;
+    }
+
+    /// Generates a chunk for something other than a command or the simulation (such as the
+    /// camera moving), if it hasn't been generated already.
+    pub fn generate_chunk(&mut self, world: &mut World, chunk_pos: ChunkPos) {
+        if world.chunks.contains_key(&chunk_pos) {
+            return;
+        }
+        world.generate_chunk(chunk_pos);
+        if let Some(log) = self.start_log_entry(GENERATE_CHUNK_ENTRY) {
+            log.i32(chunk_pos.x);
+            log.i32(chunk_pos.y);
+        }
+    }
+
+    /// Everything that has been done through this history, for replaying later with `replay`, or
+    /// the reason that replaying it wouldn't recreate the world.
+    pub fn log(&mut self) -> Result<&[u8], LogProblem> {
+        if self.is_logging() {
+            self.log_unlogged_ticks();
+        }
+        match self.log_problem {
+            Some(problem) => Err(problem),
+            None => Ok(&self.log.data),
+        }
+    }
+
+    /// Writes the start of an entry to the log (after any ticks that haven't been logged yet), and
+    /// returns the log so that the rest of the entry can be written. Returns None if the log can't
+    /// be used any more.
+    fn start_log_entry(&mut self, entry: u8) -> Option<&mut Writer> {
+        if !self.is_logging() {
+            return None;
+        }
+        self.log_unlogged_ticks();
+        self.log.u8(entry);
+        Some(&mut self.log)
+    }
+
+    /// Whether things are still being logged, which stops once the log can't be used any more.
+    fn is_logging(&mut self) -> bool {
+        if self.log_problem.is_none() && self.log.data.len() >= MAX_LOG_BYTES {
+            self.log_problem = Some(LogProblem::Full);
+        }
+        self.log_problem.is_none()
+    }
+
+    fn log_unlogged_ticks(&mut self) {
+        if self.unlogged_ticks > 0 {
+            self.log.u8(TICKS_ENTRY);
+            self.log.u32(self.unlogged_ticks);
+            self.unlogged_ticks = 0;
+        }
+    }
+
+    /// Replays a log (from `log`) against a fresh world, returning the resulting world and history.
+    pub fn replay(log: &[u8]) -> Result<(World, CommandHistory), LoadError> {
+        let mut world = World::new();
+        let mut history = CommandHistory::default();
+        let mut reader = Reader::new(log);
+        // The edits logged bit by bit since the last recorded command.
+        let mut unrecorded_edits = Vec::new();
+
+        while !reader.is_finished() {
+            match reader.u8()? {
+                EXECUTE_ENTRY => {
+                    history.execute(WorldCommand::deserialize(&mut reader)?, &mut world);
+                }
+                UNDO_ENTRY => history.undo(&mut world),
+                REDO_ENTRY => history.redo(&mut world),
+                UNRECORDED_ENTRY => {
+                    let mut command = WorldCommand::deserialize(&mut reader)?;
+                    command.apply(&mut world);
+                    history.log_unrecorded(&command);
+                    if let WorldCommand::EditTiles(edits) = command {
+                        unrecorded_edits.extend(edits);
+                    }
+                }
+                RECORD_ENTRY => {
+                    let edits = std::mem::take(&mut unrecorded_edits);
+                    history.record(WorldCommand::EditTiles(edits));
+                }
+                TICKS_ENTRY => {
+                    for _ in 0..reader.u32()? {
+                        history.tick(&mut world);
+                    }
+                }
+                GENERATE_CHUNK_ENTRY => {
+                    let chunk_pos = ChunkPos {
+                        x: reader.i32()?,
+                        y: reader.i32()?,
+                    };
+                    history.generate_chunk(&mut world, chunk_pos);
+                }
+                id => return Err(LoadError::InvalidCommand(id)),
+            }
+        }
+
+        Ok((world, history))
+    }
+}
+
+/// Why a command log can't be used to recreate the world.
+#[derive(Copy, Clone, Debug)]
+pub enum LogProblem {
+    /// The log got too big, so the rest of the session wasn't logged.
+    Full,
+    /// The session started from a loaded world rather than a fresh one.
+    LoadedWorld,
+}
+
+impl Display for LogProblem {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            LogProblem::Full => write!(
+                f,
+                "the session went on for too long to be logged (the limit is {MAX_LOG_BYTES} bytes)"
+            ),
+            LogProblem::LoadedWorld => {
+                write!(
+                    f,
+                    "the session started from a loaded world, not a fresh one"
+                )
+            }
+        }
+    }
+}
+
+/// The maximum number of commands that can be undone.
+const MAX_HISTORY_LENGTH: usize = 100;
+/// The maximum number of regenerations that can be undone.
+const MAX_UNDOABLE_REGENERATIONS: usize = 3;
+/// The size that the command log stops growing at.
+const MAX_LOG_BYTES: usize = 16 * 1024 * 1024;
+
+// The IDs of the types of command in the save file format.
+const EDIT_TILES_ID: u8 = 0;
+const SPAWN_SETTLEMENT_ID: u8 = 1;
+const REGENERATE_ID: u8 = 2;
+
+// The IDs of the types of log entry in the save file format.
+const EXECUTE_ENTRY: u8 = 0;
+const UNDO_ENTRY: u8 = 1;
+const REDO_ENTRY: u8 = 2;
+const UNRECORDED_ENTRY: u8 = 3;
+const TICKS_ENTRY: u8 = 4;
+const GENERATE_CHUNK_ENTRY: u8 = 5;
+const RECORD_ENTRY: u8 = 6;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn replaying_a_log_recreates_the_world() {
+        let mut world = World::new();
+        let mut history = CommandHistory::default();
+        for x in -2..2 {
+            for y in -2..2 {
+                history.generate_chunk(&mut world, ChunkPos { x, y });
+            }
+        }
+        for _ in 0..200 {
+            history.tick(&mut world);
+        }
+        history.execute(
+            WorldCommand::SpawnSettlement {
+                position: TilePos { x: 5, y: -7 },
+                index: None,
+            },
+            &mut world,
+        );
+        let mut edit = WorldCommand::EditTiles(vec![TileEdit {
+            coords: TilePos { x: -3, y: 4 },
+            before: TileData {
+                tile: Tile::Grass,
+                elevation: 0.1,
+            },
+            after: TileData {
+                tile: Tile::Water,
+                elevation: -0.5,
+            },
+        }]);
+        edit.apply(&mut world);
+        history.log_unrecorded(&edit);
+        history.undo(&mut world);
+        for _ in 0..50 {
+            history.tick(&mut world);
+        }
+
+        let log = history
+            .log()
+            .expect("the log should be replayable")
+            .to_vec();
+        let (replayed_world, _) = CommandHistory::replay(&log).expect("the log should load");
+
+        assert!(replayed_world.serialize() == world.serialize());
+    }
+
+    #[test]
+    fn brush_strokes_are_logged_once() {
+        let mut world = World::new();
+        let mut history = CommandHistory::default();
+        history.generate_chunk(&mut world, ChunkPos::default());
+        let coords = TilePos { x: 3, y: 3 };
+        let original = world.tile_data(coords).expect("the chunk was generated");
+
+        // Two ticks' worth of a stroke over the same tile.
+        let mut before = original;
+        for elevation in [0.3, 0.6] {
+            let after = TileData {
+                elevation,
+                ..before
+            };
+            let mut bit = WorldCommand::EditTiles(vec![TileEdit {
+                coords,
+                before,
+                after,
+            }]);
+            bit.apply(&mut world);
+            history.log_unrecorded(&bit);
+            history.tick(&mut world);
+            before = after;
+        }
+        let logged_bits = history.log().expect("the log should be replayable").len();
+        history.record(WorldCommand::EditTiles(vec![TileEdit {
+            coords,
+            before: original,
+            after: before,
+        }]));
+
+        let log = history
+            .log()
+            .expect("the log should be replayable")
+            .to_vec();
+        // Only the marker that turns the bits into a command is added, not the edits again.
+        assert_eq!(log.len(), logged_bits + 1);
+
+        let (mut replayed_world, mut replayed_history) =
+            CommandHistory::replay(&log).expect("the log should load");
+        assert!(replayed_world.serialize() == world.serialize());
+
+        // The whole stroke is undone in one go.
+        history.undo(&mut world);
+        replayed_history.undo(&mut replayed_world);
+        assert!(world.tile_data(coords) == Some(original));
+        assert!(replayed_world.serialize() == world.serialize());
+    }
+
+    #[test]
+    fn only_the_latest_regenerations_can_be_undone() {
+        let mut world = World::new();
+        let mut history = CommandHistory::default();
+        history.execute(
+            WorldCommand::SpawnSettlement {
+                position: TilePos { x: 0, y: 0 },
+                index: None,
+            },
+            &mut world,
+        );
+        let params: Vec<_> = (0..=MAX_UNDOABLE_REGENERATIONS)
+            .map(|octaves| GeneratorParams {
+                octaves: octaves as i32 + 1,
+                ..GeneratorParams::default()
+            })
+            .collect();
+        for &params in &params {
+            history.execute(
+                WorldCommand::Regenerate {
+                    params,
+                    previous_world: None,
+                },
+                &mut world,
+            );
+        }
+
+        let kept_worlds = history
+            .undo_stack
+            .iter()
+            .filter(|command| {
+                matches!(
+                    command,
+                    WorldCommand::Regenerate {
+                        previous_world: Some(_),
+                        ..
+                    }
+                )
+            })
+            .count();
+        assert_eq!(kept_worlds, MAX_UNDOABLE_REGENERATIONS);
+        assert_eq!(history.undo_stack.len(), MAX_UNDOABLE_REGENERATIONS);
+
+        // Undoing everything goes back to the world from the oldest regeneration that was kept.
+        for _ in 0..=MAX_UNDOABLE_REGENERATIONS {
+            history.undo(&mut world);
+        }
+        assert!(*world.generator_params() == params[0]);
+        assert!(world.settlements.is_empty());
+    }
+
+    #[test]
+    fn a_loaded_world_cannot_be_replayed() {
+        let mut history = CommandHistory::for_loaded_world();
+
+        assert!(matches!(history.log(), Err(LogProblem::LoadedWorld)));
+    }
+}
@@ -1,17 +1,39 @@
 mod chunk;
+mod command;
 mod perlin_noise;
+mod save;
+mod settlement;
 mod tile;
 
-use crate::position::IntChunkCoordinates;
+use crate::position::{ChunkPos, TilePos};
+use crate::utils::Rng;
 pub use chunk::Chunk;
+pub use command::{CommandHistory, TileEdit, WorldCommand};
+pub use settlement::Settlement;
 use std::collections::HashMap;
-use std::default::Default;
 pub use tile::Tile;
 
 /// Represents the simulation world.
-#[derive(Default)]
 pub struct World {
-    pub chunks: HashMap<IntChunkCoordinates, Chunk>,
+    pub chunks: HashMap<ChunkPos, Chunk>,
+    pub settlements: Vec<Settlement>,
+    /// The settings used to generate new chunks.
+    generator_params: GeneratorParams,
+    /// The number of times the world has been ticked.
+    tick_count: u64,
+    rng: Rng,
+}
+
+impl Default for World {
+    fn default() -> Self {
+        World {
+            chunks: HashMap::default(),
+            settlements: Vec::default(),
+            generator_params: GeneratorParams::default(),
+            tick_count: 0,
+            rng: Rng::new(WORLD_RNG_SEED),
+        }
+    }
 }
 
 impl World {
@@ -22,15 +44,150 @@ impl World {
         }
     }
 
-    pub fn generate_chunk(&mut self, chunk_pos: IntChunkCoordinates) {
+    /// Creates an empty world that will generate its chunks with the given settings.
+    #[must_use]
+    pub fn with_generator_params(generator_params: GeneratorParams) -> Self {
+        World {
+            generator_params,
+            ..Default::default()
+        }
+    }
+
+    pub fn generator_params(&self) -> &GeneratorParams {
+        &self.generator_params
+    }
+
+    pub fn generate_chunk(&mut self, chunk_pos: ChunkPos) {
         // Don't do anything if the chunk is already generated.
         if self.chunks.contains_key(&chunk_pos) {
             return;
         }
 
-        let chunk = Chunk::generate(chunk_pos);
+        let chunk = Chunk::generate(chunk_pos, &self.generator_params);
         self.chunks.insert(chunk_pos, chunk);
     }
 
-    pub fn tick(&mut self) {}
+    /// The bottom left and top right corners of the smallest rectangle of chunks that contains
+    /// every generated chunk, or None if no chunks have been generated.
+    pub fn generated_bounds(&self) -> Option<[ChunkPos; 2]> {
+        let mut chunk_positions = self.chunks.keys();
+        let first = *chunk_positions.next()?;
+        Some(
+            chunk_positions.fold([first, first], |[bottom_left, top_right], pos| {
+                [
+                    ChunkPos {
+                        x: bottom_left.x.min(pos.x),
+                        y: bottom_left.y.min(pos.y),
+                    },
+                    ChunkPos {
+                        x: top_right.x.max(pos.x),
+                        y: top_right.y.max(pos.y),
+                    },
+                ]
+            }),
+        )
+    }
+
+    /// Returns the tile at the given coordinates, or None if its chunk hasn't been generated yet.
+    pub fn tile(&self, coords: TilePos) -> Option<Tile> {
+        let index = coords.index_in_chunk();
+        self.chunks
+            .get(&coords.chunk())
+            .map(|chunk| chunk.tiles[index.x][index.y])
+    }
+
+    /// Returns the elevation of the tile at the given coordinates, or None if its chunk hasn't been generated yet.
+    pub fn elevation(&self, coords: TilePos) -> Option<f32> {
+        let index = coords.index_in_chunk();
+        self.chunks
+            .get(&coords.chunk())
+            .map(|chunk| chunk.elevations[index.x][index.y])
+    }
+
+    /// Returns the hand-editable data of the tile at the given coordinates, or None if its chunk
+    /// hasn't been generated yet.
+    pub fn tile_data(&self, coords: TilePos) -> Option<TileData> {
+        Some(TileData {
+            tile: self.tile(coords)?,
+            elevation: self.elevation(coords)?,
+        })
+    }
+
+    /// Overwrites the hand-editable data of a tile. Returns false (and does nothing) if the tile's
+    /// chunk hasn't been generated yet.
+    pub fn set_tile_data(&mut self, coords: TilePos, data: TileData) -> bool {
+        let index = coords.index_in_chunk();
+        let Some(chunk) = self.chunks.get_mut(&coords.chunk()) else {
+            return false;
+        };
+
+        chunk.tiles[index.x][index.y] = data.tile;
+        chunk.elevations[index.x][index.y] = data.elevation;
+        chunk.mark_dirty();
+        true
+    }
+
+    /// Whether there is a road on the tile at the given coordinates.
+    pub fn has_road(&self, coords: TilePos) -> bool {
+        let index = coords.index_in_chunk();
+        self.chunks
+            .get(&coords.chunk())
+            .is_some_and(|chunk| chunk.roads[index.x][index.y])
+    }
+
+    /// The cost of walking onto the tile at the given coordinates, taking roads into account. Returns
+    /// None if the tile can't be walked on or hasn't been generated.
+    pub fn movement_cost(&self, coords: TilePos) -> Option<u32> {
+        let tile_cost = self.tile(coords)?.movement_cost()?;
+
+        if self.has_road(coords) {
+            Some(ROAD_MOVEMENT_COST)
+        } else {
+            Some(tile_cost)
+        }
+    }
+
+    pub fn tick(&mut self) {
+        self.tick_count += 1;
+        self.tick_settlements();
+    }
+
+    pub fn tick_count(&self) -> u64 {
+        self.tick_count
+    }
+}
+
+/// The parts of a tile that can be edited by hand.
+#[derive(Copy, Clone, PartialEq)]
+pub struct TileData {
+    pub tile: Tile,
+    pub elevation: f32,
+}
+
+/// Settings that control how the terrain is generated.
+#[derive(Copy, Clone, PartialEq)]
+pub struct GeneratorParams {
+    /// How stretched out the terrain is. Larger values make for bigger continents and oceans.
+    pub noise_scale: f64,
+    /// The number of octaves of noise. More octaves make for more detailed coastlines.
+    pub octaves: i32,
+    /// An amount added to every tile's elevation. Higher values mean less water.
+    pub elevation_offset: f32,
 }
+
+impl Default for GeneratorParams {
+    fn default() -> Self {
+        GeneratorParams {
+            noise_scale: 30.0,
+            octaves: 4,
+            // Adjust the noise up a bit to reduce the amount of water.
+            elevation_offset: 0.05,
+        }
+    }
+}
+
+/// The seed used for the world's random number generator.
+const WORLD_RNG_SEED: u64 = 0x5EED;
+/// The cost of walking along a road. This must be lower than the cost of any tile, or roads won't
+/// make travel any cheaper.
+const ROAD_MOVEMENT_COST: u32 = 1;
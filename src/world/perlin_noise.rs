@@ -19,6 +19,9 @@ use crate::utils::resolve_optional_val;
 /// # Returns
 ///
 /// A `f64` value representing the Perlin noise at the given point, approximately in the range of [-1, 1].
+// The argument descriptions are lined up with each other, which is easier to read than Clippy's
+// suggestion.
+#[expect(clippy::doc_overindented_list_items)]
 pub fn octaved_noise(x: f64, y: f64, z: f64, scale: Option<f64>, octaves: Option<i32>) -> f64 {
     let scale = resolve_optional_val(scale, 100.0);
     let octaves = resolve_optional_val(octaves, 4);
@@ -0,0 +1,319 @@
+//! Saving and loading the world to and from a simple binary format.
+//!
+//! All numbers are little-endian. The layout is:
+//! - The magic bytes `SIMW`, followed by the format version (u32)
+//! - The tick count (u64) and random number generator state (u64)
+//! - The generator parameters: noise scale (f64), octaves (i32) and elevation offset (f32)
+//! - The number of chunks (u32), then for each chunk its x and y coordinates (i32 each), its tile
+//!   IDs (one u8 per tile, column by column), its elevations (one f32 per tile), and its roads (one
+//!   u8 per tile, 0 or 1)
+//! - The number of settlements (u32), then for each settlement its x and y tile coordinates (i64
+//!   each), its population and capacity (u32 each), and its connections and failed connections
+//!   (each a u32 count followed by that many u32 indices)
+
+use crate::position::{ChunkPos, TilePos};
+use crate::utils::Rng;
+use crate::world::settlement::next_settlement_id;
+use crate::world::{Chunk, GeneratorParams, Settlement, Tile, World};
+use std::collections::HashMap;
+use std::fmt;
+use std::fmt::{Display, Formatter};
+
+impl World {
+    pub fn serialize(&self) -> Vec<u8> {
+        let mut writer = Writer::default();
+
+        writer.bytes(MAGIC);
+        writer.u32(FORMAT_VERSION);
+        writer.u64(self.tick_count);
+        writer.u64(self.rng.state());
+        writer.f64(self.generator_params.noise_scale);
+        writer.i32(self.generator_params.octaves);
+        writer.f32(self.generator_params.elevation_offset);
+
+        // Sort the chunks so that saving the same world always produces the same bytes.
+        let mut chunk_positions: Vec<_> = self.chunks.keys().copied().collect();
+        chunk_positions.sort_unstable_by_key(|pos| (pos.x, pos.y));
+
+        writer.u32(chunk_positions.len() as u32);
+        for chunk_pos in chunk_positions {
+            let chunk = &self.chunks[&chunk_pos];
+            writer.i32(chunk_pos.x);
+            writer.i32(chunk_pos.y);
+            for column in &chunk.tiles {
+                for tile in column {
+                    writer.u8(tile.id());
+                }
+            }
+            for column in &chunk.elevations {
+                for &elevation in column {
+                    writer.f32(elevation);
+                }
+            }
+            for column in &chunk.roads {
+                for &road in column {
+                    writer.u8(u8::from(road));
+                }
+            }
+        }
+
+        writer.u32(self.settlements.len() as u32);
+        for settlement in &self.settlements {
+            writer.i64(settlement.position.x);
+            writer.i64(settlement.position.y);
+            writer.u32(settlement.population);
+            writer.u32(settlement.capacity);
+            writer.indices(&settlement.connections);
+            writer.indices(&settlement.failed_connections);
+        }
+
+        writer.data
+    }
+
+    pub fn deserialize(data: &[u8]) -> Result<World, LoadError> {
+        let mut reader = Reader::new(data);
+
+        if reader.bytes(MAGIC.len())? != MAGIC {
+            return Err(LoadError::NotASaveFile);
+        }
+        let version = reader.u32()?;
+        if version != FORMAT_VERSION {
+            return Err(LoadError::UnsupportedVersion(version));
+        }
+
+        let tick_count = reader.u64()?;
+        let rng = Rng::new(reader.u64()?);
+        let generator_params = GeneratorParams {
+            noise_scale: reader.f64()?,
+            octaves: reader.i32()?,
+            elevation_offset: reader.f32()?,
+        };
+
+        let chunk_count = reader.u32()?;
+        let mut chunks = HashMap::new();
+        for _ in 0..chunk_count {
+            let chunk_pos = ChunkPos {
+                x: reader.i32()?,
+                y: reader.i32()?,
+            };
+            let mut chunk = Chunk::default();
+            for column in &mut chunk.tiles {
+                for tile in column {
+                    let id = reader.u8()?;
+                    *tile = Tile::from_id(id).ok_or(LoadError::InvalidTile(id))?;
+                }
+            }
+            for column in &mut chunk.elevations {
+                for elevation in column {
+                    *elevation = reader.f32()?;
+                }
+            }
+            for column in &mut chunk.roads {
+                for road in column {
+                    *road = reader.u8()? != 0;
+                }
+            }
+            chunks.insert(chunk_pos, chunk);
+        }
+
+        let settlement_count = reader.u32()?;
+        let mut settlements = Vec::new();
+        for _ in 0..settlement_count {
+            settlements.push(Settlement {
+                // IDs only last as long as the program is running, so loaded settlements get new ones.
+                id: next_settlement_id(),
+                position: TilePos {
+                    x: reader.i64()?,
+                    y: reader.i64()?,
+                },
+                population: reader.u32()?,
+                capacity: reader.u32()?,
+                connections: reader.indices()?,
+                failed_connections: reader.indices()?,
+            });
+        }
+
+        // Connections refer to other settlements by index, so they have to point at a settlement that
+        // was actually loaded.
+        for settlement in &settlements {
+            for &index in settlement
+                .connections
+                .iter()
+                .chain(&settlement.failed_connections)
+            {
+                if index >= settlements.len() {
+                    return Err(LoadError::InvalidSettlementIndex(index));
+                }
+            }
+        }
+
+        if !reader.is_finished() {
+            return Err(LoadError::TrailingData);
+        }
+
+        Ok(World {
+            chunks,
+            settlements,
+            generator_params,
+            tick_count,
+            rng,
+        })
+    }
+}
+
+#[derive(Debug)]
+pub enum LoadError {
+    /// The data doesn't start with the magic bytes.
+    NotASaveFile,
+    /// The save file was made by a different version of the program.
+    UnsupportedVersion(u32),
+    /// The data ended before the whole world was read.
+    UnexpectedEnd,
+    /// A tile had an ID that doesn't belong to any tile type.
+    InvalidTile(u8),
+    /// There was more data after the end of the world.
+    TrailingData,
+    /// A world command had a type that doesn't exist.
+    InvalidCommand(u8),
+    /// A settlement was connected to a settlement that doesn't exist.
+    InvalidSettlementIndex(usize),
+}
+
+impl Display for LoadError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            LoadError::NotASaveFile => write!(f, "the data isn't a save file"),
+            LoadError::UnsupportedVersion(version) => {
+                write!(
+                    f,
+                    "save format version {version} isn't supported (expected {FORMAT_VERSION})"
+                )
+            }
+            LoadError::UnexpectedEnd => write!(f, "the save file ended unexpectedly"),
+            LoadError::InvalidTile(id) => write!(f, "{id} isn't a valid tile ID"),
+            LoadError::TrailingData => {
+                write!(f, "there is extra data after the end of the save file")
+            }
+            LoadError::InvalidCommand(id) => write!(f, "{id} isn't a valid command type"),
+            LoadError::InvalidSettlementIndex(index) => {
+                write!(f, "there is no settlement number {index}")
+            }
+        }
+    }
+}
+
+/// Builds up binary data in the save file format.
+#[derive(Default)]
+pub(super) struct Writer {
+    pub data: Vec<u8>,
+}
+
+impl Writer {
+    pub fn bytes(&mut self, bytes: &[u8]) {
+        self.data.extend_from_slice(bytes);
+    }
+
+    pub fn u8(&mut self, value: u8) {
+        self.data.push(value);
+    }
+
+    pub fn u32(&mut self, value: u32) {
+        self.bytes(&value.to_le_bytes());
+    }
+
+    pub fn i32(&mut self, value: i32) {
+        self.bytes(&value.to_le_bytes());
+    }
+
+    pub fn i64(&mut self, value: i64) {
+        self.bytes(&value.to_le_bytes());
+    }
+
+    pub fn u64(&mut self, value: u64) {
+        self.bytes(&value.to_le_bytes());
+    }
+
+    pub fn f32(&mut self, value: f32) {
+        self.bytes(&value.to_le_bytes());
+    }
+
+    pub fn f64(&mut self, value: f64) {
+        self.bytes(&value.to_le_bytes());
+    }
+
+    pub fn indices(&mut self, indices: &[usize]) {
+        self.u32(indices.len() as u32);
+        for &index in indices {
+            self.u32(index as u32);
+        }
+    }
+}
+
+/// Reads binary data in the save file format.
+pub(super) struct Reader<'a> {
+    data: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> Reader<'a> {
+    pub fn new(data: &'a [u8]) -> Self {
+        Reader { data, offset: 0 }
+    }
+
+    /// Whether all of the data has been read.
+    pub fn is_finished(&self) -> bool {
+        self.offset == self.data.len()
+    }
+
+    pub fn bytes(&mut self, count: usize) -> Result<&[u8], LoadError> {
+        let bytes = self
+            .data
+            .get(self.offset..self.offset + count)
+            .ok_or(LoadError::UnexpectedEnd)?;
+        self.offset += count;
+        Ok(bytes)
+    }
+
+    pub fn array<const N: usize>(&mut self) -> Result<[u8; N], LoadError> {
+        let mut array = [0; N];
+        array.copy_from_slice(self.bytes(N)?);
+        Ok(array)
+    }
+
+    pub fn u8(&mut self) -> Result<u8, LoadError> {
+        Ok(self.array::<1>()?[0])
+    }
+
+    pub fn u32(&mut self) -> Result<u32, LoadError> {
+        Ok(u32::from_le_bytes(self.array()?))
+    }
+
+    pub fn i32(&mut self) -> Result<i32, LoadError> {
+        Ok(i32::from_le_bytes(self.array()?))
+    }
+
+    pub fn i64(&mut self) -> Result<i64, LoadError> {
+        Ok(i64::from_le_bytes(self.array()?))
+    }
+
+    pub fn u64(&mut self) -> Result<u64, LoadError> {
+        Ok(u64::from_le_bytes(self.array()?))
+    }
+
+    pub fn f32(&mut self) -> Result<f32, LoadError> {
+        Ok(f32::from_le_bytes(self.array()?))
+    }
+
+    pub fn f64(&mut self) -> Result<f64, LoadError> {
+        Ok(f64::from_le_bytes(self.array()?))
+    }
+
+    pub fn indices(&mut self) -> Result<Vec<usize>, LoadError> {
+        let count = self.u32()?;
+        (0..count).map(|_| Ok(self.u32()? as usize)).collect()
+    }
+}
+
+const MAGIC: &[u8] = b"SIMW";
+/// Increment this whenever the format changes.
+const FORMAT_VERSION: u32 = 4;
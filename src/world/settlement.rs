@@ -0,0 +1,379 @@
+use crate::pathfinding::{find_path, SearchConfig, UnknownChunkPolicy};
+use crate::position::{TileIndex, TilePos};
+use crate::world::{Chunk, Tile, World};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// A town or city. Settlements are founded on dry land near water, grow towards the number of people
+/// their surroundings can support, and build roads to their neighbours as they get bigger.
+pub struct Settlement {
+    /// Identifies the settlement for as long as it exists. Unlike its index in `World.settlements`,
+    /// this doesn't change when other settlements are removed, and it isn't reused by any other
+    /// settlement (even in another world).
+    pub id: u64,
+    /// The tile at the center of the settlement.
+    pub position: TilePos,
+    pub population: u32,
+    /// The largest population the land around the settlement can support.
+    pub capacity: u32,
+    /// The indices (in `World.settlements`) of the settlements this one has a road to.
+    pub connections: Vec<usize>,
+    /// The indices of the settlements we tried and failed to build a road to. These won't be retried.
+    pub failed_connections: Vec<usize>,
+}
+
+impl Settlement {
+    /// The number of roads the settlement wants, based on its population.
+    fn desired_connections(&self) -> usize {
+        (self.population / POPULATION_PER_ROAD) as usize
+    }
+
+    fn has_tried_connecting_to(&self, other: usize) -> bool {
+        self.connections.contains(&other) || self.failed_connections.contains(&other)
+    }
+}
+
+impl World {
+    pub(super) fn tick_settlements(&mut self) {
+        if self.tick_count.is_multiple_of(FOUNDING_INTERVAL_TICKS) {
+            self.try_found_settlement();
+        }
+        if self.tick_count.is_multiple_of(GROWTH_INTERVAL_TICKS) {
+            self.grow_settlements();
+        }
+        if self.tick_count.is_multiple_of(ROAD_INTERVAL_TICKS) {
+            self.try_build_road();
+        }
+    }
+
+    /// Picks a random tile in the generated part of the world and founds a settlement on it if it's suitable.
+    fn try_found_settlement(&mut self) {
+        // Sort the chunks so that the choice only depends on the random number generator (and not
+        // on the iteration order of the hash map).
+        let mut chunk_positions: Vec<_> = self.chunks.keys().copied().collect();
+        if chunk_positions.is_empty() {
+            return;
+        }
+        chunk_positions.sort_unstable_by_key(|pos| (pos.x, pos.y));
+
+        let chunk_pos = chunk_positions[self.rng.below(chunk_positions.len() as u32) as usize];
+        let site = chunk_pos.tile(TileIndex {
+            x: self.rng.below(Chunk::side_size() as u32) as usize,
+            y: self.rng.below(Chunk::side_size() as u32) as usize,
+        });
+
+        if !self.is_suitable_settlement_site(site) {
+            return;
+        }
+
+        self.spawn_settlement(site);
+    }
+
+    /// Founds a new settlement at the given tile, regardless of whether it's a suitable site.
+    /// Returns the index of the new settlement.
+    pub fn spawn_settlement(&mut self, position: TilePos) -> usize {
+        self.settlements.push(Settlement {
+            id: next_settlement_id(),
+            position,
+            population: INITIAL_POPULATION,
+            capacity: self.carrying_capacity(position),
+            connections: vec![],
+            failed_connections: vec![],
+        });
+
+        self.settlements.len() - 1
+    }
+
+    /// Returns the settlement with the given ID, or None if it doesn't exist (any more).
+    pub fn settlement(&self, id: u64) -> Option<&Settlement> {
+        self.settlements
+            .iter()
+            .find(|settlement| settlement.id == id)
+    }
+
+    /// Removes a settlement, updating the connections of the others to match. Any roads it built
+    /// are left in place.
+    pub fn remove_settlement(&mut self, index: usize) {
+        self.settlements.remove(index);
+
+        let fix_indices = |indices: &mut Vec<usize>| {
+            indices.retain(|&other| other != index);
+            for other in indices {
+                if *other > index {
+                    *other -= 1;
+                }
+            }
+        };
+        for settlement in &mut self.settlements {
+            fix_indices(&mut settlement.connections);
+            fix_indices(&mut settlement.failed_connections);
+        }
+    }
+
+    fn is_suitable_settlement_site(&self, site: TilePos) -> bool {
+        if !self.tile(site).is_some_and(Tile::is_settleable) {
+            return false;
+        }
+
+        let too_close_to_neighbour = self.settlements.iter().any(|settlement| {
+            settlement.position.x.abs_diff(site.x) < MIN_SETTLEMENT_SPACING
+                && settlement.position.y.abs_diff(site.y) < MIN_SETTLEMENT_SPACING
+        });
+        if too_close_to_neighbour {
+            return false;
+        }
+
+        tiles_in_radius(site, WATER_SEARCH_RADIUS)
+            .any(|coords| self.tile(coords) == Some(Tile::Water))
+    }
+
+    /// Each settleable tile around a site can support a few people.
+    fn carrying_capacity(&self, site: TilePos) -> u32 {
+        let settleable_tiles = tiles_in_radius(site, CAPACITY_SEARCH_RADIUS)
+            .filter(|&coords| self.tile(coords).is_some_and(Tile::is_settleable))
+            .count() as u32;
+
+        INITIAL_POPULATION + settleable_tiles * POPULATION_PER_TILE
+    }
+
+    /// Grows every settlement logistically towards its carrying capacity.
+    fn grow_settlements(&mut self) {
+        for settlement in &mut self.settlements {
+            if settlement.population >= settlement.capacity {
+                continue;
+            }
+
+            let population = f64::from(settlement.population);
+            let remaining_room = 1.0 - population / f64::from(settlement.capacity);
+            let growth = (population * GROWTH_RATE * remaining_room).ceil() as u32;
+
+            settlement.population = (settlement.population + growth).min(settlement.capacity);
+        }
+    }
+
+    /// Finds the first settlement that wants another road and connects it to its nearest
+    /// unconnected neighbour along the cheapest path. Only one road is attempted per call to keep
+    /// the cost of a tick down.
+    fn try_build_road(&mut self) {
+        let Some((from, to)) = self.next_road_to_build() else {
+            return;
+        };
+
+        let config = SearchConfig {
+            // Roads to places nobody has seen yet would be a bit odd.
+            unknown_chunks: UnknownChunkPolicy::Blocked,
+            node_budget: ROAD_SEARCH_NODE_BUDGET,
+        };
+        let start = self.settlements[from].position;
+        let goal = self.settlements[to].position;
+
+        if let Ok(path) = find_path(self, start, goal, &config) {
+            for coords in path.tiles {
+                let index = coords.index_in_chunk();
+                if let Some(chunk) = self.chunks.get_mut(&coords.chunk()) {
+                    chunk.roads[index.x][index.y] = true;
+                    chunk.mark_dirty();
+                }
+            }
+            self.settlements[from].connections.push(to);
+            self.settlements[to].connections.push(from);
+        } else {
+            self.settlements[from].failed_connections.push(to);
+            self.settlements[to].failed_connections.push(from);
+        }
+    }
+
+    /// Returns the indices of a settlement that wants another road and the nearest settlement it
+    /// could build one to.
+    fn next_road_to_build(&self) -> Option<(usize, usize)> {
+        self.settlements
+            .iter()
+            .enumerate()
+            .filter(|(_, settlement)| {
+                settlement.connections.len() < settlement.desired_connections()
+            })
+            .find_map(|(index, settlement)| {
+                self.settlements
+                    .iter()
+                    .enumerate()
+                    .filter(|&(other_index, other)| {
+                        other_index != index
+                            && !settlement.has_tried_connecting_to(other_index)
+                            && chebyshev_distance(settlement.position, other.position)
+                                <= MAX_ROAD_LENGTH
+                    })
+                    .min_by_key(|(_, other)| {
+                        chebyshev_distance(settlement.position, other.position)
+                    })
+                    .map(|(other_index, _)| (index, other_index))
+            })
+    }
+}
+
+fn chebyshev_distance(a: TilePos, b: TilePos) -> u64 {
+    a.x.abs_diff(b.x).max(a.y.abs_diff(b.y))
+}
+
+/// Iterates over every tile in the square with the given radius around a center tile.
+/// The square stops at the edge of the world.
+fn tiles_in_radius(center: TilePos, radius: i64) -> impl Iterator<Item = TilePos> {
+    let offset = TilePos {
+        x: radius,
+        y: radius,
+    };
+    TilePos::rect(center - offset, center + offset)
+}
+
+pub(super) fn next_settlement_id() -> u64 {
+    static NEXT_SETTLEMENT_ID: AtomicU64 = AtomicU64::new(0);
+    NEXT_SETTLEMENT_ID.fetch_add(1, Ordering::Relaxed)
+}
+
+/// How often (in ticks) we try to found a new settlement.
+const FOUNDING_INTERVAL_TICKS: u64 = 30;
+/// How often (in ticks) settlements grow.
+const GROWTH_INTERVAL_TICKS: u64 = 60;
+/// How often (in ticks) we try to build a road.
+const ROAD_INTERVAL_TICKS: u64 = 20;
+
+const INITIAL_POPULATION: u32 = 10;
+/// The fraction that a settlement's population grows by each growth step when it's far from its capacity.
+const GROWTH_RATE: f64 = 0.1;
+/// The number of people each settleable tile around a settlement can support.
+const POPULATION_PER_TILE: u32 = 4;
+/// Each time a settlement gains this many people, it wants another road.
+const POPULATION_PER_ROAD: u32 = 60;
+
+/// The minimum distance (in tiles, on each axis) between two settlements.
+const MIN_SETTLEMENT_SPACING: u64 = 24;
+/// How close to water (in tiles) a settlement needs to be.
+const WATER_SEARCH_RADIUS: i64 = 3;
+/// The radius (in tiles) of land that a settlement can farm.
+const CAPACITY_SEARCH_RADIUS: i64 = 6;
+/// The longest road (in tiles, on the longer axis) that a settlement will try to build.
+const MAX_ROAD_LENGTH: u64 = 120;
+const ROAD_SEARCH_NODE_BUDGET: usize = 20_000;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::position::ChunkPos;
+    use crate::world::TileData;
+
+    /// Creates a single chunk of grass, with water on the given tiles.
+    fn grass_world(water: &[TilePos]) -> World {
+        let mut world = World::new();
+        world.generate_chunk(ChunkPos::default());
+        for coords in TilePos::rect(TilePos { x: 0, y: 0 }, TilePos { x: 31, y: 31 }) {
+            let tile = if water.contains(&coords) {
+                Tile::Water
+            } else {
+                Tile::Grass
+            };
+            world.set_tile_data(
+                coords,
+                TileData {
+                    tile,
+                    elevation: 0.0,
+                },
+            );
+        }
+        world
+    }
+
+    #[test]
+    fn settlements_grow_until_they_reach_their_capacity() {
+        let mut world = grass_world(&[]);
+        let index = world.spawn_settlement(TilePos { x: 16, y: 16 });
+        let capacity = world.settlements[index].capacity;
+        assert!(capacity > INITIAL_POPULATION);
+
+        let mut population = world.settlements[index].population;
+        while population < capacity {
+            world.grow_settlements();
+            let grown = world.settlements[index].population;
+            assert!(
+                grown > population,
+                "The settlement stopped growing at {population}"
+            );
+            population = grown;
+        }
+
+        world.grow_settlements();
+        assert_eq!(world.settlements[index].population, capacity);
+    }
+
+    #[test]
+    fn big_settlements_build_roads_to_their_neighbours() {
+        let mut world = grass_world(&[]);
+        let from = world.spawn_settlement(TilePos { x: 5, y: 5 });
+        let to = world.spawn_settlement(TilePos { x: 15, y: 5 });
+        world.settlements[from].population = POPULATION_PER_ROAD;
+
+        world.try_build_road();
+
+        assert_eq!(world.settlements[from].connections, [to]);
+        assert_eq!(world.settlements[to].connections, [from]);
+        assert!((5..=15).all(|x| world.has_road(TilePos { x, y: 5 })));
+        assert!(!world.has_road(TilePos { x: 5, y: 6 }));
+
+        // The settlement has all the roads it wants now.
+        assert_eq!(world.next_road_to_build(), None);
+    }
+
+    #[test]
+    fn roads_that_cant_be_built_are_not_retried() {
+        let river: Vec<_> = (0..32).map(|y| TilePos { x: 10, y }).collect();
+        let mut world = grass_world(&river);
+        let from = world.spawn_settlement(TilePos { x: 5, y: 5 });
+        let to = world.spawn_settlement(TilePos { x: 15, y: 5 });
+        world.settlements[from].population = POPULATION_PER_ROAD;
+
+        world.try_build_road();
+
+        assert!(world.settlements[from].connections.is_empty());
+        assert_eq!(world.settlements[from].failed_connections, [to]);
+        assert_eq!(world.settlements[to].failed_connections, [from]);
+        assert!(!world.has_road(TilePos { x: 5, y: 5 }));
+        assert_eq!(world.next_road_to_build(), None);
+    }
+
+    #[test]
+    fn removing_a_settlement_fixes_up_the_others() {
+        let mut world = grass_world(&[]);
+        let first = world.spawn_settlement(TilePos { x: 2, y: 2 });
+        let removed = world.spawn_settlement(TilePos { x: 12, y: 2 });
+        let last = world.spawn_settlement(TilePos { x: 22, y: 2 });
+        world.settlements[first].connections = vec![last];
+        world.settlements[first].failed_connections = vec![removed];
+        world.settlements[removed].connections = vec![last];
+        world.settlements[last].connections = vec![first, removed];
+        let removed_id = world.settlements[removed].id;
+        let last_id = world.settlements[last].id;
+
+        world.remove_settlement(removed);
+
+        assert_eq!(world.settlements.len(), 2);
+        assert!(world.settlement(removed_id).is_none());
+        let last_settlement = world
+            .settlement(last_id)
+            .expect("The other settlements should keep their IDs");
+        assert_eq!(last_settlement.position, TilePos { x: 22, y: 2 });
+        assert_eq!(last_settlement.connections, [0]);
+        assert_eq!(world.settlements[0].connections, [1]);
+        assert!(world.settlements[0].failed_connections.is_empty());
+    }
+
+    #[test]
+    fn tiles_in_radius_stop_at_the_edge_of_the_world() {
+        let center = TilePos { x: 0, y: 0 };
+        assert_eq!(tiles_in_radius(center, 3).count(), 49);
+
+        let corner = TilePos {
+            x: i64::MAX,
+            y: i64::MIN,
+        };
+        let tiles: Vec<_> = tiles_in_radius(corner, 3).collect();
+        assert_eq!(tiles.len(), 16);
+        assert!(tiles.contains(&corner));
+    }
+}
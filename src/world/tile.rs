@@ -1,4 +1,4 @@
-#[derive(Default)]
+#[derive(Default, Copy, Clone, Debug, PartialEq, Eq)]
 pub enum Tile {
     #[default]
     Black,
@@ -10,7 +10,7 @@ pub enum Tile {
 
 /// A world tile. Tiles are rendered centered on their position.
 impl Tile {
-    pub fn color(&self) -> [u8; 3] {
+    pub fn color(self) -> [u8; 3] {
         match self {
             Tile::Grass => [51, 127, 51],
             Tile::Beach => [255, 204, 0],
@@ -20,6 +20,56 @@ impl Tile {
         }
     }
 
+    /// The natural tile type for a given elevation.
+    pub fn from_elevation(elevation: f32) -> Self {
+        match elevation {
+            h if h < 0.0 => Tile::Water,
+            h if h < 0.04 => Tile::Beach,
+            h if h < 0.3 => Tile::Grass,
+            _ => Tile::Mountain,
+        }
+    }
+
+    /// A unique number for each tile type, used when saving the world.
+    pub fn id(self) -> u8 {
+        match self {
+            Tile::Black => 0,
+            Tile::Grass => 1,
+            Tile::Beach => 2,
+            Tile::Water => 3,
+            Tile::Mountain => 4,
+        }
+    }
+
+    /// The inverse of `Tile::id`.
+    pub fn from_id(id: u8) -> Option<Self> {
+        match id {
+            0 => Some(Tile::Black),
+            1 => Some(Tile::Grass),
+            2 => Some(Tile::Beach),
+            3 => Some(Tile::Water),
+            4 => Some(Tile::Mountain),
+            _ => None,
+        }
+    }
+
+    /// The cost of walking onto this tile, or None if it can't be walked on at all. Every tile costs
+    /// more than walking along a road (which costs 1), so that a road always makes the tiles it's
+    /// built on quicker to cross.
+    pub fn movement_cost(self) -> Option<u32> {
+        match self {
+            Tile::Beach => Some(2),
+            Tile::Grass => Some(3),
+            Tile::Mountain => Some(12),
+            Tile::Water | Tile::Black => None,
+        }
+    }
+
+    /// Whether a settlement can be founded on this tile.
+    pub fn is_settleable(self) -> bool {
+        matches!(self, Tile::Grass | Tile::Beach)
+    }
+
     /// The size of a side of a tile in pixels at 1x zoom.
     pub const fn width_px() -> i32 {
         128